@@ -0,0 +1,158 @@
+#ifndef TIME_CALC_H
+#define TIME_CALC_H
+
+/* Generated by cbindgen from src/main/rust/ffi.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The status of a call to the C API.
+typedef enum TimeCalcStatus {
+  // The call succeeded.
+  TIME_CALC_STATUS_OK = 0,
+  // A required pointer was null.
+  TIME_CALC_STATUS_NULL_POINTER = 1,
+  // A string was not valid UTF-8.
+  TIME_CALC_STATUS_INVALID_UTF8 = 2,
+  // The buffer was too small to hold the string.
+  TIME_CALC_STATUS_BUFFER_TOO_SMALL = 3,
+  // The expression could not be evaluated. The error message was written to the buffer.
+  TIME_CALC_STATUS_EVAL_ERROR = 4,
+  // The time format was not valid.
+  TIME_CALC_STATUS_INVALID_FORMAT = 5,
+} TimeCalcStatus;
+
+// An amount of elapsed time.
+//
+// Times are represented as a number of seconds, plus a nanosecond offset. The number of
+// nanoseconds is always positive, which means that negative numbers are represented as one less
+// than the whole number of seconds. For example, -1.2 seconds is represented as
+// -2 seconds - .8 seconds (800,000,000 nanoseconds).
+//
+// Given this representation, the minimum and maximum times that can be represented are
+// +/- 2,562,047,788,015,215:30:7.999999999 (2^63 seconds + 999,999,999 nanoseconds).
+typedef struct TimeCalcTime TimeCalcTime;
+
+// Evaluates expressions containing times and numbers, keeping the output of the most recent
+// expression so that it can be retrieved again.
+typedef struct TimeCalcEvaluator TimeCalcEvaluator;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a new evaluator, with the default options and an empty history.
+struct TimeCalcEvaluator *time_calc_evaluator_new(void);
+
+// Frees an evaluator. Does nothing if `evaluator` is null.
+//
+// # Safety
+//
+// `evaluator` must be null or have been returned by `time_calc_evaluator_new`, and must not be used
+// afterwards.
+void time_calc_evaluator_free(struct TimeCalcEvaluator *evaluator);
+
+// Evaluates an expression, writing the result to `buffer` and recording it in the history of the
+// evaluator.
+//
+// If the expression cannot be evaluated, the error message is written to `buffer` and `EvalError`
+// is returned. If `buffer` is too small, the expression is still evaluated, and the result or
+// error message can be retrieved with `time_calc_evaluator_output`.
+//
+// # Safety
+//
+// `evaluator` must have been returned by `time_calc_evaluator_new`. `expression` must be a
+// NUL-terminated string. `buffer` must be valid for writes of `buffer_len` bytes. `length` must
+// be null or valid for writes.
+enum TimeCalcStatus time_calc_evaluator_eval(struct TimeCalcEvaluator *evaluator,
+                                             const char *expression,
+                                             char *buffer,
+                                             size_t buffer_len,
+                                             size_t *length);
+
+// Writes the result or error message of the most recently evaluated expression to `buffer`,
+// without evaluating it again. Returns the status which `time_calc_evaluator_eval` would have
+// returned with a large enough buffer, or `BufferTooSmall`. Before an expression is evaluated, the
+// output is an empty string.
+//
+// # Safety
+//
+// `evaluator` must have been returned by `time_calc_evaluator_new`. `buffer` must be valid for
+// writes of `buffer_len` bytes. `length` must be null or valid for writes.
+enum TimeCalcStatus time_calc_evaluator_output(const struct TimeCalcEvaluator *evaluator,
+                                               char *buffer,
+                                               size_t buffer_len,
+                                               size_t *length);
+
+// Returns a new time with a whole number of `seconds`, rounded toward negative infinity, plus a
+// positive offset of `nanoseconds`. For example, -1.2 seconds is -2 seconds plus 800,000,000
+// nanoseconds. Returns null if `nanoseconds` is not less than one second or the time is out of
+// range.
+struct TimeCalcTime *time_calc_time_new(int64_t seconds, uint32_t nanoseconds);
+
+// Parses a time such as `1:30:00`, `1h30m` or `PT1H30M`. Returns null if the string is not a
+// valid time.
+//
+// # Safety
+//
+// `s` must be a NUL-terminated string.
+struct TimeCalcTime *time_calc_time_parse(const char *s);
+
+// Returns the whole number of seconds of a time, rounded toward negative infinity.
+//
+// # Safety
+//
+// `time` must be a valid time.
+int64_t time_calc_time_seconds(const struct TimeCalcTime *time);
+
+// Returns the positive nanoseconds offset of a time, which is added to the seconds.
+//
+// # Safety
+//
+// `time` must be a valid time.
+uint32_t time_calc_time_nanoseconds(const struct TimeCalcTime *time);
+
+// Returns a new time which is the sum of two times. Returns null if either time is null or the
+// result overflows.
+//
+// # Safety
+//
+// `a` and `b` must be null or valid times.
+struct TimeCalcTime *time_calc_time_add(const struct TimeCalcTime *a, const struct TimeCalcTime *b);
+
+// Returns a new time which is the difference of two times. Returns null if either time is null or
+// the result overflows.
+//
+// # Safety
+//
+// `a` and `b` must be null or valid times.
+struct TimeCalcTime *time_calc_time_sub(const struct TimeCalcTime *a, const struct TimeCalcTime *b);
+
+// Formats a time, writing it to `buffer`. `format` is a format name such as `hh:mm:ss` or
+// `hours.2`, as used by the `--format` option, or null for the default format.
+//
+// # Safety
+//
+// `time` must be a valid time. `format` must be null or a NUL-terminated string. `buffer` must
+// be valid for writes of `buffer_len` bytes. `length` must be null or valid for writes.
+enum TimeCalcStatus time_calc_time_format(const struct TimeCalcTime *time,
+                                          const char *format,
+                                          char *buffer,
+                                          size_t buffer_len,
+                                          size_t *length);
+
+// Frees a time. Does nothing if `time` is null.
+//
+// # Safety
+//
+// `time` must be null or have been returned by this library, and must not be used afterwards.
+void time_calc_time_free(struct TimeCalcTime *time);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* TIME_CALC_H */
@@ -0,0 +1,411 @@
+//! Parsing of command line arguments.
+use std::fmt;
+use std::path::PathBuf;
+
+use time_calc::Evaluator;
+use time_calc::TimeFormat;
+use time_calc::TimeStyle;
+use time_calc::TimeUnit;
+
+use crate::parse_overflow_policy;
+use crate::parse_rounding_strategy;
+
+pub const USAGE: &str = "\
+Usage: time-calc [OPTIONS] [--] [EXPRESSION]...
+       time-calc [OPTIONS] -e EXPRESSION [-e EXPRESSION]...
+       time-calc [OPTIONS] --file PATH
+       time-calc [OPTIONS] --interactive | --batch | --serve
+
+Evaluates calculations on times and numbers, such as `1:30:00 + 45:00`. The words of EXPRESSION are
+joined with spaces and evaluated. With no expression, expressions are read from stdin, one per line,
+with a prompt and line editing if stdin is a terminal.
+
+Modes:
+  -e, --expr EXPRESSION    Evaluate EXPRESSION. Can be given more than once to evaluate several
+                           expressions in order.
+  -f, --file PATH          Evaluate each line of the file at PATH.
+  -i, --interactive        Read expressions from stdin with a prompt, line editing and history.
+  -b, --batch              Read expressions from stdin without a prompt.
+      --serve              Serve JSON-RPC 2.0 requests on stdin.
+  -h, --help               Print this help and exit.
+  -V, --version            Print the version and exit.
+
+Options:
+      --overflow POLICY    Handle a time which overflows with `checked` (the default),
+                           `saturating` or `wrapping`.
+      --precision UNIT     Round times to a multiple of UNIT: d, h, m, s, ms, us or ns.
+      --places N           Round numbers to N decimal places.
+      --rounding STRATEGY  Round with half-up (the default), half-down, half-even, up, down,
+                           floor or ceiling.
+      --format FORMAT      Write times in FORMAT: default, h:mm:ss, hh:mm:ss, days, seconds,
+                           hours, units or iso8601, optionally followed by `.` and a number of
+                           fractional digits.
+      --days               Same as --format days.
+      --iso8601            Same as --format iso8601.
+      --default-unit UNIT  Treat a number added to or subtracted from a time as a number of UNIT.
+      --implicit-multiplication
+                           Multiply values which are written next to each other.
+      --json               Write results and errors as JSON objects.
+
+An option's value can also be given as --option=VALUE. An argument starting with `-` and followed
+by something other than a letter, such as `-5s`, starts the expression. Use `--` before an
+expression which starts with a variable, such as `-lap1`.
+
+Exit status is 0 on success, 1 if the result of an expression is `false`, and 2 on an error.
+";
+
+/// What the command line arguments ask the program to do.
+pub enum Command {
+    /// Print the usage and exit.
+    Help,
+    /// Print the version and exit.
+    Version,
+    /// Evaluate expressions with the given configuration.
+    Run(Config),
+}
+
+/// The configuration of the calculator, and where expressions come from.
+pub struct Config {
+    pub evaluator: Evaluator,
+    pub time_format: TimeFormat,
+    pub json_output: bool,
+    /// The mode, or `None` if no mode was given and no expression was provided.
+    pub mode: Option<Mode>,
+}
+
+/// Where expressions are read from, and how results are written.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Evaluate expressions from the command line, in order.
+    Expressions(Vec<String>),
+    /// Evaluate each line of a file.
+    File(PathBuf),
+    /// Read expressions typed by a person on stdin.
+    Interactive,
+    /// Read expressions from stdin.
+    Batch,
+    /// Serve JSON-RPC requests on stdin.
+    Serve,
+}
+
+impl Mode {
+    fn describe(&self) -> &'static str {
+        match self {
+            Mode::Expressions(_) => "an expression",
+            Mode::File(_) => "--file",
+            Mode::Interactive => "--interactive",
+            Mode::Batch => "--batch",
+            Mode::Serve => "--serve",
+        }
+    }
+}
+
+/// An error in the command line arguments.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgError {
+    UnknownOption(String),
+    MissingValue(String),
+    UnexpectedValue(String),
+    InvalidValue(String, String),
+    ConflictingModes(&'static str, &'static str),
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgError::UnknownOption(option) => write!(f, "unknown option '{}'", option),
+            ArgError::MissingValue(option) => write!(f, "option '{}' requires a value", option),
+            ArgError::UnexpectedValue(option) => {
+                write!(f, "option '{}' does not take a value", option)
+            }
+            ArgError::InvalidValue(option, value) => {
+                write!(f, "invalid value '{}' for option '{}'", value, option)
+            }
+            ArgError::ConflictingModes(first, second) => {
+                write!(f, "{} cannot be used with {}", second, first)
+            }
+        }
+    }
+}
+
+/// Parses command line arguments, not including the program name.
+///
+/// Options are read until the first argument which is not an option, which starts the expression.
+/// All remaining arguments are part of the expression, even if they look like options.
+pub fn parse_args<I>(args: I) -> Result<Command, ArgError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut config = Config {
+        evaluator: Evaluator::new(),
+        time_format: TimeFormat::default(),
+        json_output: false,
+        mode: Option::None,
+    };
+    let mut expressions = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Option::Some(arg) = args.next() {
+        if arg == "--" {
+            expressions.push(args.by_ref().collect::<Vec<_>>().join(" "));
+            break;
+        }
+        if !is_option(&arg) {
+            expressions.push(
+                std::iter::once(arg)
+                    .chain(args.by_ref())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+            break;
+        }
+
+        // Split `--option=value` into the option and its value.
+        let (option, mut inline_value) = match arg.find('=') {
+            Option::Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Option::Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg, Option::None),
+        };
+        let mut value = || take_value(&option, &mut inline_value, &mut args);
+
+        match option.as_str() {
+            "-h" | "--help" => return Result::Ok(Command::Help),
+            "-V" | "--version" => return Result::Ok(Command::Version),
+            "-e" | "--expr" => expressions.push(value()?),
+            "-f" | "--file" => set_mode(&mut config.mode, Mode::File(PathBuf::from(value()?)))?,
+            "-i" | "--interactive" => set_mode(&mut config.mode, Mode::Interactive)?,
+            "-b" | "--batch" => set_mode(&mut config.mode, Mode::Batch)?,
+            "--serve" => set_mode(&mut config.mode, Mode::Serve)?,
+            "--overflow" => {
+                let policy = value()?;
+                match parse_overflow_policy(&policy) {
+                    Option::Some(policy) => config.evaluator.overflow_policy(policy),
+                    Option::None => return Result::Err(ArgError::InvalidValue(option, policy)),
+                };
+            }
+            "--precision" => {
+                let unit = value()?;
+                match TimeUnit::from_suffix(&unit) {
+                    Option::Some(unit) => config.evaluator.time_precision(unit),
+                    Option::None => return Result::Err(ArgError::InvalidValue(option, unit)),
+                };
+            }
+            "--places" => {
+                let places = value()?;
+                match places.parse() {
+                    Result::Ok(places) => config.evaluator.number_precision(places),
+                    Result::Err(_) => return Result::Err(ArgError::InvalidValue(option, places)),
+                };
+            }
+            "--rounding" => {
+                let strategy = value()?;
+                match parse_rounding_strategy(&strategy) {
+                    Option::Some(strategy) => config.evaluator.rounding_strategy(strategy),
+                    Option::None => return Result::Err(ArgError::InvalidValue(option, strategy)),
+                };
+            }
+            "--format" => {
+                let format = value()?;
+                match format.parse() {
+                    Result::Ok(format) => config.time_format = format,
+                    Result::Err(_) => return Result::Err(ArgError::InvalidValue(option, format)),
+                };
+            }
+            "--default-unit" => {
+                let unit = value()?;
+                match TimeUnit::from_name(&unit) {
+                    Option::Some(unit) => config.evaluator.default_unit(Option::Some(unit)),
+                    Option::None => return Result::Err(ArgError::InvalidValue(option, unit)),
+                };
+            }
+            "--days" => {
+                config.time_format.style(TimeStyle::Days);
+            }
+            "--iso8601" => {
+                config.time_format.style(TimeStyle::Iso8601);
+            }
+            "--implicit-multiplication" => {
+                config.evaluator.implicit_multiplication(true);
+            }
+            "--json" => config.json_output = true,
+            _ => return Result::Err(ArgError::UnknownOption(option)),
+        }
+
+        if inline_value.is_some() {
+            return Result::Err(ArgError::UnexpectedValue(option));
+        }
+    }
+
+    if !expressions.is_empty() {
+        set_mode(&mut config.mode, Mode::Expressions(expressions))?;
+    }
+    Result::Ok(Command::Run(config))
+}
+
+/// Returns whether an argument is an option rather than the start of an expression. An argument
+/// is an option if it starts with `-` followed by a letter, or with `--`. Other arguments which
+/// start with `-`, such as `-5s` or `-(1h)`, are expressions.
+fn is_option(arg: &str) -> bool {
+    let mut chars = arg.chars();
+    chars.next() == Option::Some('-')
+        && matches!(chars.next(), Option::Some(c) if c == '-' || c.is_ascii_alphabetic())
+}
+
+/// Returns the value of an option, which is either given inline as `--option=value` or is the next
+/// argument.
+fn take_value<I>(
+    option: &str,
+    inline_value: &mut Option<String>,
+    args: &mut I,
+) -> Result<String, ArgError>
+where
+    I: Iterator<Item = String>,
+{
+    inline_value
+        .take()
+        .or_else(|| args.next())
+        .ok_or_else(|| ArgError::MissingValue(option.to_string()))
+}
+
+fn set_mode(mode: &mut Option<Mode>, new_mode: Mode) -> Result<(), ArgError> {
+    match mode {
+        Option::Some(mode) => Result::Err(ArgError::ConflictingModes(
+            mode.describe(),
+            new_mode.describe(),
+        )),
+        Option::None => {
+            *mode = Option::Some(new_mode);
+            Result::Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use std::path::PathBuf;
+
+    use time_calc::EvalResult;
+    use time_calc::Time;
+    use time_calc::TimeFormat;
+    use time_calc::TimeStyle;
+
+    use crate::args::parse_args;
+    use crate::args::ArgError;
+    use crate::args::Command;
+    use crate::args::Config;
+    use crate::args::Mode;
+
+    #[test]
+    fn parse_args_expression() {
+        assert_eq!(mode(&[]), Option::None);
+        assert_eq!(mode(&["1:30:00", "+", "45:00"]), Option::Some(Mode::Expressions(vec!["1:30:00 + 45:00".to_string()])));
+        assert_eq!(mode(&["1:30:00 + 45:00"]), Option::Some(Mode::Expressions(vec!["1:30:00 + 45:00".to_string()])));
+        assert_eq!(mode(&["-e", "1h", "--expr", "2h", "--expr=3h"]), Option::Some(Mode::Expressions(vec!["1h".to_string(), "2h".to_string(), "3h".to_string()])));
+        assert_eq!(mode(&["-e", "1h", "2h", "+", "1"]), Option::Some(Mode::Expressions(vec!["1h".to_string(), "2h + 1".to_string()])));
+        assert_eq!(mode(&["-e", "--json"]), Option::Some(Mode::Expressions(vec!["--json".to_string()])));
+    }
+
+    #[test]
+    fn parse_args_expression_starting_with_dash() {
+        assert_eq!(mode(&["-5s", "+", "1m"]), Option::Some(Mode::Expressions(vec!["-5s + 1m".to_string()])));
+        assert_eq!(mode(&["-(1h)"]), Option::Some(Mode::Expressions(vec!["-(1h)".to_string()])));
+        assert_eq!(mode(&["-.5"]), Option::Some(Mode::Expressions(vec!["-.5".to_string()])));
+        assert_eq!(mode(&["--", "-x", "+", "1"]), Option::Some(Mode::Expressions(vec!["-x + 1".to_string()])));
+        assert_eq!(mode(&["--json", "--", "--json"]), Option::Some(Mode::Expressions(vec!["--json".to_string()])));
+
+        // Options after the start of the expression are part of the expression.
+        assert_eq!(mode(&["3", "--json"]), Option::Some(Mode::Expressions(vec!["3 --json".to_string()])));
+        assert_eq!(mode(&["3", "-2"]), Option::Some(Mode::Expressions(vec!["3 -2".to_string()])));
+    }
+
+    #[test]
+    fn parse_args_modes() {
+        assert_eq!(mode(&["--file", "times.txt"]), Option::Some(Mode::File(PathBuf::from("times.txt"))));
+        assert_eq!(mode(&["-f", "times.txt"]), Option::Some(Mode::File(PathBuf::from("times.txt"))));
+        assert_eq!(mode(&["--file=times.txt"]), Option::Some(Mode::File(PathBuf::from("times.txt"))));
+        assert_eq!(mode(&["--interactive"]), Option::Some(Mode::Interactive));
+        assert_eq!(mode(&["-i"]), Option::Some(Mode::Interactive));
+        assert_eq!(mode(&["--batch"]), Option::Some(Mode::Batch));
+        assert_eq!(mode(&["-b"]), Option::Some(Mode::Batch));
+        assert_eq!(mode(&["--serve"]), Option::Some(Mode::Serve));
+    }
+
+    #[test]
+    fn parse_args_help_and_version() {
+        assert!(matches!(parse(&["--help"]), Result::Ok(Command::Help)));
+        assert!(matches!(parse(&["-h"]), Result::Ok(Command::Help)));
+        assert!(matches!(parse(&["--json", "-h", "1h"]), Result::Ok(Command::Help)));
+        assert!(matches!(parse(&["--version"]), Result::Ok(Command::Version)));
+        assert!(matches!(parse(&["-V"]), Result::Ok(Command::Version)));
+        assert!(matches!(parse(&["1h", "--help"]), Result::Ok(Command::Run(_))));
+    }
+
+    #[test]
+    fn parse_args_options() {
+        let json_days = config(&["--json", "--days"]);
+        assert!(json_days.json_output);
+        assert_eq!(json_days.time_format, TimeFormat::new(TimeStyle::Days));
+        assert_eq!(json_days.mode, Option::None);
+
+        assert_eq!(config(&["--iso8601"]).time_format, TimeFormat::new(TimeStyle::Iso8601));
+        assert_eq!(config(&["--format", "hours.2"]).time_format, "hours.2".parse().unwrap());
+        assert_eq!(config(&["--format=hh:mm:ss"]).time_format, "hh:mm:ss".parse().unwrap());
+
+        assert_eq!(eval(&["--places", "2"], "1 / 3"), EvalResult::Number("0.33".parse().unwrap()));
+        assert_eq!(eval(&["--places=2", "--rounding=up"], "1 / 3"), EvalResult::Number("0.34".parse().unwrap()));
+        assert_eq!(eval(&["--precision", "m"], "01:30"), EvalResult::Time(Time::builder().minutes(2).build()));
+        assert_eq!(eval(&["--overflow=saturating"], "2562047788015215:30:07 + 1s"), EvalResult::Time(Time::MAX));
+        assert_eq!(eval(&["--default-unit", "minutes"], "1h + 30"), EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+        assert_eq!(eval(&["--implicit-multiplication"], "2(3)"), EvalResult::Number(6.into()));
+    }
+
+    #[test]
+    fn parse_args_errors() {
+        assert_eq!(error(&["--frobnicate"]), ArgError::UnknownOption("--frobnicate".to_string()));
+        assert_eq!(error(&["-x"]), ArgError::UnknownOption("-x".to_string()));
+        assert_eq!(error(&["-lap1"]), ArgError::UnknownOption("-lap1".to_string()));
+        assert_eq!(error(&["--format"]), ArgError::MissingValue("--format".to_string()));
+        assert_eq!(error(&["-e"]), ArgError::MissingValue("-e".to_string()));
+        assert_eq!(error(&["--json=yes"]), ArgError::UnexpectedValue("--json".to_string()));
+        assert_eq!(error(&["--format=fancy"]), ArgError::InvalidValue("--format".to_string(), "fancy".to_string()));
+        assert_eq!(error(&["--overflow", "sometimes"]), ArgError::InvalidValue("--overflow".to_string(), "sometimes".to_string()));
+        assert_eq!(error(&["--places=-1"]), ArgError::InvalidValue("--places".to_string(), "-1".to_string()));
+        assert_eq!(error(&["--precision=fortnight"]), ArgError::InvalidValue("--precision".to_string(), "fortnight".to_string()));
+        assert_eq!(error(&["--rounding=sideways"]), ArgError::InvalidValue("--rounding".to_string(), "sideways".to_string()));
+        assert_eq!(error(&["--default-unit=parsec"]), ArgError::InvalidValue("--default-unit".to_string(), "parsec".to_string()));
+        assert_eq!(error(&["--serve", "1h"]), ArgError::ConflictingModes("--serve", "an expression"));
+        assert_eq!(error(&["--interactive", "--batch"]), ArgError::ConflictingModes("--interactive", "--batch"));
+        assert_eq!(error(&["--file=a", "-e", "1h"]), ArgError::ConflictingModes("--file", "an expression"));
+
+        assert_eq!(ArgError::ConflictingModes("--serve", "an expression").to_string(), "an expression cannot be used with --serve");
+        assert_eq!(ArgError::InvalidValue("--format".to_string(), "fancy".to_string()).to_string(), "invalid value 'fancy' for option '--format'");
+    }
+
+    fn parse(args: &[&str]) -> Result<Command, ArgError> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn config(args: &[&str]) -> Config {
+        match parse(args) {
+            Result::Ok(Command::Run(config)) => config,
+            _ => panic!("expected a configuration for {:?}", args),
+        }
+    }
+
+    fn mode(args: &[&str]) -> Option<Mode> {
+        config(args).mode
+    }
+
+    fn eval(args: &[&str], expression: &str) -> EvalResult {
+        config(args).evaluator.eval(expression).unwrap()
+    }
+
+    fn error(args: &[&str]) -> ArgError {
+        match parse(args) {
+            Result::Err(error) => error,
+            _ => panic!("expected an error for {:?}", args),
+        }
+    }
+}
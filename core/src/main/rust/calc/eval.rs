@@ -1,21 +1,42 @@
-use crate::calc::parse::parse_expression;
+use crate::calc::parse::parse_statement;
 use crate::calc::parse::BinaryOp;
+use crate::calc::parse::ComparisonOp;
+use crate::calc::parse::Conversion;
 use crate::calc::parse::Expr;
 use crate::calc::parse::Literal;
 use crate::calc::parse::ParseError;
+use crate::calc::parse::Stmt;
 use crate::calc::parse::UnaryOp;
+use crate::span::Span;
+use crate::time::format::TimeFormat;
 use crate::time::Time;
+use crate::time::TimeUnit;
 
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
+use rust_decimal::MathematicalOps;
 use rust_decimal::RoundingStrategy;
 use rust_decimal_macros::dec;
 
 impl Expr {
     fn accept<T>(&self, visitor: &dyn ExprVisitor<Result = T>) -> T {
         match *self {
-            Expr::Literal(_) => visitor.visit_literal(self),
-            Expr::Unary(_, _) => visitor.visit_unary(self),
-            Expr::Binary(_, _, _) => visitor.visit_binary(self),
+            Expr::Literal(_, _) => visitor.visit_literal(self),
+            Expr::Unary(_, _, _) => visitor.visit_unary(self),
+            Expr::Binary(_, _, _, _) => visitor.visit_binary(self),
+            Expr::Comparison(_, _, _, _) => visitor.visit_comparison(self),
+            Expr::Conditional(_, _, _, _) => visitor.visit_conditional(self),
+            Expr::Call(_, _, _) => visitor.visit_call(self),
+            Expr::Variable(_, _) => visitor.visit_variable(self),
+            Expr::Answer(_) | Expr::History(_, _) => visitor.visit_history(self),
+            Expr::Convert(_, _, _) => visitor.visit_convert(self),
         }
     }
 }
@@ -24,13 +45,27 @@ trait ExprVisitor {
     type Result;
     fn visit_literal(&self, expr: &Expr) -> Self::Result;
     fn visit_binary(&self, expr: &Expr) -> Self::Result;
+    fn visit_comparison(&self, expr: &Expr) -> Self::Result;
+    fn visit_conditional(&self, expr: &Expr) -> Self::Result;
     fn visit_unary(&self, expr: &Expr) -> Self::Result;
+    fn visit_call(&self, expr: &Expr) -> Self::Result;
+    fn visit_variable(&self, expr: &Expr) -> Self::Result;
+    fn visit_history(&self, expr: &Expr) -> Self::Result;
+    fn visit_convert(&self, expr: &Expr) -> Self::Result;
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) enum EvalResult {
+/// The result of evaluating an expression.
+///
+/// A `FormattedTime` is the result of converting a time to a format, such as `90m to iso8601`, and
+/// is used as a plain time if it is an operand of another expression. A `Bool` is the result of a
+/// comparison, such as `90m > 1h`, and cannot be an operand of another expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EvalResult {
     Time(Time),
     Number(Decimal),
+    FormattedTime(Time, TimeFormat),
+    Bool(bool),
 }
 
 impl std::fmt::Display for EvalResult {
@@ -38,20 +73,209 @@ impl std::fmt::Display for EvalResult {
         match self {
             EvalResult::Time(t) => write!(f, "{}", t),
             EvalResult::Number(n) => write!(f, "{}", n),
+            EvalResult::FormattedTime(t, format) => write!(f, "{}", t.format(format)),
+            EvalResult::Bool(b) => write!(f, "{}", b),
         }
     }
 }
 
-struct ExprEvaluator;
+struct ExprEvaluator<'a> {
+    overflow_policy: OverflowPolicy,
+    default_unit: Option<TimeUnit>,
+    variables: &'a HashMap<String, EvalResult>,
+    history: &'a [EvalResult],
+}
+
+/// Determines how overflow is handled in time arithmetic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Overflow results in an `EvalError::Overflow`.
+    Checked,
+    /// Overflow results in `Time::MAX` or `Time::MIN`.
+    Saturating,
+    /// Overflow wraps around at the boundary of the time range.
+    Wrapping,
+}
 
+/// An error which occurred while evaluating an expression.
 #[derive(Debug)]
-pub(crate) enum EvalError {
+#[non_exhaustive]
+pub enum EvalError {
     ParseError(ParseError),
-    MultiplyTimes,
-    AddTimeAndNumber,
-    SubtractTimeAndNumber,
-    DivideNumberByTime,
-    DivideByZero,
+    MultiplyTimes(Span),
+    AddTimeAndNumber(Span),
+    SubtractTimeAndNumber(Span),
+    DivideNumberByTime(Span),
+    IntegerDivideTimeAndNumber(Span),
+    RemainderTimeAndNumber(Span),
+    DivideByZero(Span),
+    Overflow(Span),
+    UnknownFunction(String, Span),
+    UndefinedVariable(String, Span),
+    NoPreviousResult(Span),
+    UndefinedResult(usize, Span),
+    ArgumentCount(String, usize, Span),
+    MixedArguments(String, Span),
+    RoundTimeToPlaces(Span),
+    RoundNumberToTime(Span),
+    InvalidDecimalPlaces(Span),
+    ZeroIncrement(Span),
+    ConvertNumber(Span),
+    CompareTimeAndNumber(Span),
+    BoolOperand(Span),
+    ConditionNotBool(Span),
+    BranchTypes(Span),
+    PowerOfTime(Span),
+    TimeExponent(Span),
+    NegativeFractionalPower(Span),
+    SqrtTime(Span),
+    SqrtNegative(Span),
+}
+
+impl EvalError {
+    /// Returns the location of the error in the expression.
+    pub fn span(&self) -> Span {
+        match self {
+            EvalError::ParseError(error) => error.span(),
+            EvalError::MultiplyTimes(span)
+            | EvalError::AddTimeAndNumber(span)
+            | EvalError::SubtractTimeAndNumber(span)
+            | EvalError::DivideNumberByTime(span)
+            | EvalError::IntegerDivideTimeAndNumber(span)
+            | EvalError::RemainderTimeAndNumber(span)
+            | EvalError::DivideByZero(span)
+            | EvalError::Overflow(span)
+            | EvalError::UnknownFunction(_, span)
+            | EvalError::UndefinedVariable(_, span)
+            | EvalError::NoPreviousResult(span)
+            | EvalError::UndefinedResult(_, span)
+            | EvalError::ArgumentCount(_, _, span)
+            | EvalError::MixedArguments(_, span)
+            | EvalError::RoundTimeToPlaces(span)
+            | EvalError::RoundNumberToTime(span)
+            | EvalError::InvalidDecimalPlaces(span)
+            | EvalError::ZeroIncrement(span)
+            | EvalError::ConvertNumber(span)
+            | EvalError::CompareTimeAndNumber(span)
+            | EvalError::BoolOperand(span)
+            | EvalError::ConditionNotBool(span)
+            | EvalError::BranchTypes(span)
+            | EvalError::PowerOfTime(span)
+            | EvalError::TimeExponent(span)
+            | EvalError::NegativeFractionalPower(span)
+            | EvalError::SqrtTime(span)
+            | EvalError::SqrtNegative(span) => *span,
+        }
+    }
+
+    /// Returns an identifier for the kind of error, such as `divide_by_zero`. The kind of a parse
+    /// error is the kind of the underlying `ParseError`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EvalError::ParseError(error) => error.kind(),
+            EvalError::MultiplyTimes(_) => "multiply_times",
+            EvalError::AddTimeAndNumber(_) => "add_time_and_number",
+            EvalError::SubtractTimeAndNumber(_) => "subtract_time_and_number",
+            EvalError::DivideNumberByTime(_) => "divide_number_by_time",
+            EvalError::IntegerDivideTimeAndNumber(_) => "integer_divide_time_and_number",
+            EvalError::RemainderTimeAndNumber(_) => "remainder_time_and_number",
+            EvalError::DivideByZero(_) => "divide_by_zero",
+            EvalError::Overflow(_) => "overflow",
+            EvalError::UnknownFunction(_, _) => "unknown_function",
+            EvalError::UndefinedVariable(_, _) => "undefined_variable",
+            EvalError::NoPreviousResult(_) => "no_previous_result",
+            EvalError::UndefinedResult(_, _) => "undefined_result",
+            EvalError::ArgumentCount(_, _, _) => "argument_count",
+            EvalError::MixedArguments(_, _) => "mixed_arguments",
+            EvalError::RoundTimeToPlaces(_) => "round_time_to_places",
+            EvalError::RoundNumberToTime(_) => "round_number_to_time",
+            EvalError::InvalidDecimalPlaces(_) => "invalid_decimal_places",
+            EvalError::ZeroIncrement(_) => "zero_increment",
+            EvalError::ConvertNumber(_) => "convert_number",
+            EvalError::CompareTimeAndNumber(_) => "compare_time_and_number",
+            EvalError::BoolOperand(_) => "bool_operand",
+            EvalError::ConditionNotBool(_) => "condition_not_bool",
+            EvalError::BranchTypes(_) => "branch_types",
+            EvalError::PowerOfTime(_) => "power_of_time",
+            EvalError::TimeExponent(_) => "time_exponent",
+            EvalError::NegativeFractionalPower(_) => "negative_fractional_power",
+            EvalError::SqrtTime(_) => "sqrt_time",
+            EvalError::SqrtNegative(_) => "sqrt_negative",
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::ParseError(error) => write!(f, "{}", error),
+            EvalError::MultiplyTimes(_) => write!(f, "cannot multiply a time by a time"),
+            EvalError::AddTimeAndNumber(_) => write!(f, "cannot add a time and a number"),
+            EvalError::SubtractTimeAndNumber(_) => {
+                write!(f, "cannot subtract a time and a number")
+            }
+            EvalError::DivideNumberByTime(_) => write!(f, "cannot divide a number by a time"),
+            EvalError::IntegerDivideTimeAndNumber(_) => {
+                write!(f, "cannot integer divide a time and a number")
+            }
+            EvalError::RemainderTimeAndNumber(_) => {
+                write!(f, "cannot take the remainder of a time and a number")
+            }
+            EvalError::DivideByZero(_) => write!(f, "division by zero"),
+            EvalError::Overflow(_) => write!(f, "result is too large"),
+            EvalError::UnknownFunction(name, _) => write!(f, "unknown function '{}'", name),
+            EvalError::UndefinedVariable(name, _) => write!(f, "undefined variable '{}'", name),
+            EvalError::NoPreviousResult(_) => write!(f, "there is no previous result"),
+            EvalError::UndefinedResult(n, _) => write!(f, "there is no result ${}", n),
+            EvalError::ArgumentCount(name, count, _) => {
+                write!(
+                    f,
+                    "wrong number of arguments to '{}', found {}",
+                    name, count
+                )
+            }
+            EvalError::MixedArguments(name, _) => {
+                write!(f, "cannot mix times and numbers in arguments to '{}'", name)
+            }
+            EvalError::RoundTimeToPlaces(_) => {
+                write!(f, "cannot round a time to a number of decimal places")
+            }
+            EvalError::RoundNumberToTime(_) => {
+                write!(f, "cannot round a number to a multiple of a time")
+            }
+            EvalError::InvalidDecimalPlaces(_) => {
+                write!(f, "decimal places must be a whole number from 0 to 28")
+            }
+            EvalError::ZeroIncrement(_) => write!(f, "rounding increment must not be zero"),
+            EvalError::ConvertNumber(_) => write!(f, "cannot convert a number to a unit or format"),
+            EvalError::CompareTimeAndNumber(_) => write!(f, "cannot compare a time and a number"),
+            EvalError::BoolOperand(_) => write!(f, "cannot use a boolean as an operand"),
+            EvalError::ConditionNotBool(_) => write!(f, "condition must be true or false"),
+            EvalError::BranchTypes(_) => {
+                write!(f, "both branches of a conditional must have the same type")
+            }
+            EvalError::PowerOfTime(_) => write!(f, "cannot raise a time to a power"),
+            EvalError::TimeExponent(_) => {
+                write!(f, "cannot raise a number to the power of a time")
+            }
+            EvalError::NegativeFractionalPower(_) => {
+                write!(f, "cannot raise a negative number to a fractional power")
+            }
+            EvalError::SqrtTime(_) => write!(f, "cannot take the square root of a time"),
+            EvalError::SqrtNegative(_) => {
+                write!(f, "cannot take the square root of a negative number")
+            }
+        }
+    }
+}
+
+impl error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            EvalError::ParseError(error) => Option::Some(error),
+            _ => Option::None,
+        }
+    }
 }
 
 impl std::convert::From<ParseError> for EvalError {
@@ -60,83 +284,281 @@ impl std::convert::From<ParseError> for EvalError {
     }
 }
 
-impl ExprVisitor for ExprEvaluator {
+impl ExprVisitor for ExprEvaluator<'_> {
     type Result = Result<EvalResult, EvalError>;
 
     fn visit_literal(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
         match expr {
-            Expr::Literal(Literal::Time(t)) => Result::Ok(EvalResult::Time(*t)),
-            Expr::Literal(Literal::Number(n)) => Result::Ok(EvalResult::Number(*n)),
+            Expr::Literal(Literal::Time(t), _) => Result::Ok(EvalResult::Time(*t)),
+            Expr::Literal(Literal::Number(n), _) => Result::Ok(EvalResult::Number(*n)),
+            Expr::Literal(Literal::Percentage(p), _) => {
+                Result::Ok(EvalResult::Number(*p / dec!(100)))
+            }
             _ => panic!(),
         }
     }
 
     fn visit_binary(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
         match expr {
-            Expr::Binary(left, op, right) => {
-                let r1 = left.accept(self)?;
-                let r2 = right.accept(self)?;
+            Expr::Binary(left, op, right, span) => {
+                // Adding or subtracting a percentage, such as `t + 20%`, changes the left operand
+                // by that percentage of itself.
+                if let (BinaryOp::Add, Expr::Literal(Literal::Percentage(p), _))
+                | (BinaryOp::Subtract, Expr::Literal(Literal::Percentage(p), _)) =
+                    (op, right.as_ref())
+                {
+                    let factor = match op {
+                        BinaryOp::Add => dec!(1) + *p / dec!(100),
+                        _ => dec!(1) - *p / dec!(100),
+                    };
+                    return self.scale(left, factor, *span);
+                }
+
+                let r1 = self.operand(left)?;
+                let r2 = self.operand(right)?;
+                let (r1, r2) = self.coerce_numbers(op, r1, r2, left.span(), right.span())?;
 
                 match (r1, r2) {
                     (EvalResult::Number(n1), EvalResult::Number(n2)) => match op {
-                        BinaryOp::Add => Result::Ok(EvalResult::Number(round_decimal(n1 + n2))),
-                        BinaryOp::Subtract => {
-                            Result::Ok(EvalResult::Number(round_decimal(n1 - n2)))
+                        BinaryOp::Add => number_result(n1.checked_add(n2), *span),
+                        BinaryOp::Subtract => number_result(n1.checked_sub(n2), *span),
+                        BinaryOp::Multiply => number_result(n1.checked_mul(n2), *span),
+                        BinaryOp::Divide => {
+                            if n2 == dec!(0) {
+                                Result::Err(EvalError::DivideByZero(right.span()))
+                            } else {
+                                number_result(n1.checked_div(n2), *span)
+                            }
                         }
-                        BinaryOp::Multiply => {
-                            Result::Ok(EvalResult::Number(round_decimal(n1 * n2)))
+                        BinaryOp::IntegerDivide => {
+                            if n2 == dec!(0) {
+                                Result::Err(EvalError::DivideByZero(right.span()))
+                            } else {
+                                number_result(div_floor(n1, n2), *span)
+                            }
                         }
-                        BinaryOp::Divide => {
+                        BinaryOp::Remainder => {
                             if n2 == dec!(0) {
-                                Result::Err(EvalError::DivideByZero)
+                                Result::Err(EvalError::DivideByZero(right.span()))
+                            } else {
+                                number_result(rem_floor(n1, n2), *span)
+                            }
+                        }
+                        BinaryOp::Power => {
+                            if n1 == dec!(0) && n2 < dec!(0) {
+                                Result::Err(EvalError::DivideByZero(*span))
+                            } else if n1 < dec!(0) && n2.fract() != dec!(0) {
+                                Result::Err(EvalError::NegativeFractionalPower(*span))
                             } else {
-                                Result::Ok(EvalResult::Number(round_decimal(n1 / n2)))
+                                // Powers are written without the trailing zeros which repeated
+                                // multiplication adds.
+                                let power = pow(n1, n2).map(|p| round_decimal(p).normalize());
+                                number_result(power, *span)
                             }
                         }
                     },
                     (EvalResult::Time(t1), EvalResult::Time(t2)) => match op {
-                        BinaryOp::Add => Result::Ok(EvalResult::Time(t1 + t2)),
-                        BinaryOp::Subtract => Result::Ok(EvalResult::Time(t1 - t2)),
+                        BinaryOp::Add => self.time_op(
+                            *span,
+                            t1,
+                            t2,
+                            Time::checked_add,
+                            Time::saturating_add,
+                            Time::wrapping_add,
+                        ),
+                        BinaryOp::Subtract => self.time_op(
+                            *span,
+                            t1,
+                            t2,
+                            Time::checked_sub,
+                            Time::saturating_sub,
+                            Time::wrapping_sub,
+                        ),
                         BinaryOp::Divide => {
                             if t2 == Time::builder().build() {
-                                Result::Err(EvalError::DivideByZero)
+                                Result::Err(EvalError::DivideByZero(right.span()))
                             } else {
                                 Result::Ok(EvalResult::Number(round_decimal(t1 / t2)))
                             }
                         }
-                        BinaryOp::Multiply => Result::Err(EvalError::MultiplyTimes),
+                        BinaryOp::IntegerDivide => match t1.checked_div_floor(t2) {
+                            Option::Some(n) => Result::Ok(EvalResult::Number(n)),
+                            Option::None => Result::Err(EvalError::DivideByZero(right.span())),
+                        },
+                        BinaryOp::Remainder => match t1.checked_rem(t2) {
+                            Option::Some(t) => Result::Ok(EvalResult::Time(t)),
+                            Option::None => Result::Err(EvalError::DivideByZero(right.span())),
+                        },
+                        BinaryOp::Multiply => Result::Err(EvalError::MultiplyTimes(*span)),
+                        BinaryOp::Power => Result::Err(EvalError::PowerOfTime(*span)),
                     },
                     (EvalResult::Time(t), EvalResult::Number(n)) => match op {
-                        BinaryOp::Multiply => Result::Ok(EvalResult::Time(t * n)),
+                        BinaryOp::Multiply => self.time_op(
+                            *span,
+                            t,
+                            n,
+                            Time::checked_mul,
+                            Time::saturating_mul,
+                            Time::wrapping_mul,
+                        ),
                         BinaryOp::Divide => {
                             if n == dec!(0) {
-                                Result::Err(EvalError::DivideByZero)
+                                Result::Err(EvalError::DivideByZero(right.span()))
                             } else {
-                                Result::Ok(EvalResult::Time(t / n))
+                                self.time_op(
+                                    *span,
+                                    t,
+                                    n,
+                                    Time::checked_div,
+                                    Time::saturating_div,
+                                    Time::wrapping_div,
+                                )
                             }
                         }
-                        BinaryOp::Add => Result::Err(EvalError::AddTimeAndNumber),
-                        BinaryOp::Subtract => Result::Err(EvalError::SubtractTimeAndNumber),
+                        BinaryOp::Add => Result::Err(EvalError::AddTimeAndNumber(*span)),
+                        BinaryOp::Subtract => Result::Err(EvalError::SubtractTimeAndNumber(*span)),
+                        BinaryOp::IntegerDivide => {
+                            Result::Err(EvalError::IntegerDivideTimeAndNumber(*span))
+                        }
+                        BinaryOp::Remainder => {
+                            Result::Err(EvalError::RemainderTimeAndNumber(*span))
+                        }
+                        BinaryOp::Power => Result::Err(EvalError::PowerOfTime(*span)),
                     },
                     (EvalResult::Number(n), EvalResult::Time(t)) => match op {
-                        BinaryOp::Multiply => Result::Ok(EvalResult::Time(n * t)),
-                        BinaryOp::Add => Result::Err(EvalError::AddTimeAndNumber),
-                        BinaryOp::Subtract => Result::Err(EvalError::SubtractTimeAndNumber),
-                        BinaryOp::Divide => Result::Err(EvalError::DivideNumberByTime),
+                        BinaryOp::Multiply => self.time_op(
+                            *span,
+                            t,
+                            n,
+                            Time::checked_mul,
+                            Time::saturating_mul,
+                            Time::wrapping_mul,
+                        ),
+                        BinaryOp::Add => Result::Err(EvalError::AddTimeAndNumber(*span)),
+                        BinaryOp::Subtract => Result::Err(EvalError::SubtractTimeAndNumber(*span)),
+                        BinaryOp::Divide => Result::Err(EvalError::DivideNumberByTime(*span)),
+                        BinaryOp::IntegerDivide => {
+                            Result::Err(EvalError::IntegerDivideTimeAndNumber(*span))
+                        }
+                        BinaryOp::Remainder => {
+                            Result::Err(EvalError::RemainderTimeAndNumber(*span))
+                        }
+                        BinaryOp::Power => Result::Err(EvalError::TimeExponent(*span)),
                     },
+                    _ => unreachable!(),
                 }
             }
             _ => panic!(),
         }
     }
 
+    fn visit_comparison(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        match expr {
+            Expr::Comparison(left, op, right, span) => {
+                let ordering = match (self.operand(left)?, self.operand(right)?) {
+                    (EvalResult::Number(n1), EvalResult::Number(n2)) => n1.cmp(&n2),
+                    (EvalResult::Time(t1), EvalResult::Time(t2)) => t1.cmp(&t2),
+                    _ => return Result::Err(EvalError::CompareTimeAndNumber(*span)),
+                };
+                let result = match op {
+                    ComparisonOp::Less => ordering == Ordering::Less,
+                    ComparisonOp::LessEqual => ordering != Ordering::Greater,
+                    ComparisonOp::Equal => ordering == Ordering::Equal,
+                    ComparisonOp::NotEqual => ordering != Ordering::Equal,
+                    ComparisonOp::Greater => ordering == Ordering::Greater,
+                    ComparisonOp::GreaterEqual => ordering != Ordering::Less,
+                };
+                Result::Ok(EvalResult::Bool(result))
+            }
+            _ => panic!(),
+        }
+    }
+
+    fn visit_conditional(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        match expr {
+            Expr::Conditional(condition, then_expr, else_expr, span) => {
+                self.conditional(condition, then_expr, else_expr, *span)
+            }
+            _ => panic!(),
+        }
+    }
+
     fn visit_unary(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
         match expr {
-            Expr::Unary(UnaryOp::Negative, operand_expr) => {
-                let operand = operand_expr.accept(self)?;
+            Expr::Unary(UnaryOp::Negative, operand_expr, span) => {
+                let operand = self.operand(operand_expr)?;
                 match operand {
-                    EvalResult::Time(t) => Result::Ok(EvalResult::Time(t * dec!(-1))),
+                    EvalResult::Time(t) => time_result(t.checked_mul(dec!(-1)), *span),
                     EvalResult::Number(n) => Result::Ok(EvalResult::Number(n * dec!(-1))),
+                    _ => unreachable!(),
+                }
+            }
+            _ => panic!(),
+        }
+    }
+
+    fn visit_call(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        match expr {
+            Expr::Call(name, args, span) => match name.as_str() {
+                "round" => self.round(name, args, RoundingStrategy::MidpointAwayFromZero, *span),
+                "floor" => self.round(name, args, RoundingStrategy::ToNegativeInfinity, *span),
+                "ceil" => self.round(name, args, RoundingStrategy::ToPositiveInfinity, *span),
+                "trunc" => self.round(name, args, RoundingStrategy::ToZero, *span),
+                "sum" | "avg" | "min" | "max" | "median" | "stddev" => {
+                    self.aggregate(name, args, *span)
+                }
+                "if" => match args.as_slice() {
+                    [condition, then_expr, else_expr] => {
+                        self.conditional(condition, then_expr, else_expr, *span)
+                    }
+                    _ => Result::Err(EvalError::ArgumentCount(name.clone(), args.len(), *span)),
+                },
+                "abs" | "sign" | "neg" => self.sign_function(name, args, *span),
+                "sqrt" => self.sqrt(name, args, *span),
+                _ => {
+                    let name_span = Span::new(span.start, span.start + name.len());
+                    Result::Err(EvalError::UnknownFunction(name.clone(), name_span))
+                }
+            },
+            _ => panic!(),
+        }
+    }
+
+    fn visit_variable(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        match expr {
+            Expr::Variable(name, span) => match self.variables.get(name) {
+                Option::Some(value) => Result::Ok(*value),
+                Option::None => Result::Err(EvalError::UndefinedVariable(name.clone(), *span)),
+            },
+            _ => panic!(),
+        }
+    }
+
+    fn visit_history(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        match expr {
+            Expr::Answer(span) => match self.history.last() {
+                Option::Some(value) => Result::Ok(*value),
+                Option::None => Result::Err(EvalError::NoPreviousResult(*span)),
+            },
+            Expr::History(n, span) => match self.history.get(n - 1) {
+                Option::Some(value) => Result::Ok(*value),
+                Option::None => Result::Err(EvalError::UndefinedResult(*n, *span)),
+            },
+            _ => panic!(),
+        }
+    }
+
+    fn visit_convert(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        match expr {
+            Expr::Convert(operand_expr, conversion, span) => {
+                match (self.operand(operand_expr)?, conversion) {
+                    (EvalResult::Time(t), Conversion::Unit(unit)) => {
+                        number_result(Decimal::from(t).checked_div(unit.seconds()), *span)
+                    }
+                    (EvalResult::Time(t), Conversion::Format(format)) => {
+                        Result::Ok(EvalResult::FormattedTime(t, *format))
+                    }
+                    _ => Result::Err(EvalError::ConvertNumber(operand_expr.span())),
                 }
             }
             _ => panic!(),
@@ -144,20 +566,621 @@ impl ExprVisitor for ExprEvaluator {
     }
 }
 
+impl ExprEvaluator<'_> {
+    /// Evaluates an operand of another expression. A formatted time is converted to a plain time,
+    /// and a boolean is an error, so the result is always either a time or a number.
+    fn operand(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        match expr.accept(self)? {
+            EvalResult::FormattedTime(t, _) => Result::Ok(EvalResult::Time(t)),
+            EvalResult::Bool(_) => Result::Err(EvalError::BoolOperand(expr.span())),
+            result => Result::Ok(result),
+        }
+    }
+
+    /// Converts a number which is added to or subtracted from a time to a time in the default unit,
+    /// if there is one.
+    fn coerce_numbers(
+        &self,
+        op: &BinaryOp,
+        r1: EvalResult,
+        r2: EvalResult,
+        left_span: Span,
+        right_span: Span,
+    ) -> Result<(EvalResult, EvalResult), EvalError> {
+        let unit = match (op, self.default_unit) {
+            (BinaryOp::Add, Option::Some(unit)) | (BinaryOp::Subtract, Option::Some(unit)) => unit,
+            _ => return Result::Ok((r1, r2)),
+        };
+        let to_time = |n: Decimal, span| {
+            n.checked_mul(unit.seconds())
+                .and_then(|seconds| Time::try_from(seconds).ok())
+                .map(EvalResult::Time)
+                .ok_or(EvalError::Overflow(span))
+        };
+        match (r1, r2) {
+            (EvalResult::Time(_), EvalResult::Number(n)) => {
+                Result::Ok((r1, to_time(n, right_span)?))
+            }
+            (EvalResult::Number(n), EvalResult::Time(_)) => {
+                Result::Ok((to_time(n, left_span)?, r2))
+            }
+            _ => Result::Ok((r1, r2)),
+        }
+    }
+
+    /// Multiplies a time or a number by `factor`.
+    fn scale(&self, expr: &Expr, factor: Decimal, span: Span) -> Result<EvalResult, EvalError> {
+        match self.operand(expr)? {
+            EvalResult::Time(t) => self.time_op(
+                span,
+                t,
+                factor,
+                Time::checked_mul,
+                Time::saturating_mul,
+                Time::wrapping_mul,
+            ),
+            EvalResult::Number(n) => number_result(n.checked_mul(factor), span),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Applies an operation to a time, handling overflow according to the overflow policy.
+    fn time_op<T>(
+        &self,
+        span: Span,
+        time: Time,
+        other: T,
+        checked: fn(Time, T) -> Option<Time>,
+        saturating: fn(Time, T) -> Time,
+        wrapping: fn(Time, T) -> Time,
+    ) -> Result<EvalResult, EvalError> {
+        match self.overflow_policy {
+            OverflowPolicy::Checked => time_result(checked(time, other), span),
+            OverflowPolicy::Saturating => Result::Ok(EvalResult::Time(saturating(time, other))),
+            OverflowPolicy::Wrapping => Result::Ok(EvalResult::Time(wrapping(time, other))),
+        }
+    }
+
+    /// Evaluates the branch of a conditional chosen by `condition`.
+    ///
+    /// The other branch is also evaluated to check that both branches have the same type. Errors
+    /// in the other branch are ignored, so that a condition can guard against them, for example in
+    /// `x == 0 ? 0 : 1 / x`.
+    fn conditional(
+        &self,
+        condition: &Expr,
+        then_expr: &Expr,
+        else_expr: &Expr,
+        span: Span,
+    ) -> Result<EvalResult, EvalError> {
+        let (chosen, other) = match condition.accept(self)? {
+            EvalResult::Bool(true) => (then_expr, else_expr),
+            EvalResult::Bool(false) => (else_expr, then_expr),
+            _ => return Result::Err(EvalError::ConditionNotBool(condition.span())),
+        };
+        let result = chosen.accept(self)?;
+        if let Result::Ok(other_result) = other.accept(self) {
+            if !same_type(result, other_result) {
+                return Result::Err(EvalError::BranchTypes(span));
+            }
+        }
+        Result::Ok(result)
+    }
+
+    /// Evaluates `abs`, `sign` or `neg` of a time or a number. The sign of a time is a number.
+    fn sign_function(
+        &self,
+        name: &str,
+        args: &[Expr],
+        span: Span,
+    ) -> Result<EvalResult, EvalError> {
+        let value = match args {
+            [value] => self.operand(value)?,
+            _ => return Result::Err(EvalError::ArgumentCount(name.to_string(), args.len(), span)),
+        };
+        match (name, value) {
+            ("abs", EvalResult::Time(t)) => Result::Ok(EvalResult::Time(t.abs())),
+            ("abs", EvalResult::Number(n)) => Result::Ok(EvalResult::Number(n.abs())),
+            ("sign", EvalResult::Time(t)) => {
+                Result::Ok(EvalResult::Number(Decimal::from(t.signum())))
+            }
+            ("sign", EvalResult::Number(n)) => {
+                Result::Ok(EvalResult::Number(Decimal::from(n.cmp(&dec!(0)) as i8)))
+            }
+            ("neg", EvalResult::Time(t)) => Result::Ok(EvalResult::Time(-t)),
+            ("neg", EvalResult::Number(n)) => Result::Ok(EvalResult::Number(-n)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Evaluates the square root of a number.
+    fn sqrt(&self, name: &str, args: &[Expr], span: Span) -> Result<EvalResult, EvalError> {
+        let value = match args {
+            [value] => self.operand(value)?,
+            _ => return Result::Err(EvalError::ArgumentCount(name.to_string(), args.len(), span)),
+        };
+        match value {
+            EvalResult::Number(n) => match n.sqrt() {
+                Option::Some(root) => {
+                    Result::Ok(EvalResult::Number(round_decimal(root).normalize()))
+                }
+                Option::None => Result::Err(EvalError::SqrtNegative(span)),
+            },
+            EvalResult::Time(_) => Result::Err(EvalError::SqrtTime(span)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Rounds a time to a multiple of a time increment, or a number to a number of decimal places.
+    ///
+    /// Without an increment, a time is rounded to a whole number of seconds and a number to a
+    /// whole number.
+    fn round(
+        &self,
+        name: &str,
+        args: &[Expr],
+        strategy: RoundingStrategy,
+        span: Span,
+    ) -> Result<EvalResult, EvalError> {
+        let (value_expr, increment_expr) = match args {
+            [value] => (value, Option::None),
+            [value, increment] => (value, Option::Some(increment)),
+            _ => return Result::Err(EvalError::ArgumentCount(name.to_string(), args.len(), span)),
+        };
+        let value = self.operand(value_expr)?;
+        let increment = match increment_expr {
+            Option::Some(e) => Option::Some((self.operand(e)?, e.span())),
+            Option::None => Option::None,
+        };
+
+        match (value, increment) {
+            (EvalResult::Time(t), Option::None) => {
+                self.round_time(t, Time::builder().seconds(1).build(), strategy, span)
+            }
+            (EvalResult::Time(t), Option::Some((EvalResult::Time(increment), increment_span))) => {
+                if increment == Time::builder().build() {
+                    Result::Err(EvalError::ZeroIncrement(increment_span))
+                } else {
+                    self.round_time(t, increment, strategy, span)
+                }
+            }
+            (EvalResult::Time(_), Option::Some((EvalResult::Number(_), _))) => {
+                Result::Err(EvalError::RoundTimeToPlaces(span))
+            }
+            (EvalResult::Number(n), Option::None) => {
+                Result::Ok(EvalResult::Number(n.round_dp_with_strategy(0, strategy)))
+            }
+            (EvalResult::Number(n), Option::Some((EvalResult::Number(places), places_span))) => {
+                if places.fract() != dec!(0) || places < dec!(0) || places > dec!(28) {
+                    return Result::Err(EvalError::InvalidDecimalPlaces(places_span));
+                }
+                let places = places.to_u32().unwrap();
+                Result::Ok(EvalResult::Number(
+                    n.round_dp_with_strategy(places, strategy),
+                ))
+            }
+            (EvalResult::Number(_), Option::Some((EvalResult::Time(_), _))) => {
+                Result::Err(EvalError::RoundNumberToTime(span))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Evaluates an aggregate function over one or more arguments which are either all times or all
+    /// numbers. The standard deviation is the population standard deviation.
+    fn aggregate(&self, name: &str, args: &[Expr], span: Span) -> Result<EvalResult, EvalError> {
+        if args.is_empty() {
+            return Result::Err(EvalError::ArgumentCount(name.to_string(), 0, span));
+        }
+
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push((self.operand(arg)?, arg.span()));
+        }
+        let is_time = |value: &EvalResult| matches!(value, EvalResult::Time(_));
+        let times = is_time(&values[0].0);
+        if let Option::Some((_, arg_span)) = values.iter().find(|(v, _)| is_time(v) != times) {
+            return Result::Err(EvalError::MixedArguments(name.to_string(), *arg_span));
+        }
+
+        // Sums of times are the only aggregate which can overflow, so they are calculated using
+        // time arithmetic in order to apply the overflow policy.
+        if times && name == "sum" {
+            let mut total = Time::builder().build();
+            for (value, _) in &values {
+                if let EvalResult::Time(t) = value {
+                    total = match self.time_op(
+                        span,
+                        total,
+                        *t,
+                        Time::checked_add,
+                        Time::saturating_add,
+                        Time::wrapping_add,
+                    )? {
+                        EvalResult::Time(t) => t,
+                        _ => unreachable!(),
+                    };
+                }
+            }
+            return Result::Ok(EvalResult::Time(total));
+        }
+
+        // Times are aggregated as a decimal number of seconds, which represents them exactly.
+        let mut decimals: Vec<Decimal> = values
+            .iter()
+            .map(|(value, _)| match value {
+                EvalResult::Time(t) => Decimal::from(*t),
+                EvalResult::Number(n) => *n,
+                _ => unreachable!(),
+            })
+            .collect();
+        decimals.sort();
+        let count = Decimal::from(decimals.len());
+        // The sum is only calculated when it is needed, since it can overflow.
+        let sum = || {
+            decimals
+                .iter()
+                .try_fold(dec!(0), |sum, d| sum.checked_add(*d))
+                .ok_or(EvalError::Overflow(span))
+        };
+
+        let result = match name {
+            "sum" => sum()?,
+            "avg" => sum()? / count,
+            "min" => decimals[0],
+            "max" => decimals[decimals.len() - 1],
+            "median" => {
+                let middle = decimals.len() / 2;
+                if decimals.len() % 2 == 1 {
+                    decimals[middle]
+                } else {
+                    // The sum of the middle values only overflows if they have the same sign, in
+                    // which case their difference does not.
+                    let (low, high) = (decimals[middle - 1], decimals[middle]);
+                    low.checked_add(high)
+                        .map(|sum| sum / dec!(2))
+                        .or_else(|| low.checked_add((high - low) / dec!(2)))
+                        .ok_or(EvalError::Overflow(span))?
+                }
+            }
+            "stddev" => {
+                let mean = sum()? / count;
+                let variance = decimals
+                    .iter()
+                    .try_fold(dec!(0), |sum, d| {
+                        let deviation = d.checked_sub(mean)?;
+                        sum.checked_add(deviation.checked_mul(deviation)?)
+                    })
+                    .ok_or(EvalError::Overflow(span))?
+                    / count;
+                variance.sqrt().ok_or(EvalError::Overflow(span))?
+            }
+            _ => panic!(),
+        };
+
+        if times {
+            match Time::try_from(result) {
+                Result::Ok(t) => Result::Ok(EvalResult::Time(t)),
+                Result::Err(_) => Result::Err(EvalError::Overflow(span)),
+            }
+        } else {
+            Result::Ok(EvalResult::Number(round_decimal(result)))
+        }
+    }
+
+    /// Rounds a time to a multiple of a non-zero increment. A time which would overflow when
+    /// rounded is an error with the checked overflow policy, and is otherwise rounded toward zero
+    /// instead.
+    fn round_time(
+        &self,
+        time: Time,
+        increment: Time,
+        strategy: RoundingStrategy,
+        span: Span,
+    ) -> Result<EvalResult, EvalError> {
+        match time.round_to_multiple(increment, strategy) {
+            Option::Some(t) => Result::Ok(EvalResult::Time(t)),
+            Option::None => match self.overflow_policy {
+                OverflowPolicy::Checked => Result::Err(EvalError::Overflow(span)),
+                OverflowPolicy::Saturating | OverflowPolicy::Wrapping => {
+                    let t = time.round_to_multiple(increment, RoundingStrategy::ToZero);
+                    Result::Ok(EvalResult::Time(t.unwrap()))
+                }
+            },
+        }
+    }
+}
+
 fn round_decimal(decimal: Decimal) -> Decimal {
-    decimal.round_dp_with_strategy(9, RoundingStrategy::RoundHalfUp)
+    decimal.round_dp_with_strategy(9, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Returns the quotient of floored division, rounded toward negative infinity, or `None` if the
+/// divisor is zero or the quotient would overflow.
+fn div_floor(dividend: Decimal, divisor: Decimal) -> Option<Decimal> {
+    dividend
+        .checked_sub(rem_floor(dividend, divisor)?)?
+        .checked_div(divisor)
+}
+
+/// Returns the remainder of floored division, which has the same sign as the divisor, or `None`
+/// if the divisor is zero.
+fn rem_floor(dividend: Decimal, divisor: Decimal) -> Option<Decimal> {
+    let remainder = dividend.checked_rem(divisor)?;
+    if !remainder.is_zero() && remainder.is_sign_negative() != divisor.is_sign_negative() {
+        remainder.checked_add(divisor)
+    } else {
+        Option::Some(remainder)
+    }
+}
+
+/// Returns `base` raised to the power of `exponent`, or `None` if the result would overflow.
+///
+/// A whole number exponent is calculated exactly by repeated squaring. Otherwise, `base` must not be
+/// negative, and the result is calculated using floating point numbers, so it is only accurate to
+/// about 15 significant digits.
+fn pow(base: Decimal, exponent: Decimal) -> Option<Decimal> {
+    if exponent.fract() != dec!(0) {
+        return Decimal::from_f64(base.to_f64()?.powf(exponent.to_f64()?));
+    }
+
+    // A power of 0, 1 or -1 is known without multiplying, however large the exponent is.
+    if base == dec!(1) || exponent == dec!(0) {
+        return Option::Some(dec!(1));
+    } else if base == dec!(0) && exponent > dec!(0) {
+        return Option::Some(dec!(0));
+    } else if base == dec!(-1) && exponent % dec!(2) == dec!(0) {
+        return Option::Some(dec!(1));
+    } else if base == dec!(-1) {
+        return Option::Some(dec!(-1));
+    }
+
+    // The result approaches zero as the exponent grows if the base is between -1 and 1 and the
+    // exponent is positive, or if the base is outside that range and the exponent is negative. A
+    // power which is too large to calculate is then too small to represent, so the result is zero.
+    let approaches_zero = (base.abs() < dec!(1)) == (exponent > dec!(0));
+    match exponent.abs().to_u64().and_then(|n| pow_whole(base, n)) {
+        Option::Some(power) if exponent < dec!(0) => dec!(1).checked_div(power),
+        Option::Some(power) => Option::Some(power),
+        Option::None if approaches_zero => Option::Some(dec!(0)),
+        Option::None => Option::None,
+    }
+}
+
+/// Returns `base` raised to the power of a whole number `exponent` by repeated squaring, or `None`
+/// if the result would overflow.
+fn pow_whole(base: Decimal, exponent: u64) -> Option<Decimal> {
+    let mut remaining = exponent;
+    let mut square = base;
+    let mut result = dec!(1);
+    loop {
+        if remaining % 2 == 1 {
+            result = result.checked_mul(square)?;
+        }
+        remaining /= 2;
+        if remaining == 0 {
+            return Option::Some(result);
+        }
+        square = square.checked_mul(square)?;
+    }
+}
+
+/// Converts the result of a checked number operation to an evaluation result.
+fn number_result(number: Option<Decimal>, span: Span) -> Result<EvalResult, EvalError> {
+    match number {
+        Option::Some(n) => Result::Ok(EvalResult::Number(round_decimal(n))),
+        Option::None => Result::Err(EvalError::Overflow(span)),
+    }
+}
+
+/// Returns `true` if both results are times, both are numbers or both are booleans. A formatted
+/// time has the same type as a time.
+fn same_type(r1: EvalResult, r2: EvalResult) -> bool {
+    matches!(
+        (r1, r2),
+        (EvalResult::Time(_), EvalResult::Time(_))
+            | (EvalResult::Time(_), EvalResult::FormattedTime(_, _))
+            | (EvalResult::FormattedTime(_, _), EvalResult::Time(_))
+            | (
+                EvalResult::FormattedTime(_, _),
+                EvalResult::FormattedTime(_, _)
+            )
+            | (EvalResult::Number(_), EvalResult::Number(_))
+            | (EvalResult::Bool(_), EvalResult::Bool(_))
+    )
+}
+
+/// Converts the result of a checked time operation to an evaluation result.
+fn time_result(time: Option<Time>, span: Span) -> Result<EvalResult, EvalError> {
+    match time {
+        Option::Some(t) => Result::Ok(EvalResult::Time(t)),
+        Option::None => Result::Err(EvalError::Overflow(span)),
+    }
 }
 
-pub(in crate) fn eval(expression: &str) -> Result<EvalResult, EvalError> {
-    parse_expression(expression)?.accept(&ExprEvaluator)
+/// Evaluates expressions containing times and numbers.
+///
+/// Variables assigned by a statement such as `lap1 = 01:02.3` are kept by the evaluator and can be
+/// used in later expressions. Results which are recorded in the evaluator's history can be used in
+/// later expressions as `ans` or `_` for the most recent result, and `$1`, `$2`, ... for the first,
+/// second, ... result.
+pub struct Evaluator {
+    overflow_policy: OverflowPolicy,
+    time_precision: TimeUnit,
+    number_precision: u32,
+    rounding_strategy: RoundingStrategy,
+    implicit_multiplication: bool,
+    default_unit: Option<TimeUnit>,
+    variables: HashMap<String, EvalResult>,
+    history: Vec<EvalResult>,
+}
+
+impl Evaluator {
+    /// Returns a new evaluator.
+    ///
+    /// By default, times are rounded to the nearest nanosecond and numbers to 9 decimal places,
+    /// with midpoints rounded away from zero.
+    pub fn new() -> Evaluator {
+        Evaluator {
+            overflow_policy: OverflowPolicy::Checked,
+            time_precision: TimeUnit::Nanoseconds,
+            number_precision: 9,
+            rounding_strategy: RoundingStrategy::MidpointAwayFromZero,
+            implicit_multiplication: false,
+            default_unit: Option::None,
+            variables: HashMap::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Sets how overflow is handled in time arithmetic. Arithmetic on numbers is always checked.
+    pub fn overflow_policy(&mut self, overflow_policy: OverflowPolicy) -> &mut Evaluator {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Sets the unit which results that are times are rounded to.
+    pub fn time_precision(&mut self, time_precision: TimeUnit) -> &mut Evaluator {
+        self.time_precision = time_precision;
+        self
+    }
+
+    /// Sets the number of decimal places which results that are numbers are rounded to.
+    pub fn number_precision(&mut self, number_precision: u32) -> &mut Evaluator {
+        self.number_precision = number_precision;
+        self
+    }
+
+    /// Sets how results are rounded to the time and number precision.
+    pub fn rounding_strategy(&mut self, rounding_strategy: RoundingStrategy) -> &mut Evaluator {
+        self.rounding_strategy = rounding_strategy;
+        self
+    }
+
+    /// Sets whether values which are written next to each other are multiplied, so that `3 01:30` is
+    /// `3 * 01:30`. This is disabled by default.
+    pub fn implicit_multiplication(&mut self, implicit_multiplication: bool) -> &mut Evaluator {
+        self.implicit_multiplication = implicit_multiplication;
+        self
+    }
+
+    /// Sets the unit of a number which is added to or subtracted from a time, so that with a
+    /// default unit of minutes, `1:00:00 + 30` is `1:30:00`. Without a default unit, which is the
+    /// default, this is an error.
+    pub fn default_unit(&mut self, default_unit: Option<TimeUnit>) -> &mut Evaluator {
+        self.default_unit = default_unit;
+        self
+    }
+
+    /// Evaluates the specified expression, or assigns the value of an expression to a variable
+    /// given a statement of the form `name = expression`. The result of an assignment is the
+    /// assigned value.
+    pub fn eval(&mut self, expression: &str) -> Result<EvalResult, EvalError> {
+        match parse_statement(expression, self.implicit_multiplication)? {
+            Stmt::Expr(expr) => self.eval_expr(&expr),
+            Stmt::Assign(name, expr, _) => {
+                let result = self.eval_expr(&expr)?;
+                self.variables.insert(name, result);
+                Result::Ok(result)
+            }
+        }
+    }
+
+    /// Returns whether a statement is complete, rather than ending before the end of an expression,
+    /// such as `(1:30:00 +`. Blank input is complete. A complete statement is not necessarily
+    /// valid.
+    pub fn is_complete(&self, expression: &str) -> bool {
+        if expression.trim().is_empty() {
+            return true;
+        }
+        match parse_statement(expression, self.implicit_multiplication) {
+            Result::Err(error) => !error.is_incomplete(),
+            Result::Ok(_) => true,
+        }
+    }
+
+    /// Returns the value of a variable, or `None` if it has not been assigned.
+    pub fn variable(&self, name: &str) -> Option<EvalResult> {
+        self.variables.get(name).copied()
+    }
+
+    /// Records a result in the history, making it available to later expressions.
+    pub fn record(&mut self, result: EvalResult) {
+        self.history.push(result);
+    }
+
+    /// Returns the recorded results, from first to most recent.
+    pub fn history(&self) -> &[EvalResult] {
+        &self.history
+    }
+
+    fn eval_expr(&self, expr: &Expr) -> Result<EvalResult, EvalError> {
+        let result = expr.accept(&ExprEvaluator {
+            overflow_policy: self.overflow_policy,
+            default_unit: self.default_unit,
+            variables: &self.variables,
+            history: &self.history,
+        })?;
+        self.round(result, expr.span())
+    }
+
+    /// Rounds a result to the time or number precision.
+    ///
+    /// A time which would overflow when rounded is an error with the checked overflow policy, and
+    /// is otherwise rounded toward zero instead.
+    fn round(&self, result: EvalResult, span: Span) -> Result<EvalResult, EvalError> {
+        match result {
+            EvalResult::Time(t) => Result::Ok(EvalResult::Time(self.round_time(t, span)?)),
+            EvalResult::Number(n) => {
+                let rounded =
+                    n.round_dp_with_strategy(self.number_precision, self.rounding_strategy);
+                // Rounding zero pads it with trailing zeros, which are removed.
+                if rounded.is_zero() {
+                    Result::Ok(EvalResult::Number(rounded.normalize()))
+                } else {
+                    Result::Ok(EvalResult::Number(rounded))
+                }
+            }
+            EvalResult::FormattedTime(t, format) => {
+                Result::Ok(EvalResult::FormattedTime(self.round_time(t, span)?, format))
+            }
+            EvalResult::Bool(_) => Result::Ok(result),
+        }
+    }
+
+    fn round_time(&self, time: Time, span: Span) -> Result<Time, EvalError> {
+        match time.round_to(self.time_precision, self.rounding_strategy) {
+            Option::Some(t) => Result::Ok(t),
+            Option::None => match self.overflow_policy {
+                OverflowPolicy::Checked => Result::Err(EvalError::Overflow(span)),
+                OverflowPolicy::Saturating | OverflowPolicy::Wrapping => {
+                    let t = time.round_to(self.time_precision, RoundingStrategy::ToZero);
+                    Result::Ok(t.unwrap())
+                }
+            },
+        }
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Evaluator::new()
+    }
 }
 
 #[cfg(test)]
 #[rustfmt::skip]
 mod tests {
-    use super::eval;
+    use crate::calc::eval::EvalError;
     use crate::calc::eval::EvalResult;
+    use crate::calc::eval::Evaluator;
+    use crate::calc::eval::OverflowPolicy;
+    use crate::span::Span;
+    use crate::time::format::TimeFormat;
+    use crate::time::format::TimeStyle;
     use crate::time::Time;
+    use crate::time::TimeUnit;
+    use rust_decimal::RoundingStrategy;
     use rust_decimal_macros::dec;
 
     #[test]
@@ -222,6 +1245,33 @@ mod tests {
             EvalResult::Number(dec!(25)));
     }
 
+    #[test]
+    fn eval_units() {
+        assert_eval("1h 30m + 45m", EvalResult::Time(Time::builder().hours(2).minutes(15).build()));
+        assert_eval("250ms * 12", EvalResult::Time(Time::builder().seconds(3).build()));
+        assert_eval("2.5h - 1:00:00", EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+        assert_eval("90m / 30m", EvalResult::Number(dec!(3)));
+        assert_eval("-1h2m3.5s", EvalResult::Time(Time::builder().negative().hours(1).minutes(2).seconds(3).nanoseconds(500000000).build()));
+        assert_eval("1d - 1ns", EvalResult::Time(Time::builder().hours(23).minutes(59).seconds(59).nanoseconds(999999999).build()));
+    }
+
+    #[test]
+    fn eval_days() {
+        assert_eval("2d 03:04:05 + 1s", EvalResult::Time(Time::builder().hours(51).minutes(4).seconds(6).build()));
+        assert_eval("2:03:04:05 - 1d", EvalResult::Time(Time::builder().hours(27).minutes(4).seconds(5).build()));
+        assert_eval("-1d 00:00:00 / 2", EvalResult::Time(Time::builder().negative().hours(12).build()));
+    }
+
+    #[test]
+    fn eval_iso8601() {
+        assert_eval("PT1H30M + 45m", EvalResult::Time(Time::builder().hours(2).minutes(15).build()));
+        assert_eval("P2DT3H / 3", EvalResult::Time(Time::builder().hours(17).build()));
+        assert_eval("-PT1.5S * 2", EvalResult::Time(Time::builder().negative().seconds(3).build()));
+        assert_eval("PT1H / PT30M", EvalResult::Number(dec!(2)));
+        assert!(eval("P1Y + 1s").is_err());
+        assert!(eval("P1M").is_err());
+    }
+
     #[test]
     fn eval_rounding() {
         assert_eval("1 / 9", EvalResult::Number(dec!(0.111111111)));
@@ -247,7 +1297,598 @@ mod tests {
         assert!(eval("44 / 0").is_err());
     }
 
+    #[test]
+    fn eval_overflow() {
+        assert_overflow("2562047788015215:30:07 + 1s");
+        assert_overflow("-2562047788015215:30:07 - 1s");
+        assert_overflow("2562047788015215:30:07 * 2");
+        assert_overflow("2 * 2562047788015215:30:07");
+        assert_overflow("2562047788015215:30:07 / 0.5");
+        assert_overflow("1:00:00 * 100000000000000000000");
+        assert_overflow("100000000000000000000 * 100000000000000000000");
+
+        assert_eval("2562047788015215:30:07 - 1s + 1s",
+            EvalResult::Time(Time::builder().hours(2562047788015215).minutes(30).seconds(7).build()));
+        assert_eval("-(2562047788015215:30:07)",
+            EvalResult::Time(Time::builder().negative().hours(2562047788015215).minutes(30).seconds(7).build()));
+    }
+
+    #[test]
+    fn eval_error_span() {
+        assert_error("1 + 0:00:02", "cannot add a time and a number", 0, 11);
+        assert_error("(0:00:30 - 4) * 2", "cannot subtract a time and a number", 1, 12);
+        assert_error("1 + 9:09:09 * 10:10:10", "cannot multiply a time by a time", 4, 22);
+        assert_error("11 / 12:12:12", "cannot divide a number by a time", 0, 13);
+        assert_error("22s / (1 - 1)", "division by zero", 7, 12);
+        assert_error("1s + 2562047788015215:30:07 * 2", "result is too large", 5, 31);
+        assert_error("1 + 2 +", "expected a time or number, found end of input", 7, 7);
+    }
+
+    #[test]
+    fn eval_integer_divide_and_remainder() {
+        // How many 25 minute pomodoros fit in 3:10:00, and what is left over.
+        assert_eval("3:10:00 // 25m", EvalResult::Number(dec!(7)));
+        assert_eval("3:10:00 % 25m", EvalResult::Time(Time::builder().minutes(15).build()));
+        assert_eval("3:10:00 // 25m * 25m + 3:10:00 % 25m", EvalResult::Time(Time::builder().hours(3).minutes(10).build()));
+        assert_eval("1s // 0.3s", EvalResult::Number(dec!(3)));
+        assert_eval("1s % 0.3s", EvalResult::Time(Time::builder().nanoseconds(100000000).build()));
+        assert_eval("-1s // 0.3s", EvalResult::Number(dec!(-4)));
+        assert_eval("-1s % 0.3s", EvalResult::Time(Time::builder().nanoseconds(200000000).build()));
+        assert_eval("1s % -0.3s", EvalResult::Time(Time::builder().negative().nanoseconds(200000000).build()));
+        assert_eval("1:00:00 % 1:00:00", EvalResult::Time(Time::builder().build()));
+
+        assert_eval("7 // 2", EvalResult::Number(dec!(3)));
+        assert_eval("7 % 2", EvalResult::Number(dec!(1)));
+        assert_eval("-7 // 2", EvalResult::Number(dec!(-4)));
+        assert_eval("-7 % 2", EvalResult::Number(dec!(1)));
+        assert_eval("7 // -2", EvalResult::Number(dec!(-4)));
+        assert_eval("7 % -2", EvalResult::Number(dec!(-1)));
+        assert_eval("7.5 % 2", EvalResult::Number(dec!(1.5)));
+        assert_eval("7.5 // 2.5", EvalResult::Number(dec!(3)));
+        assert_eval("1 + 7 % 4 * 2", EvalResult::Number(dec!(7)));
+
+        assert_error("1h // 0s", "division by zero", 6, 8);
+        assert_error("1h % (1m - 1m)", "division by zero", 6, 13);
+        assert_error("7 // 0", "division by zero", 5, 6);
+        assert_error("7 % (2 - 2)", "division by zero", 5, 10);
+        assert_error("1h // 2", "cannot integer divide a time and a number", 0, 7);
+        assert_error("2 // 1h", "cannot integer divide a time and a number", 0, 7);
+        assert_error("1h % 2", "cannot take the remainder of a time and a number", 0, 6);
+        assert_error("2 % 1h", "cannot take the remainder of a time and a number", 0, 6);
+    }
+
+    #[test]
+    fn eval_rounding_functions() {
+        assert_eval("round(1:07:30, 15m)", EvalResult::Time(Time::builder().hours(1).minutes(15).build()));
+        assert_eval("round(1:07:29, 15m)", EvalResult::Time(Time::builder().hours(1).build()));
+        assert_eval("floor(1:14:59, 15m)", EvalResult::Time(Time::builder().hours(1).build()));
+        assert_eval("ceil(1:00:01, 15m)", EvalResult::Time(Time::builder().hours(1).minutes(15).build()));
+        assert_eval("trunc(-1:14:59, 15m)", EvalResult::Time(Time::builder().negative().hours(1).build()));
+        assert_eval("floor(-1:14:59, 15m)", EvalResult::Time(Time::builder().negative().hours(1).minutes(15).build()));
+        assert_eval("round(0:00:02.5)", EvalResult::Time(Time::builder().seconds(3).build()));
+        assert_eval("round(1:00:00 / 7, 1s) * 7", EvalResult::Time(Time::builder().minutes(59).seconds(58).build()));
+
+        assert_eval("round(2.345, 2)", EvalResult::Number(dec!(2.35)));
+        assert_eval("floor(2.349, 2)", EvalResult::Number(dec!(2.34)));
+        assert_eval("ceil(2.341, 2)", EvalResult::Number(dec!(2.35)));
+        assert_eval("trunc(-2.349, 1 + 1)", EvalResult::Number(dec!(-2.34)));
+        assert_eval("round(2.5)", EvalResult::Number(dec!(3)));
+        assert_eval("floor(-2.5)", EvalResult::Number(dec!(-3)));
+        assert_eval("1:30:00 / round(1:07:30, 15m)", EvalResult::Number(dec!(1.2)));
+
+        assert_error("round(1:00:00, 2)", "cannot round a time to a number of decimal places", 0, 17);
+        assert_error("1 + floor(1.5, 1s)", "cannot round a number to a multiple of a time", 4, 18);
+        assert_error("round(1.5, 0.5)", "decimal places must be a whole number from 0 to 28", 11, 14);
+        assert_error("round(1.5, -1)", "decimal places must be a whole number from 0 to 28", 11, 13);
+        assert_error("round(1.5, 29)", "decimal places must be a whole number from 0 to 28", 11, 13);
+        assert_error("ceil(1:00:00, 0s)", "rounding increment must not be zero", 14, 16);
+        assert_error("trunc()", "wrong number of arguments to 'trunc', found 0", 0, 7);
+        assert_error("round(1, 2, 3)", "wrong number of arguments to 'round', found 3", 0, 14);
+        assert_error("2 * nearest(1h, 15m)", "unknown function 'nearest'", 4, 11);
+        assert_error("round(1h + 1, 15m)", "cannot add a time and a number", 6, 12);
+    }
+
+    #[test]
+    fn eval_aggregate_functions() {
+        assert_eval("sum(01:02.5, 58.5s, 1m)", EvalResult::Time(Time::builder().minutes(3).seconds(1).build()));
+        assert_eval("avg(01:02.5, 58.5s, 1m)", EvalResult::Time(Time::builder().minutes(1).seconds(0).nanoseconds(333333333).build()));
+        assert_eval("min(01:02.5, 58.5s, 1m)", EvalResult::Time(Time::builder().seconds(58).nanoseconds(500000000).build()));
+        assert_eval("max(01:02.5, 58.5s, 1m)", EvalResult::Time(Time::builder().minutes(1).seconds(2).nanoseconds(500000000).build()));
+        assert_eval("median(01:02.5, 58.5s, 1m)", EvalResult::Time(Time::builder().minutes(1).build()));
+        assert_eval("median(4s, 1ns, 2ns, 1s)", EvalResult::Time(Time::builder().nanoseconds(500000001).build()));
+        assert_eval("median(1ns, 2ns)", EvalResult::Time(Time::builder().nanoseconds(2).build()));
+        assert_eval("stddev(2s, 4s, 4s, 4s, 5s, 5s, 7s, 9s)", EvalResult::Time(Time::builder().seconds(2).build()));
+        assert_eval("stddev(1ns, 2ns)", EvalResult::Time(Time::builder().nanoseconds(1).build()));
+        assert_eval("stddev(1:00:00)", EvalResult::Time(Time::builder().build()));
+        assert_eval("min(-1h, 1m)", EvalResult::Time(Time::builder().negative().hours(1).build()));
+
+        assert_eval("sum(1, 2, 3.5)", EvalResult::Number(dec!(6.5)));
+        assert_eval("avg(1, 2)", EvalResult::Number(dec!(1.5)));
+        assert_eval("avg(1, 1, 2)", EvalResult::Number(dec!(1.333333333)));
+        assert_eval("min(3, -2, 1)", EvalResult::Number(dec!(-2)));
+        assert_eval("max(3, -2, 1)", EvalResult::Number(dec!(3)));
+        assert_eval("median(3, -2, 1, 10)", EvalResult::Number(dec!(2)));
+        assert_eval("median(-79228162514264337593543950335, 39614081257132168796771975168, 39614081257132168796771975168, 39614081257132168796771975168)",
+            EvalResult::Number(dec!(39614081257132168796771975168)));
+        assert_eval("median(79228162514264337593543950335, 79228162514264337593543950335)", EvalResult::Number(dec!(79228162514264337593543950335)));
+        assert_eval("stddev(2, 4, 4, 4, 5, 5, 7, 9)", EvalResult::Number(dec!(2)));
+        assert_eval("stddev(1, 2)", EvalResult::Number(dec!(0.5)));
+
+        // Arguments can be expressions.
+        assert_eval("sum(1h, 2 * 30m) / avg(1, 3)", EvalResult::Time(Time::builder().hours(1).build()));
+        assert_eval("sum(1:00:00 / 30:00, 1)", EvalResult::Number(dec!(3)));
+
+        assert_error("sum(1h, 2, 3)", "cannot mix times and numbers in arguments to 'sum'", 8, 9);
+        assert_error("1 + avg(1, 2, 3s)", "cannot mix times and numbers in arguments to 'avg'", 14, 16);
+        assert_error("median()", "wrong number of arguments to 'median', found 0", 0, 8);
+        assert_error("max(1h, 1h * 1h)", "cannot multiply a time by a time", 8, 15);
+        assert_error("sum(2562047788015215:30:07, 1s)", "result is too large", 0, 31);
+        assert_error("stddev(-500000000000000, 500000000000000)", "result is too large", 0, 41);
+        assert_error("stddev(-79228162514264337593543950335, 39614081257132168796771975168, 39614081257132168796771975168, 39614081257132168796771975168)",
+            "result is too large", 0, 131);
+    }
+
+    #[test]
+    fn eval_aggregate_overflow() {
+        let mut evaluator = Evaluator::new();
+        evaluator.overflow_policy(OverflowPolicy::Saturating);
+        assert_eq!(evaluator.eval("sum(2562047788015215:30:07, 1s, -1s)").unwrap(), EvalResult::Time(Time::MAX - Time::builder().seconds(1).build()));
+        evaluator.overflow_policy(OverflowPolicy::Wrapping);
+        assert_eq!(evaluator.eval("sum(2562047788015215:30:07, 1s, -1s)").unwrap(), EvalResult::Time(Time::builder().hours(2562047788015215).minutes(30).seconds(7).build()));
+        assert_eq!(evaluator.eval("avg(2562047788015215:30:07, 2562047788015215:30:07)").unwrap(), EvalResult::Time(Time::builder().hours(2562047788015215).minutes(30).seconds(7).build()));
+    }
+
+    #[test]
+    fn eval_comparison() {
+        assert_eval("1 < 2", EvalResult::Bool(true));
+        assert_eval("2 < 2", EvalResult::Bool(false));
+        assert_eval("2 <= 2", EvalResult::Bool(true));
+        assert_eval("3 <= 2", EvalResult::Bool(false));
+        assert_eval("2.0 == 2", EvalResult::Bool(true));
+        assert_eval("2 != 2.00", EvalResult::Bool(false));
+        assert_eval("-1 > -2", EvalResult::Bool(true));
+        assert_eval("1 >= 2", EvalResult::Bool(false));
+        assert_eval("1 + 2 * 3 == 7", EvalResult::Bool(true));
+
+        assert_eval("90m > 1h", EvalResult::Bool(true));
+        assert_eval("1:30:00 == 90m", EvalResult::Bool(true));
+        assert_eval("-1s < 0.5s", EvalResult::Bool(true));
+        assert_eval("-1.5s >= -1s", EvalResult::Bool(false));
+        assert_eval("1h != 60m", EvalResult::Bool(false));
+        assert_eval("90m in minutes > 60", EvalResult::Bool(true));
+        assert_eval("90m to iso8601 == 1h 30m", EvalResult::Bool(true));
+        assert_eval("max(1h, 2h) <= sum(1h, 1h)", EvalResult::Bool(true));
+
+        assert_eq!(EvalResult::Bool(true).to_string(), "true");
+        assert_eq!(EvalResult::Bool(false).to_string(), "false");
+
+        assert_error("1h < 60", "cannot compare a time and a number", 0, 7);
+        assert_error("(1 < 2) + 1", "cannot use a boolean as an operand", 1, 6);
+        assert_error("-(1 < 2)", "cannot use a boolean as an operand", 2, 7);
+        assert_error("round(1 == 1)", "cannot use a boolean as an operand", 6, 12);
+        assert_error("(1 < 2) in s", "cannot use a boolean as an operand", 1, 6);
+        assert_error("(1 < 2) == (2 < 3)", "cannot use a boolean as an operand", 1, 6);
+
+        let mut evaluator = Evaluator::new();
+        assert_eq!(evaluator.eval("late = 2h > 90m").unwrap(), EvalResult::Bool(true));
+        assert_eq!(evaluator.variable("late"), Option::Some(EvalResult::Bool(true)));
+    }
+
+    #[test]
+    fn eval_conditional() {
+        assert_eval("1 < 2 ? 1h : 2h", EvalResult::Time(Time::builder().hours(1).build()));
+        assert_eval("1 > 2 ? 1h : 2h", EvalResult::Time(Time::builder().hours(2).build()));
+        assert_eval("90m > 1h ? 90m - 1h : 0s", EvalResult::Time(Time::builder().minutes(30).build()));
+        assert_eval("1 == 2 ? 1 : 2 == 2 ? 2 : 3", EvalResult::Number(dec!(2)));
+        assert_eval("1 == 1 ? 2 == 2 ? 1 : 2 : 3", EvalResult::Number(dec!(1)));
+        assert_eval("(1 < 2 ? 3 : 4) * 2", EvalResult::Number(dec!(6)));
+        assert_eval("1 < 2 ? 3 < 4 : 5 < 6", EvalResult::Bool(true));
+        assert_eval("1 < 2 ? 90m to iso8601 : 1h",
+            EvalResult::FormattedTime(Time::builder().hours(1).minutes(30).build(), TimeFormat::new(TimeStyle::Iso8601)));
+
+        assert_eval("if(1 < 2, 1h, 2h)", EvalResult::Time(Time::builder().hours(1).build()));
+        assert_eval("if(1h != 60m, 1, 2) + 1", EvalResult::Number(dec!(3)));
+
+        // Errors in the branch which is not chosen are ignored.
+        assert_eval("0 == 0 ? 0 : 1 / 0", EvalResult::Number(dec!(0)));
+        assert_eval("if(1 == 0, 1 / 0, 1)", EvalResult::Number(dec!(1)));
+
+        assert_error("1 ? 2 : 3", "condition must be true or false", 0, 1);
+        assert_error("if(1h, 2, 3)", "condition must be true or false", 3, 5);
+        assert_error("1 < 2 ? 1h : 2", "both branches of a conditional must have the same type", 0, 14);
+        assert_error("1 > 2 ? 1 : 1 < 2", "both branches of a conditional must have the same type", 0, 17);
+        assert_error("if(1 < 2, 3, 4h)", "both branches of a conditional must have the same type", 0, 16);
+        assert_error("1 < 2 ? 1 / 0 : 1", "division by zero", 12, 13);
+        assert_error("if(1 < 2, 3)", "wrong number of arguments to 'if', found 2", 0, 12);
+        assert_error("(1 < 2 ? 1 < 2 : 1 > 2) + 1", "cannot use a boolean as an operand", 1, 22);
+    }
+
+    #[test]
+    fn eval_sign_functions() {
+        assert_eval("abs(-01:30)", EvalResult::Time(Time::builder().minutes(1).seconds(30).build()));
+        assert_eval("abs(1h)", EvalResult::Time(Time::builder().hours(1).build()));
+        assert_eval("abs(-2.5)", EvalResult::Number(dec!(2.5)));
+        assert_eval("abs(0)", EvalResult::Number(dec!(0)));
+        assert_eval("abs(-2562047788015215:30:07.999999999)", EvalResult::Time(Time::MAX));
+
+        assert_eval("sign(-1ns)", EvalResult::Number(dec!(-1)));
+        assert_eval("sign(0s)", EvalResult::Number(dec!(0)));
+        assert_eval("sign(5h)", EvalResult::Number(dec!(1)));
+        assert_eval("sign(-0.5)", EvalResult::Number(dec!(-1)));
+        assert_eval("sign(0)", EvalResult::Number(dec!(0)));
+        assert_eval("sign(2.5)", EvalResult::Number(dec!(1)));
+
+        assert_eval("neg(01:30)", EvalResult::Time(Time::builder().negative().minutes(1).seconds(30).build()));
+        assert_eval("neg(-2.5)", EvalResult::Number(dec!(2.5)));
+        assert_eval("neg(0s)", EvalResult::Time(Time::builder().build()));
+        assert_eval("neg(90m to iso8601)", EvalResult::Time(Time::builder().negative().hours(1).minutes(30).build()));
+
+        assert_error("abs()", "wrong number of arguments to 'abs', found 0", 0, 5);
+        assert_error("sign(1, 2)", "wrong number of arguments to 'sign', found 2", 0, 10);
+        assert_error("neg(1 < 2)", "cannot use a boolean as an operand", 4, 9);
+    }
+
+    #[test]
+    fn eval_percentage() {
+        assert_eval("15%", EvalResult::Number(dec!(0.15)));
+        assert_eval("12.5%", EvalResult::Number(dec!(0.125)));
+        assert_eval("1:00:00 * 15%", EvalResult::Time(Time::builder().minutes(9).build()));
+        assert_eval("15% * 1:00:00", EvalResult::Time(Time::builder().minutes(9).build()));
+        assert_eval("1:00:00 / 50%", EvalResult::Time(Time::builder().hours(2).build()));
+        assert_eval("3:45:00 + 20%", EvalResult::Time(Time::builder().hours(4).minutes(30).build()));
+        assert_eval("3:45:00 - 20%", EvalResult::Time(Time::builder().hours(3).build()));
+        assert_eval("1h+10%", EvalResult::Time(Time::builder().hours(1).minutes(6).build()));
+        assert_eval("1h + 150%", EvalResult::Time(Time::builder().hours(2).minutes(30).build()));
+        assert_eval("1h - 150%", EvalResult::Time(Time::builder().negative().minutes(30).build()));
+        assert_eval("1h + 2h + 10%", EvalResult::Time(Time::builder().hours(3).minutes(18).build()));
+        assert_eval("1h + 2h * 10%", EvalResult::Time(Time::builder().hours(1).minutes(12).build()));
+        assert_eval("200 + 5%", EvalResult::Number(dec!(210)));
+        assert_eval("200 - 5%", EvalResult::Number(dec!(190)));
+        assert_eval("50% + 50%", EvalResult::Number(dec!(0.75)));
+        assert_eval("(1h + 20%) in minutes", EvalResult::Number(dec!(72)));
+        assert_eval("1h + 0% == 1h", EvalResult::Bool(true));
+
+        // A `%` followed by an operand is the remainder operator.
+        assert_eval("20%3", EvalResult::Number(dec!(2)));
+        assert_eval("20 % 3", EvalResult::Number(dec!(2)));
+        assert_eval("20%(3)", EvalResult::Number(dec!(2)));
+
+        assert_error("1h + 20 %", "expected a time or number, found end of input", 9, 9);
+        assert_error("1h + 20", "cannot add a time and a number", 0, 7);
+        assert_overflow("2562047788015215:30:07 + 1%");
+
+        let mut evaluator = Evaluator::new();
+        evaluator.overflow_policy(OverflowPolicy::Saturating);
+        assert_eq!(evaluator.eval("2562047788015215:30:07 + 1%").unwrap(), EvalResult::Time(Time::MAX));
+    }
+
+    #[test]
+    fn eval_power() {
+        assert_eval("2 ^ 10", EvalResult::Number(dec!(1024)));
+        assert_eval("2^3^2", EvalResult::Number(dec!(512)));
+        assert_eval("(2^3)^2", EvalResult::Number(dec!(64)));
+        assert_eval("-2^2", EvalResult::Number(dec!(-4)));
+        assert_eval("(-2)^3", EvalResult::Number(dec!(-8)));
+        assert_eval("2^-2", EvalResult::Number(dec!(0.25)));
+        assert_eval("2 * 3^2", EvalResult::Number(dec!(18)));
+        assert_eval("1.5^2", EvalResult::Number(dec!(2.25)));
+        assert_eval("0^0", EvalResult::Number(dec!(1)));
+        assert_eval("0^5", EvalResult::Number(dec!(0)));
+        assert_eval("5^0", EvalResult::Number(dec!(1)));
+        assert_eval("0.5^100", EvalResult::Number(dec!(0)));
+        assert_eval("0.5^100000000000000000000", EvalResult::Number(dec!(0)));
+        assert_eval("2^-30", EvalResult::Number(dec!(0.000000001)));
+        assert_eval("2^-100", EvalResult::Number(dec!(0)));
+        assert_eval("2^-1000", EvalResult::Number(dec!(0)));
+        assert_eval("(-2)^-1001", EvalResult::Number(dec!(0)));
+        assert_eval("2^-100000000000000000000", EvalResult::Number(dec!(0)));
+        assert_eval("0.5^-10", EvalResult::Number(dec!(1024)));
+        assert_eval("1^12345678901234", EvalResult::Number(dec!(1)));
+        assert_eval("1 ^ 100000000000000000000", EvalResult::Number(dec!(1)));
+        assert_eval("1 ^ -100000000000000000000", EvalResult::Number(dec!(1)));
+        assert_eval("0 ^ 100000000000000000000", EvalResult::Number(dec!(0)));
+        assert_eval("(-1) ^ 100000000000000000000", EvalResult::Number(dec!(1)));
+        assert_eval("(-1) ^ 100000000000000000001", EvalResult::Number(dec!(-1)));
+        assert_eval("(-1) ^ -100000000000000000001", EvalResult::Number(dec!(-1)));
+        assert_eval("(-1)^-2", EvalResult::Number(dec!(1)));
+        assert_eval("4^0.5", EvalResult::Number(dec!(2)));
+        assert_eval("2^0.5", EvalResult::Number(dec!(1.414213562)));
+        assert_eval("16^0.25", EvalResult::Number(dec!(2)));
+        assert_eval("100^-1.5", EvalResult::Number(dec!(0.001)));
+        assert_eval("1h * 2^2", EvalResult::Time(Time::builder().hours(4).build()));
+
+        assert_eval("sqrt(16)", EvalResult::Number(dec!(4)));
+        assert_eval("sqrt(2)", EvalResult::Number(dec!(1.414213562)));
+        assert_eval("sqrt(0)", EvalResult::Number(dec!(0)));
+        assert_eval("sqrt(1h / 1s)", EvalResult::Number(dec!(60)));
+
+        assert_error("1h ^ 2", "cannot raise a time to a power", 0, 6);
+        assert_error("1h ^ 1h", "cannot raise a time to a power", 0, 7);
+        assert_error("2 ^ 1h", "cannot raise a number to the power of a time", 0, 6);
+        assert_error("(-8)^0.5", "cannot raise a negative number to a fractional power", 1, 8);
+        assert_error("0^-1", "division by zero", 0, 4);
+        assert_error("0 ^ -100000000000000000000", "division by zero", 0, 26);
+        assert_error("2 ^ 100000000000000000000", "result is too large", 0, 25);
+        assert_error("10^29", "result is too large", 0, 5);
+        assert_error("0.5^-1000", "result is too large", 0, 9);
+
+        // Powers and square roots are written without trailing zeros.
+        assert_eq!(eval("0.5^100").unwrap().to_string(), "0");
+        assert_eq!(eval("0.1^2").unwrap().to_string(), "0.01");
+        assert_eq!(eval("2.50^2").unwrap().to_string(), "6.25");
+        assert_eq!(eval("sqrt(0.0001)").unwrap().to_string(), "0.01");
+        assert_error("sqrt(1h)", "cannot take the square root of a time", 0, 8);
+        assert_error("sqrt(-1)", "cannot take the square root of a negative number", 0, 8);
+        assert_error("sqrt(1, 2)", "wrong number of arguments to 'sqrt', found 2", 0, 10);
+    }
+
+    #[test]
+    fn eval_implicit_multiplication() {
+        let mut evaluator = Evaluator::new();
+        assert_eq!(evaluator.eval("3 01:30").unwrap_err().to_string(), "unexpected '01:30' after expression");
+
+        evaluator.implicit_multiplication(true);
+        assert_eq!(evaluator.eval("3 01:30").unwrap(), EvalResult::Time(Time::builder().minutes(4).seconds(30).build()));
+        assert_eq!(evaluator.eval("2(45:00)").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+        // The examples from the documentation.
+        assert_eq!(evaluator.eval("3 01:30").unwrap(), evaluator.eval("3 * 01:30").unwrap());
+        assert_eq!(evaluator.eval("2(45:00)").unwrap(), evaluator.eval("2 * (45:00)").unwrap());
+        assert_eq!(evaluator.eval("1h + 2 15m").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+        assert_eq!(evaluator.eval("x = 2 3").unwrap(), EvalResult::Number(dec!(6)));
+        assert_eq!(evaluator.eval("x 1h").unwrap(), EvalResult::Time(Time::builder().hours(6).build()));
+        assert_eq!(evaluator.eval("3 -2").unwrap(), EvalResult::Number(dec!(1)));
+        assert_eq!(evaluator.eval("(1h)(1h)").unwrap_err().to_string(), "cannot multiply a time by a time");
+    }
+
+    #[test]
+    fn eval_default_unit() {
+        let mut evaluator = Evaluator::new();
+        assert_eq!(evaluator.eval("1:00:00 + 30").unwrap_err().to_string(), "cannot add a time and a number");
+
+        evaluator.default_unit(Option::Some(TimeUnit::Minutes));
+        assert_eq!(evaluator.eval("1:00:00 + 30").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+        assert_eq!(evaluator.eval("30 + 1:00:00").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+        assert_eq!(evaluator.eval("1:00:00 - 1.5").unwrap(), EvalResult::Time(Time::builder().minutes(58).seconds(30).build()));
+        assert_eq!(evaluator.eval("30 - 1h").unwrap(), EvalResult::Time(Time::builder().negative().minutes(30).build()));
+        assert_eq!(evaluator.eval("1h + 10%").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(6).build()));
+        assert_eq!(evaluator.eval("1 + 2").unwrap(), EvalResult::Number(dec!(3)));
+        assert_eq!(evaluator.eval("1h * 2").unwrap(), EvalResult::Time(Time::builder().hours(2).build()));
+
+        let error = evaluator.eval("1h + 1000000000000000000").unwrap_err();
+        assert_eq!(error.to_string(), "result is too large");
+        assert_eq!(error.span(), Span::new(5, 24));
+
+        evaluator.default_unit(Option::Some(TimeUnit::Seconds));
+        assert_eq!(evaluator.eval("01:00 + 30").unwrap(), EvalResult::Time(Time::builder().minutes(1).seconds(30).build()));
+
+        evaluator.default_unit(Option::None);
+        assert_eq!(evaluator.eval("01:00 + 30").unwrap_err().to_string(), "cannot add a time and a number");
+    }
+
+    #[test]
+    fn eval_error_kind() {
+        assert_eq!(eval("1 / 0").unwrap_err().kind(), "divide_by_zero");
+        assert_eq!(eval("1h * 1h").unwrap_err().kind(), "multiply_times");
+        assert_eq!(eval("foo(1)").unwrap_err().kind(), "unknown_function");
+        assert_eq!(eval("(1").unwrap_err().kind(), "expected_right_paren");
+        assert_eq!(eval("1 @ 2").unwrap_err().kind(), "lex_error");
+        assert_eq!(eval("1:2").unwrap_err().kind(), "invalid_time");
+    }
+
+    #[test]
+    fn eval_is_complete() {
+        let evaluator = Evaluator::new();
+        assert!(evaluator.is_complete("1:30:00 + 45:00"));
+        assert!(evaluator.is_complete(""));
+        assert!(evaluator.is_complete(" \n"));
+        assert!(evaluator.is_complete("1 +* 2"));
+        assert!(evaluator.is_complete("(1))"));
+        assert!(!evaluator.is_complete("(1:30:00 +"));
+        assert!(!evaluator.is_complete("(1:30:00 +\n45:00"));
+        assert!(!evaluator.is_complete("x ="));
+        assert!(!evaluator.is_complete("1h >= 2h ? 1"));
+        assert!(!evaluator.is_complete("90m in"));
+        assert!(!evaluator.is_complete("abs(-1h"));
+        assert!(evaluator.is_complete("(1:30:00 +\n45:00)"));
+
+        assert!(Evaluator::new().is_complete("2 (1h"));
+        assert!(!Evaluator::new().implicit_multiplication(true).is_complete("2 (1h"));
+    }
+
+    #[test]
+    fn eval_conversion() {
+        assert_eval("1:30:00 in minutes", EvalResult::Number(dec!(90)));
+        assert_eval("90m to h", EvalResult::Number(dec!(1.5)));
+        assert_eval("1d 12:00:00 in days", EvalResult::Number(dec!(1.5)));
+        assert_eval("01:00 to ms", EvalResult::Number(dec!(60000)));
+        assert_eval("1s in nanoseconds", EvalResult::Number(dec!(1000000000)));
+        assert_eval("-45s in min", EvalResult::Number(dec!(-0.75)));
+        assert_eval("20m in h", EvalResult::Number(dec!(0.333333333)));
+        assert_eval("1h + 30m in m", EvalResult::Number(dec!(90)));
+        assert_eval("(1h in m) * 2", EvalResult::Number(dec!(120)));
+        assert_eval("round(100m in h, 1)", EvalResult::Number(dec!(1.7)));
+
+        assert_eval("90m to iso8601",
+            EvalResult::FormattedTime(Time::builder().hours(1).minutes(30).build(), TimeFormat::new(TimeStyle::Iso8601)));
+        assert_eq!(eval("90m to iso8601").unwrap().to_string(), "PT1H30M");
+        assert_eq!(eval("12:34.5 to hh:mm:ss.3").unwrap().to_string(), "00:12:34.500");
+        assert_eq!(eval("100:00:00 in days.0").unwrap().to_string(), "4d 04:00:00");
+
+        // Formatted times are plain times when used as operands.
+        assert_eval("(90m to iso8601) + 30m", EvalResult::Time(Time::builder().hours(2).build()));
+        assert_eval("90m to iso8601 to m", EvalResult::Number(dec!(90)));
+
+        assert_error("90 in m", "cannot convert a number to a unit or format", 0, 2);
+        assert_error("1h + (1h / 1m to iso8601)", "cannot convert a number to a unit or format", 6, 13);
+    }
+
+    #[test]
+    fn eval_conversion_precision() {
+        let mut evaluator = Evaluator::new();
+        evaluator.time_precision(TimeUnit::Seconds);
+        assert_eq!(evaluator.eval("1:02:03.5 to iso8601").unwrap(),
+            EvalResult::FormattedTime(Time::builder().hours(1).minutes(2).seconds(4).build(), TimeFormat::new(TimeStyle::Iso8601)));
+        evaluator.number_precision(2);
+        assert_eq!(evaluator.eval("20m in h").unwrap(), EvalResult::Number(dec!(0.33)));
+    }
+
+    #[test]
+    fn eval_variables() {
+        let mut evaluator = Evaluator::new();
+        assert_eq!(evaluator.eval("lap1 = 01:02.3").unwrap(), EvalResult::Time(Time::builder().minutes(1).seconds(2).nanoseconds(300000000).build()));
+        assert_eq!(evaluator.eval("lap2 = 58.7s").unwrap(), EvalResult::Time(Time::builder().seconds(58).nanoseconds(700000000).build()));
+        assert_eq!(evaluator.eval("lap1 + lap2").unwrap(), EvalResult::Time(Time::builder().minutes(2).seconds(1).build()));
+        assert_eq!(evaluator.eval("laps = 2").unwrap(), EvalResult::Number(dec!(2)));
+        assert_eq!(evaluator.eval("average = (lap1 + lap2) / laps").unwrap(), EvalResult::Time(Time::builder().minutes(1).seconds(0).nanoseconds(500000000).build()));
+        assert_eq!(evaluator.variable("average"), Option::Some(EvalResult::Time(Time::builder().minutes(1).seconds(0).nanoseconds(500000000).build())));
+        assert_eq!(evaluator.variable("lap3"), Option::None);
+
+        // Reassignment, including in terms of the previous value.
+        assert_eq!(evaluator.eval("laps = laps + 1").unwrap(), EvalResult::Number(dec!(3)));
+        assert_eq!(evaluator.eval("laps").unwrap(), EvalResult::Number(dec!(3)));
+
+        // Assigned values are rounded to the evaluator's precision.
+        evaluator.time_precision(TimeUnit::Seconds);
+        assert_eq!(evaluator.eval("rounded = lap1").unwrap(), EvalResult::Time(Time::builder().minutes(1).seconds(2).build()));
+        assert_eq!(evaluator.variable("rounded"), Option::Some(EvalResult::Time(Time::builder().minutes(1).seconds(2).build())));
+
+        // Type rules apply to variables.
+        let error = evaluator.eval("lap1 + laps").unwrap_err();
+        assert_eq!(error.to_string(), "cannot add a time and a number");
+        assert_eq!(error.span(), Span::new(0, 11));
+
+        // A failed assignment leaves the variable unchanged.
+        assert!(evaluator.eval("laps = lap1 * lap2").is_err());
+        assert_eq!(evaluator.variable("laps"), Option::Some(EvalResult::Number(dec!(3))));
+
+        let error = evaluator.eval("total = lap1 + lap3").unwrap_err();
+        assert_eq!(error.to_string(), "undefined variable 'lap3'");
+        assert_eq!(error.span(), Span::new(15, 19));
+        assert_eq!(evaluator.variable("total"), Option::None);
+    }
+
+    #[test]
+    fn eval_history() {
+        let mut evaluator = Evaluator::new();
+        assert_eq!(evaluator.history(), &[]);
+        let error = evaluator.eval("ans + 1").unwrap_err();
+        assert_eq!(error.to_string(), "there is no previous result");
+        assert_eq!(error.span(), Span::new(0, 3));
+
+        // Results are only available once recorded.
+        let result = evaluator.eval("1:30:00").unwrap();
+        assert!(evaluator.eval("_").is_err());
+        evaluator.record(result);
+        assert_eq!(evaluator.eval("ans").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+
+        let result = evaluator.eval("ans / 3").unwrap();
+        assert_eq!(result, EvalResult::Time(Time::builder().minutes(30).build()));
+        evaluator.record(result);
+        let result = evaluator.eval("$1 / _").unwrap();
+        assert_eq!(result, EvalResult::Number(dec!(3)));
+        evaluator.record(result);
+        assert_eq!(evaluator.history(), &[
+            EvalResult::Time(Time::builder().hours(1).minutes(30).build()),
+            EvalResult::Time(Time::builder().minutes(30).build()),
+            EvalResult::Number(dec!(3)),
+        ]);
+
+        assert_eq!(evaluator.eval("$2 * ans").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(30).build()));
+        assert_eq!(evaluator.eval("total = $1 + $2").unwrap(), EvalResult::Time(Time::builder().hours(2).build()));
+
+        // Type rules apply to history values.
+        let error = evaluator.eval("$1 + ans").unwrap_err();
+        assert_eq!(error.to_string(), "cannot add a time and a number");
+        assert_eq!(error.span(), Span::new(0, 8));
+        assert_eq!(evaluator.eval("$1 * $2").unwrap_err().to_string(), "cannot multiply a time by a time");
+        assert_eq!(evaluator.eval("ans / $1").unwrap_err().to_string(), "cannot divide a number by a time");
+
+        let error = evaluator.eval("1 + $4").unwrap_err();
+        assert_eq!(error.to_string(), "there is no result $4");
+        assert_eq!(error.span(), Span::new(4, 6));
+    }
+
+    #[test]
+    fn eval_precision() {
+        let mut evaluator = Evaluator::new();
+        assert_eq!(evaluator.eval("1:02:03.5").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(2).seconds(3).nanoseconds(500000000).build()));
+        assert_eq!(evaluator.eval("2 / 3").unwrap(), EvalResult::Number(dec!(0.666666667)));
+
+        evaluator.time_precision(TimeUnit::Seconds).number_precision(2);
+        assert_eq!(evaluator.eval("1:02:03.5").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(2).seconds(4).build()));
+        assert_eq!(evaluator.eval("1:02:03.4 * 1").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(2).seconds(3).build()));
+        assert_eq!(evaluator.eval("2 / 3").unwrap(), EvalResult::Number(dec!(0.67)));
+        assert_eq!(evaluator.eval("0.125").unwrap(), EvalResult::Number(dec!(0.13)));
+
+        evaluator.rounding_strategy(RoundingStrategy::MidpointNearestEven);
+        assert_eq!(evaluator.eval("1:02:02.5").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(2).seconds(2).build()));
+        assert_eq!(evaluator.eval("0.125").unwrap(), EvalResult::Number(dec!(0.12)));
+
+        evaluator.rounding_strategy(RoundingStrategy::ToNegativeInfinity).time_precision(TimeUnit::Minutes).number_precision(0);
+        assert_eq!(evaluator.eval("1:02:59").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(2).build()));
+        assert_eq!(evaluator.eval("-1:02:01").unwrap(), EvalResult::Time(Time::builder().negative().hours(1).minutes(3).build()));
+        assert_eq!(evaluator.eval("2 / 3").unwrap(), EvalResult::Number(dec!(0)));
+
+        evaluator.rounding_strategy(RoundingStrategy::ToPositiveInfinity);
+        assert_eq!(evaluator.eval("1:02:00.000000001").unwrap(), EvalResult::Time(Time::builder().hours(1).minutes(3).build()));
+        assert_eq!(evaluator.eval("1 / 3").unwrap(), EvalResult::Number(dec!(1)));
+
+        // Rounding which would overflow.
+        evaluator.time_precision(TimeUnit::Seconds);
+        match evaluator.eval("2562047788015215:30:07.5") {
+            Result::Err(EvalError::Overflow(span)) => assert_eq!(span, Span::new(0, 24)),
+            r => panic!("Expected overflow, but was {:?}", r),
+        }
+        evaluator.overflow_policy(OverflowPolicy::Saturating);
+        assert_eq!(evaluator.eval("2562047788015215:30:07.5").unwrap(),
+            EvalResult::Time(Time::builder().hours(2562047788015215).minutes(30).seconds(7).build()));
+    }
+
+    #[test]
+    fn eval_overflow_policy() {
+        let mut evaluator = Evaluator::new();
+
+        evaluator.overflow_policy(OverflowPolicy::Saturating);
+        assert_eq!(evaluator.eval("2562047788015215:30:07 + 1s").unwrap(), EvalResult::Time(Time::MAX));
+        assert_eq!(evaluator.eval("-2562047788015215:30:07 - 1s").unwrap(), EvalResult::Time(Time::MIN));
+        assert_eq!(evaluator.eval("2562047788015215:30:07 * -2").unwrap(), EvalResult::Time(Time::MIN));
+        assert_eq!(evaluator.eval("-2 * -2562047788015215:30:07").unwrap(), EvalResult::Time(Time::MAX));
+        assert_eq!(evaluator.eval("2562047788015215:30:07 / 0.5").unwrap(), EvalResult::Time(Time::MAX));
+        assert_eq!(evaluator.eval("(2562047788015215:30:07 + 1s) - 1s").unwrap(),
+            EvalResult::Time(Time::builder().hours(2562047788015215).minutes(30).seconds(6).nanoseconds(999999999).build()));
+        assert!(evaluator.eval("1:00:00 / 0").is_err());
+        assert!(evaluator.eval("100000000000000000000 * 100000000000000000000").is_err());
+
+        evaluator.overflow_policy(OverflowPolicy::Wrapping);
+        assert_eq!(evaluator.eval("2562047788015215:30:07.999999999 + 0.000000001s").unwrap(),
+            EvalResult::Time(Time::MIN));
+        assert_eq!(evaluator.eval("-2562047788015215:30:07.999999999 - 0.000000001s").unwrap(),
+            EvalResult::Time(Time::MAX));
+        assert_eq!(evaluator.eval("(2562047788015215:30:07 + 1s) - 1s").unwrap(),
+            EvalResult::Time(Time::builder().hours(2562047788015215).minutes(30).seconds(7).build()));
+
+        evaluator.overflow_policy(OverflowPolicy::Checked);
+        assert!(evaluator.eval("2562047788015215:30:07 + 1s").is_err());
+    }
+
+    fn eval(expr: &str) -> Result<EvalResult, EvalError> {
+        Evaluator::new().eval(expr)
+    }
+
     fn assert_eval(expr: &str, result: EvalResult) {
         assert_eq!(eval(expr).unwrap(), result)
     }
+
+    fn assert_error(expr: &str, message: &str, start: usize, end: usize) {
+        let error = eval(expr).unwrap_err();
+        assert_eq!(error.to_string(), message);
+        assert_eq!(error.span(), Span::new(start, end));
+    }
+
+    fn assert_overflow(expr: &str) {
+        match eval(expr) {
+            Result::Err(EvalError::Overflow(_)) => (),
+            r => panic!("Expected overflow, but was {:?}", r),
+        }
+    }
 }
@@ -1,2 +1,2 @@
-pub(super) mod eval;
-mod parse;
+pub(crate) mod eval;
+pub(crate) mod parse;
@@ -1,25 +1,53 @@
+use std::error;
+use std::fmt;
 use std::fmt::Error;
 use std::fmt::Formatter;
 use std::iter::Peekable;
 use std::result::Result;
 use std::slice::Iter;
-use std::str::Chars;
+use std::str::CharIndices;
 use std::str::FromStr;
 
 use rust_decimal::Decimal;
 
+use crate::span::Span;
+use crate::time::format::TimeFormat;
+use crate::time::ParseError as TimeParseError;
 use crate::time::Time;
+use crate::time::TimeUnit;
 
+/// A token in an expression.
 #[derive(Clone, PartialEq, Eq)]
-pub(crate) enum Token {
+#[non_exhaustive]
+pub enum Token {
     Time(String),
     Number(String),
+    Percentage(String),
     Plus,
     Hyphen,
     Slash,
+    DoubleSlash,
     Asterisk,
+    Percent,
+    Caret,
     LeftParen,
     RightParen,
+    Comma,
+    Equals,
+    EqualEqual,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Question,
+    Colon,
+    Identifier(String),
+    Answer,
+    History(String),
+    In,
+    To,
+    Target(String),
 }
 
 impl std::fmt::Display for Token {
@@ -27,12 +55,32 @@ impl std::fmt::Display for Token {
         match self {
             Token::Time(t) => write!(f, "{}", t),
             Token::Number(n) => write!(f, "{}", n),
+            Token::Percentage(n) => write!(f, "{}%", n),
             Token::Plus => write!(f, "+"),
             Token::Hyphen => write!(f, "-"),
             Token::Slash => write!(f, "/"),
+            Token::DoubleSlash => write!(f, "//"),
             Token::Asterisk => write!(f, "*"),
+            Token::Percent => write!(f, "%"),
+            Token::Caret => write!(f, "^"),
             Token::LeftParen => write!(f, "("),
             Token::RightParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+            Token::Equals => write!(f, "="),
+            Token::EqualEqual => write!(f, "=="),
+            Token::BangEqual => write!(f, "!="),
+            Token::Less => write!(f, "<"),
+            Token::LessEqual => write!(f, "<="),
+            Token::Greater => write!(f, ">"),
+            Token::GreaterEqual => write!(f, ">="),
+            Token::Question => write!(f, "?"),
+            Token::Colon => write!(f, ":"),
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::Answer => write!(f, "ans"),
+            Token::History(n) => write!(f, "${}", n),
+            Token::In => write!(f, "in"),
+            Token::To => write!(f, "to"),
+            Token::Target(target) => write!(f, "{}", target),
         }
     }
 }
@@ -44,34 +92,74 @@ impl std::fmt::Debug for Token {
 }
 
 struct Lexer<'a> {
-    chars: Peekable<Chars<'a>>,
-    tokens: Vec<Token>,
+    chars: Peekable<CharIndices<'a>>,
+    input_length: usize,
+    tokens: Vec<(Token, Span)>,
     scan_complete: bool,
 }
 
+/// An error which occurred while scanning an expression.
 #[derive(Debug)]
-pub(crate) enum LexError {
-    UnexpectedCharacter(char),
-    EndOfInput,
+#[non_exhaustive]
+pub enum LexError {
+    UnexpectedCharacter(char, Span),
+    EndOfInput(Span),
 }
 
+impl LexError {
+    /// Returns the location of the error in the expression.
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedCharacter(_, span) | LexError::EndOfInput(span) => *span,
+        }
+    }
+
+    /// Returns an identifier for the kind of error, such as `unexpected_character`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LexError::UnexpectedCharacter(_, _) => "unexpected_character",
+            LexError::EndOfInput(_) => "end_of_input",
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            LexError::UnexpectedCharacter(c, _) => write!(f, "unexpected character '{}'", c),
+            LexError::EndOfInput(_) => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+impl error::Error for LexError {}
+
 impl<'a> Lexer<'a> {
-    fn new(input: &str) -> Lexer {
+    fn new(input: &str) -> Lexer<'_> {
         Lexer {
-            chars: input.chars().peekable(),
+            chars: input.char_indices().peekable(),
+            input_length: input.len(),
             tokens: Vec::new(),
             scan_complete: false,
         }
     }
 
-    fn scan(&mut self) -> Result<Vec<Token>, Vec<LexError>> {
+    fn scan(&mut self) -> Result<Vec<(Token, Span)>, Vec<LexError>> {
         let mut errors = Vec::new();
         if !self.scan_complete {
-            while let Some(ch) = self.peek() {
-                if ch.is_numeric() {
+            while let Some(&ch) = self.peek() {
+                if ch.is_ascii_digit() {
                     if let Result::Err(e) = self.scan_number() {
                         errors.push(e);
                     }
+                } else if self.is_iso8601_start() {
+                    self.scan_iso8601();
+                } else if ch.is_alphabetic() || ch == '_' {
+                    self.scan_identifier();
+                } else if ch == '$' {
+                    if let Result::Err(e) = self.scan_history() {
+                        errors.push(e);
+                    }
                 } else if let Result::Err(e) = self.scan_character() {
                     errors.push(e)
                 }
@@ -87,68 +175,384 @@ impl<'a> Lexer<'a> {
     }
 
     fn scan_number(&mut self) -> Result<(), LexError> {
+        let start = self.position();
         let mut num = String::new();
         let mut is_time = false;
         loop {
             match self.peek() {
-                Option::Some(c) if c.is_digit(10) || *c == '.' => {
+                Option::Some(c) if c.is_ascii_digit() || *c == '.' => {
                     num.push(*c);
                     self.next();
                 }
-                Option::Some(c) if *c == ':' || *c == 's' => {
+                Option::Some(c) if *c == ':' => {
                     num.push(*c);
                     is_time = true;
                     self.next();
                 }
+                Option::Some(c) if c.is_alphabetic() => match self.unit_suffix() {
+                    Option::Some(suffix) => {
+                        for c in suffix.chars() {
+                            num.push(c);
+                            self.next();
+                        }
+                        is_time = true;
+
+                        // Whitespace between two unit-suffixed components, such as the space in
+                        // `1h 30m`, or between days and hours, such as the space in
+                        // `2d 03:04:05`, is part of the time.
+                        let is_days = suffix == TimeUnit::Days.suffix();
+                        if self.is_unit_separator(is_days) {
+                            while let Option::Some(c) = self.peek() {
+                                if !c.is_whitespace() {
+                                    break;
+                                }
+                                num.push(*c);
+                                self.next();
+                            }
+                        }
+                    }
+                    // Letters directly after a number which are not a unit, such as the `x` in
+                    // `24:3x`, are not the start of an identifier.
+                    Option::None => {
+                        let start = self.position();
+                        let c = self.next().unwrap();
+                        return Result::Err(LexError::UnexpectedCharacter(
+                            c,
+                            Span::new(start, self.position()),
+                        ));
+                    }
+                },
                 _ => break,
             }
         }
+        if !is_time && self.is_percent_suffix() {
+            self.next(); // Consume percent sign.
+            let span = Span::new(start, self.position());
+            self.tokens.push((Token::Percentage(num), span));
+            return Result::Ok(());
+        }
+
+        let span = Span::new(start, self.position());
         if is_time {
-            self.tokens.push(Token::Time(num));
+            self.tokens.push((Token::Time(num), span));
         } else {
-            self.tokens.push(Token::Number(num));
+            self.tokens.push((Token::Number(num), span));
+        }
+
+        Result::Ok(())
+    }
+
+    /// Returns `true` if the current position is the start of an ISO 8601 duration, that is a word
+    /// made up of a `P` followed by numbers with date designators, then optionally a `T` followed by
+    /// numbers with time designators. Any other word starting with `P`, such as `PTO`, is an
+    /// identifier.
+    fn is_iso8601_start(&self) -> bool {
+        let word: String = self
+            .chars
+            .clone()
+            .map(|(_, c)| c)
+            .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+            .collect();
+        let mut chars = match word.strip_prefix('P') {
+            Option::Some(rest) => rest.chars().peekable(),
+            Option::None => return false,
+        };
+
+        let mut components = 0;
+        let mut designators = "YMWD";
+        while let Option::Some(c) = chars.next() {
+            if c == 'T' && designators != "HMS" {
+                designators = "HMS";
+                continue;
+            }
+            // Each component is a number followed by a designator. The order of the components is
+            // checked when the duration is parsed.
+            if !c.is_ascii_digit() {
+                return false;
+            }
+            while chars.next_if(|c| c.is_ascii_digit() || *c == '.').is_some() {}
+            match chars.next() {
+                Option::Some(designator) if designators.contains(designator) => components += 1,
+                _ => return false,
+            }
+        }
+        components > 0
+    }
+
+    fn scan_iso8601(&mut self) {
+        let start = self.position();
+        let mut time = String::new();
+        while let Option::Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || c.is_ascii_uppercase() || *c == '.') {
+                break;
+            }
+            time.push(*c);
+            self.next();
+        }
+
+        let span = Span::new(start, self.position());
+        self.tokens.push((Token::Time(time), span));
+    }
+
+    fn scan_identifier(&mut self) {
+        let start = self.position();
+        let mut name = String::new();
+        while let Option::Some(c) = self.peek() {
+            if !(c.is_alphanumeric() || *c == '_') {
+                break;
+            }
+            name.push(*c);
+            self.next();
+        }
+
+        let span = Span::new(start, self.position());
+        let token = match name.as_str() {
+            "ans" | "_" => Token::Answer,
+            "in" => Token::In,
+            "to" => Token::To,
+            _ => Token::Identifier(name),
+        };
+        let is_conversion = token == Token::In || token == Token::To;
+        self.tokens.push((token, span));
+        if is_conversion {
+            self.scan_target();
+        }
+    }
+
+    /// Scans the unit or format following `in` or `to`. The target extends to the next whitespace,
+    /// closing parenthesis or comma, so that formats such as `hh:mm:ss.3` are a single token.
+    fn scan_target(&mut self) {
+        while let Option::Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.next();
         }
 
+        let start = self.position();
+        let mut target = String::new();
+        while let Option::Some(c) = self.peek() {
+            if c.is_whitespace() || *c == ')' || *c == ',' {
+                break;
+            }
+            target.push(*c);
+            self.next();
+        }
+
+        if !target.is_empty() {
+            let span = Span::new(start, self.position());
+            self.tokens.push((Token::Target(target), span));
+        }
+    }
+
+    /// Scans a reference to an earlier result, such as `$2`.
+    fn scan_history(&mut self) -> Result<(), LexError> {
+        let start = self.position();
+        self.next(); // Consume dollar sign.
+        let mut number = String::new();
+        while let Option::Some(c) = self.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            number.push(*c);
+            self.next();
+        }
+
+        let span = Span::new(start, self.position());
+        if number.is_empty() {
+            return Result::Err(LexError::UnexpectedCharacter(
+                '$',
+                Span::new(start, start + 1),
+            ));
+        }
+        self.tokens.push((Token::History(number), span));
         Result::Ok(())
     }
 
+    /// Returns the unit suffix at the current position, or `None` if the letters at the current
+    /// position are not a unit.
+    fn unit_suffix(&self) -> Option<String> {
+        let suffix: String = self
+            .chars
+            .clone()
+            .map(|(_, c)| c)
+            .take_while(|c| c.is_alphabetic())
+            .collect();
+        TimeUnit::from_suffix(&suffix).map(|_| suffix)
+    }
+
+    /// Returns `true` if the current position is a `%` which is the suffix of a percentage rather
+    /// than the remainder operator. That is, a `%` directly after a number which is not followed by
+    /// the start of an operand, such as the `%` in `20% + 1` but not the `%` in `20%3` or `20 % 3`.
+    fn is_percent_suffix(&self) -> bool {
+        let mut chars = self.chars.clone().map(|(_, c)| c);
+        if chars.next() != Option::Some('%') {
+            return false;
+        }
+        match chars.find(|c| !c.is_whitespace()) {
+            Option::Some(c) => !(c.is_alphanumeric() || "._$(".contains(c)),
+            Option::None => true,
+        }
+    }
+
+    /// Returns `true` if the current position is whitespace followed by a unit-suffixed
+    /// component, or by hours and minutes if `is_days` is `true`.
+    fn is_unit_separator(&self, is_days: bool) -> bool {
+        let mut chars = self.chars.clone().map(|(_, c)| c).peekable();
+        if !chars.peek().is_some_and(|c| c.is_whitespace()) {
+            return false;
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if !chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        while chars
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+        {
+            chars.next();
+        }
+        if is_days && chars.peek() == Option::Some(&':') {
+            return true;
+        }
+        let suffix: String = chars.take_while(|c| c.is_alphabetic()).collect();
+        TimeUnit::from_suffix(&suffix).is_some()
+    }
+
     fn scan_character(&mut self) -> Result<(), LexError> {
+        let start = self.position();
         let token = match self.next() {
             // Skip whitespace.
             Option::Some(c) if c.is_whitespace() => return Result::Ok(()),
             Option::Some('+') => Token::Plus,
             Option::Some('-') => Token::Hyphen,
-            Option::Some('/') => Token::Slash,
+            Option::Some('/') => {
+                if self.peek() == Option::Some(&'/') {
+                    self.next();
+                    Token::DoubleSlash
+                } else {
+                    Token::Slash
+                }
+            }
             Option::Some('*') => Token::Asterisk,
+            Option::Some('%') => Token::Percent,
+            Option::Some('^') => Token::Caret,
             Option::Some('(') => Token::LeftParen,
             Option::Some(')') => Token::RightParen,
-            Option::Some(c) => return Result::Err(LexError::UnexpectedCharacter(c)),
-            Option::None => return Result::Err(LexError::EndOfInput),
+            Option::Some(',') => Token::Comma,
+            Option::Some('?') => Token::Question,
+            Option::Some(':') => Token::Colon,
+            Option::Some('=') => {
+                if self.peek() == Option::Some(&'=') {
+                    self.next();
+                    Token::EqualEqual
+                } else {
+                    Token::Equals
+                }
+            }
+            Option::Some('!') if self.peek() == Option::Some(&'=') => {
+                self.next();
+                Token::BangEqual
+            }
+            Option::Some('<') => {
+                if self.peek() == Option::Some(&'=') {
+                    self.next();
+                    Token::LessEqual
+                } else {
+                    Token::Less
+                }
+            }
+            Option::Some('>') => {
+                if self.peek() == Option::Some(&'=') {
+                    self.next();
+                    Token::GreaterEqual
+                } else {
+                    Token::Greater
+                }
+            }
+            Option::Some(c) => {
+                return Result::Err(LexError::UnexpectedCharacter(
+                    c,
+                    Span::new(start, self.position()),
+                ))
+            }
+            Option::None => return Result::Err(LexError::EndOfInput(Span::new(start, start))),
         };
 
-        self.tokens.push(token);
+        let span = Span::new(start, self.position());
+        self.tokens.push((token, span));
         Result::Ok(())
     }
 
     fn peek(&mut self) -> Option<&char> {
-        self.chars.peek()
+        self.chars.peek().map(|(_, c)| c)
     }
 
     fn next(&mut self) -> Option<char> {
-        self.chars.next()
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    /// Returns the byte offset of the next character, or the length of the input if there are no
+    /// more characters.
+    fn position(&mut self) -> usize {
+        match self.chars.peek() {
+            Option::Some((i, _)) => *i,
+            Option::None => self.input_length,
+        }
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Stmt {
+    Expr(Expr),
+    Assign(String, Expr, Span),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) enum Expr {
-    Literal(Literal),
-    Binary(Box<Expr>, BinaryOp, Box<Expr>),
-    Unary(UnaryOp, Box<Expr>),
+    Literal(Literal, Span),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>, Span),
+    Comparison(Box<Expr>, ComparisonOp, Box<Expr>, Span),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>, Span),
+    Unary(UnaryOp, Box<Expr>, Span),
+    Call(String, Vec<Expr>, Span),
+    Variable(String, Span),
+    Answer(Span),
+    History(usize, Span),
+    Convert(Box<Expr>, Conversion, Span),
+}
+
+impl Expr {
+    /// Returns the location of the expression in the input.
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Expr::Literal(_, span)
+            | Expr::Binary(_, _, _, span)
+            | Expr::Comparison(_, _, _, span)
+            | Expr::Conditional(_, _, _, span)
+            | Expr::Unary(_, _, span)
+            | Expr::Call(_, _, span)
+            | Expr::Variable(_, span)
+            | Expr::Answer(span)
+            | Expr::History(_, span)
+            | Expr::Convert(_, _, span) => *span,
+        }
+    }
+}
+
+/// The target of an `in` or `to` conversion.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Conversion {
+    Unit(TimeUnit),
+    Format(TimeFormat),
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) enum Literal {
     Number(Decimal),
+    Percentage(Decimal),
     Time(Time),
 }
 
@@ -163,20 +567,150 @@ pub(crate) enum BinaryOp {
     Subtract,
     Multiply,
     Divide,
+    IntegerDivide,
+    Remainder,
+    Power,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ComparisonOp {
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
 }
 
 struct Parser<'a> {
-    tokens: Peekable<Iter<'a, Token>>,
+    tokens: Peekable<Iter<'a, (Token, Span)>>,
+    input_length: usize,
+    implicit_multiplication: bool,
 }
 
+/// An error which occurred while parsing an expression.
 #[derive(Debug)]
-pub(crate) enum ParseError {
+#[non_exhaustive]
+pub enum ParseError {
     LexError(Vec<LexError>),
-    InvalidTime(String),
-    InvalidNumber(String),
-    LeftoverTokens(Vec<Token>),
-    ExpectedRightParen(Option<Token>),
-    ExpectedLiteral(Option<Token>),
+    InvalidTime(String, TimeParseError, Span),
+    InvalidNumber(String, Span),
+    LeftoverTokens(Vec<Token>, Span),
+    ExpectedRightParen(Option<Token>, Span),
+    ExpectedLiteral(Option<Token>, Span),
+    InvalidHistory(String, Span),
+    ExpectedConversion(Option<Token>, Span),
+    UnknownConversion(String, Span),
+    ExpectedColon(Option<Token>, Span),
+}
+
+impl ParseError {
+    /// Returns the location of the error in the expression.
+    ///
+    /// For an invalid time, this is the location of the error within the time, if known.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::LexError(errors) => errors
+                .iter()
+                .map(LexError::span)
+                .fold(errors[0].span(), Span::to),
+            ParseError::InvalidTime(_, error, span) => error.span().offset(span.start),
+            ParseError::InvalidNumber(_, span)
+            | ParseError::LeftoverTokens(_, span)
+            | ParseError::ExpectedRightParen(_, span)
+            | ParseError::ExpectedLiteral(_, span)
+            | ParseError::InvalidHistory(_, span)
+            | ParseError::ExpectedConversion(_, span)
+            | ParseError::UnknownConversion(_, span)
+            | ParseError::ExpectedColon(_, span) => *span,
+        }
+    }
+
+    /// Returns an identifier for the kind of error, such as `expected_right_paren`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::LexError(_) => "lex_error",
+            ParseError::InvalidTime(_, _, _) => "invalid_time",
+            ParseError::InvalidNumber(_, _) => "invalid_number",
+            ParseError::LeftoverTokens(_, _) => "leftover_tokens",
+            ParseError::ExpectedRightParen(_, _) => "expected_right_paren",
+            ParseError::ExpectedLiteral(_, _) => "expected_literal",
+            ParseError::InvalidHistory(_, _) => "invalid_history",
+            ParseError::ExpectedConversion(_, _) => "expected_conversion",
+            ParseError::UnknownConversion(_, _) => "unknown_conversion",
+            ParseError::ExpectedColon(_, _) => "expected_colon",
+        }
+    }
+
+    /// Returns whether the error is caused by the input ending before the expression is complete,
+    /// such as `(1:30:00 +`, so that more input could make it valid.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self,
+            ParseError::ExpectedRightParen(Option::None, _)
+                | ParseError::ExpectedLiteral(Option::None, _)
+                | ParseError::ExpectedConversion(Option::None, _)
+                | ParseError::ExpectedColon(Option::None, _)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParseError::LexError(errors) => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Result::Ok(())
+            }
+            ParseError::InvalidTime(t, error, _) => write!(f, "invalid time '{}': {}", t, error),
+            ParseError::InvalidNumber(n, _) => write!(f, "invalid number '{}'", n),
+            ParseError::LeftoverTokens(tokens, _) => {
+                write!(f, "unexpected '{}' after expression", tokens[0])
+            }
+            ParseError::ExpectedRightParen(Option::Some(t), _) => {
+                write!(f, "expected ')', found '{}'", t)
+            }
+            ParseError::ExpectedRightParen(Option::None, _) => {
+                write!(f, "expected ')', found end of input")
+            }
+            ParseError::ExpectedLiteral(Option::Some(t), _) => {
+                write!(f, "expected a time or number, found '{}'", t)
+            }
+            ParseError::ExpectedLiteral(Option::None, _) => {
+                write!(f, "expected a time or number, found end of input")
+            }
+            ParseError::InvalidHistory(n, _) => write!(f, "invalid result number '${}'", n),
+            ParseError::ExpectedConversion(Option::Some(t), _) => {
+                write!(f, "expected a unit or format, found '{}'", t)
+            }
+            ParseError::ExpectedConversion(Option::None, _) => {
+                write!(f, "expected a unit or format, found end of input")
+            }
+            ParseError::UnknownConversion(target, _) => {
+                write!(f, "unknown unit or format '{}'", target)
+            }
+            ParseError::ExpectedColon(Option::Some(t), _) => {
+                write!(f, "expected ':', found '{}'", t)
+            }
+            ParseError::ExpectedColon(Option::None, _) => {
+                write!(f, "expected ':', found end of input")
+            }
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ParseError::InvalidTime(_, error, _) => Option::Some(error),
+            _ => Option::None,
+        }
+    }
 }
 
 impl std::convert::From<Vec<LexError>> for ParseError {
@@ -186,42 +720,130 @@ impl std::convert::From<Vec<LexError>> for ParseError {
 }
 
 impl<'a> Parser<'a> {
-    fn new(tokens: &'a [Token]) -> Parser<'a> {
+    fn new(
+        tokens: &'a [(Token, Span)],
+        input_length: usize,
+        implicit_multiplication: bool,
+    ) -> Parser<'a> {
         Parser {
             tokens: tokens.iter().peekable(),
+            input_length,
+            implicit_multiplication,
         }
     }
 
-    fn parse(&mut self) -> Result<Expr, ParseError> {
-        let expr = self.expression();
+    fn parse(&mut self) -> Result<Stmt, ParseError> {
+        let stmt = self.statement()?;
         if self.peek().is_some() {
-            return Result::Err(ParseError::LeftoverTokens(self.remaining_tokens()));
+            let (tokens, span) = self.remaining_tokens();
+            return Result::Err(ParseError::LeftoverTokens(tokens, span));
+        }
+
+        Result::Ok(stmt)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        let mut lookahead = self.tokens.clone();
+        if let (
+            Option::Some((Token::Identifier(name), name_span)),
+            Option::Some((Token::Equals, _)),
+        ) = (lookahead.next(), lookahead.next())
+        {
+            self.next(); // Consume identifier.
+            self.next(); // Consume equals.
+            let expr = self.expression()?;
+            let span = name_span.to(expr.span());
+            return Result::Ok(Stmt::Assign(name.clone(), expr, span));
         }
 
-        expr
+        Result::Ok(Stmt::Expr(self.expression()?))
     }
 
+    /// Parses an expression, which is either a comparison or a conditional expression of the form
+    /// `condition ? a : b`. Conditional expressions are right associative, so `a ? b : c ? d : e`
+    /// is `a ? b : (c ? d : e)`.
     fn expression(&mut self) -> Result<Expr, ParseError> {
-        self.addition()
+        let condition = self.comparison()?;
+        if self.peek() != Option::Some(&Token::Question) {
+            return Result::Ok(condition);
+        }
+        self.next(); // Consume question mark.
+        let then_expr = self.expression()?;
+        self.colon()?;
+        let else_expr = self.expression()?;
+        let span = condition.span().to(else_expr.span());
+        Result::Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then_expr),
+            Box::new(else_expr),
+            span,
+        ))
+    }
+
+    /// Parses at most one comparison. Comparisons do not chain, so `a < b < c` is an error.
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.conversion_expression()?;
+        let op = match self.peek() {
+            Option::Some(Token::Less) => ComparisonOp::Less,
+            Option::Some(Token::LessEqual) => ComparisonOp::LessEqual,
+            Option::Some(Token::EqualEqual) => ComparisonOp::Equal,
+            Option::Some(Token::BangEqual) => ComparisonOp::NotEqual,
+            Option::Some(Token::Greater) => ComparisonOp::Greater,
+            Option::Some(Token::GreaterEqual) => ComparisonOp::GreaterEqual,
+            _ => return Result::Ok(expr),
+        };
+        self.next(); // Consume comparison operator.
+        let right = self.conversion_expression()?;
+        let span = expr.span().to(right.span());
+        Result::Ok(Expr::Comparison(Box::new(expr), op, Box::new(right), span))
+    }
+
+    fn conversion_expression(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.addition()?;
+        while let Option::Some(Token::In) | Option::Some(Token::To) = self.peek() {
+            self.next(); // Consume `in` or `to`.
+            let (conversion, target_span) = self.conversion()?;
+            let span = expr.span().to(target_span);
+            expr = Expr::Convert(Box::new(expr), conversion, span);
+        }
+        Result::Ok(expr)
+    }
+
+    /// Parses the target of a conversion, which is a unit if it is a unit name and otherwise a
+    /// format.
+    fn conversion(&mut self) -> Result<(Conversion, Span), ParseError> {
+        match self.next() {
+            Option::Some((Token::Target(target), span)) => {
+                if let Option::Some(unit) = TimeUnit::from_name(target) {
+                    return Result::Ok((Conversion::Unit(unit), span));
+                }
+                match TimeFormat::from_str(target) {
+                    Result::Ok(format) => Result::Ok((Conversion::Format(format), span)),
+                    Result::Err(_) => {
+                        Result::Err(ParseError::UnknownConversion(target.clone(), span))
+                    }
+                }
+            }
+            Option::Some((t, span)) => Result::Err(ParseError::ExpectedConversion(
+                Option::Some(t.clone()),
+                span,
+            )),
+            Option::None => Result::Err(ParseError::ExpectedConversion(
+                Option::None,
+                self.end_of_input(),
+            )),
+        }
     }
 
     fn addition(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.multiplication()?;
         while let Option::Some(token) = self.peek() {
-            if token == &&Token::Plus {
+            if token == &Token::Plus {
                 self.next(); // Consume plus
-                expr = Expr::Binary(
-                    Box::new(expr),
-                    BinaryOp::Add,
-                    Box::new(self.multiplication()?),
-                );
-            } else if token == &&Token::Hyphen {
+                expr = binary(expr, BinaryOp::Add, self.multiplication()?);
+            } else if token == &Token::Hyphen {
                 self.next(); // Consume hyphen.
-                expr = Expr::Binary(
-                    Box::new(expr),
-                    BinaryOp::Subtract,
-                    Box::new(self.multiplication()?),
-                );
+                expr = binary(expr, BinaryOp::Subtract, self.multiplication()?);
             } else {
                 break;
             }
@@ -232,12 +854,22 @@ impl<'a> Parser<'a> {
     fn multiplication(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.unary()?;
         while let Option::Some(token) = self.peek() {
-            if token == &&Token::Asterisk {
+            if token == &Token::Asterisk {
                 self.next(); // Consume asterisk.
-                expr = Expr::Binary(Box::new(expr), BinaryOp::Multiply, Box::new(self.unary()?));
-            } else if token == &&Token::Slash {
+                expr = binary(expr, BinaryOp::Multiply, self.unary()?);
+            } else if token == &Token::Slash {
                 self.next(); // Consume slash.
-                expr = Expr::Binary(Box::new(expr), BinaryOp::Divide, Box::new(self.unary()?));
+                expr = binary(expr, BinaryOp::Divide, self.unary()?);
+            } else if token == &Token::DoubleSlash {
+                self.next(); // Consume double slash.
+                expr = binary(expr, BinaryOp::IntegerDivide, self.unary()?);
+            } else if token == &Token::Percent {
+                self.next(); // Consume percent sign.
+                expr = binary(expr, BinaryOp::Remainder, self.unary()?);
+            } else if starts_value(token) && self.implicit_multiplication {
+                // The operand of an implicit multiplication can not be negated, so that `3 -2` is
+                // still a subtraction.
+                expr = binary(expr, BinaryOp::Multiply, self.power()?);
             } else {
                 break;
             }
@@ -246,81 +878,201 @@ impl<'a> Parser<'a> {
     }
 
     fn unary(&mut self) -> Result<Expr, ParseError> {
-        if self.peek() == Option::Some(&&Token::Hyphen) {
-            self.next(); // Consume hyphen.
-            Result::Ok(Expr::Unary(UnaryOp::Negative, Box::new(self.value()?)))
+        if self.peek() == Option::Some(&Token::Hyphen) {
+            let (_, hyphen_span) = self.next().unwrap(); // Consume hyphen.
+            let operand = self.power()?;
+            let span = hyphen_span.to(operand.span());
+            Result::Ok(Expr::Unary(UnaryOp::Negative, Box::new(operand), span))
         } else {
-            self.value()
+            self.power()
+        }
+    }
+
+    /// Parses an exponentiation, which is right associative and has higher precedence than
+    /// negation, so `-2^2` is `-(2^2)` and `2^3^2` is `2^(3^2)`.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.value()?;
+        if self.peek() == Option::Some(&Token::Caret) {
+            self.next(); // Consume caret.
+            let exponent = self.unary()?;
+            return Result::Ok(binary(base, BinaryOp::Power, exponent));
         }
+        Result::Ok(base)
     }
 
     fn value(&mut self) -> Result<Expr, ParseError> {
         match self.next() {
-            Option::Some(Token::Number(n)) => match Decimal::from_str(n) {
-                Result::Ok(number) => Result::Ok(Expr::Literal(Literal::Number(number))),
-                Result::Err(_) => Result::Err(ParseError::InvalidNumber(n.to_string())),
+            Option::Some((Token::Number(n), span)) => match Decimal::from_str(n) {
+                Result::Ok(number) => Result::Ok(Expr::Literal(Literal::Number(number), span)),
+                Result::Err(_) => Result::Err(ParseError::InvalidNumber(n.to_string(), span)),
             },
-            Option::Some(Token::Time(t)) => match Time::from_str(t) {
-                Result::Ok(time) => Result::Ok(Expr::Literal(Literal::Time(time))),
-                Result::Err(_) => Result::Err(ParseError::InvalidTime(t.to_string())),
+            Option::Some((Token::Percentage(n), span)) => match Decimal::from_str(n) {
+                Result::Ok(number) => Result::Ok(Expr::Literal(Literal::Percentage(number), span)),
+                Result::Err(_) => Result::Err(ParseError::InvalidNumber(n.to_string(), span)),
             },
-            Option::Some(Token::LeftParen) => {
-                let expr = self.expression();
-                match self.next() {
-                    Option::Some(Token::RightParen) => (),
-                    Option::Some(t) => {
-                        return Result::Err(ParseError::ExpectedRightParen(Option::Some(t.clone())))
-                    }
-                    Option::None => {
-                        return Result::Err(ParseError::ExpectedRightParen(Option::None))
-                    }
+            Option::Some((Token::Time(t), span)) => match Time::from_str(t) {
+                Result::Ok(time) => Result::Ok(Expr::Literal(Literal::Time(time), span)),
+                Result::Err(e) => Result::Err(ParseError::InvalidTime(t.to_string(), e, span)),
+            },
+            Option::Some((Token::Answer, span)) => Result::Ok(Expr::Answer(span)),
+            Option::Some((Token::History(n), span)) => match usize::from_str(n) {
+                Result::Ok(number) if number > 0 => Result::Ok(Expr::History(number, span)),
+                _ => Result::Err(ParseError::InvalidHistory(n.to_string(), span)),
+            },
+            Option::Some((Token::LeftParen, _)) => {
+                let expr = self.expression()?;
+                self.right_paren()?;
+                Result::Ok(expr)
+            }
+            Option::Some((Token::Identifier(name), span)) => {
+                if self.peek() == Option::Some(&Token::LeftParen) {
+                    self.call(name, span)
+                } else {
+                    Result::Ok(Expr::Variable(name.clone(), span))
                 }
-                expr
             }
-            Option::Some(token) => {
-                Result::Err(ParseError::ExpectedLiteral(Option::Some(token.clone())))
+            Option::Some((token, span)) => Result::Err(ParseError::ExpectedLiteral(
+                Option::Some(token.clone()),
+                span,
+            )),
+            Option::None => Result::Err(ParseError::ExpectedLiteral(
+                Option::None,
+                self.end_of_input(),
+            )),
+        }
+    }
+
+    /// Parses the parenthesised, comma-separated arguments of a call to the function `name`.
+    fn call(&mut self, name: &str, name_span: Span) -> Result<Expr, ParseError> {
+        self.next(); // Consume left parenthesis.
+        let mut args = Vec::new();
+        if self.peek() != Option::Some(&Token::RightParen) {
+            args.push(self.expression()?);
+            while self.peek() == Option::Some(&Token::Comma) {
+                self.next(); // Consume comma.
+                args.push(self.expression()?);
             }
-            Option::None => Result::Err(ParseError::ExpectedLiteral(Option::None)),
         }
+        let end = self.right_paren()?;
+
+        Result::Ok(Expr::Call(name.to_string(), args, name_span.to(end)))
     }
 
-    fn peek(&mut self) -> Option<&&Token> {
-        self.tokens.peek()
+    /// Consumes a closing parenthesis, returning its location.
+    fn right_paren(&mut self) -> Result<Span, ParseError> {
+        match self.next() {
+            Option::Some((Token::RightParen, span)) => Result::Ok(span),
+            Option::Some((t, span)) => Result::Err(ParseError::ExpectedRightParen(
+                Option::Some(t.clone()),
+                span,
+            )),
+            Option::None => Result::Err(ParseError::ExpectedRightParen(
+                Option::None,
+                self.end_of_input(),
+            )),
+        }
     }
 
-    fn next(&mut self) -> Option<&Token> {
-        self.tokens.next()
+    /// Consumes the colon which separates the branches of a conditional expression.
+    fn colon(&mut self) -> Result<(), ParseError> {
+        match self.next() {
+            Option::Some((Token::Colon, _)) => Result::Ok(()),
+            Option::Some((t, span)) => {
+                Result::Err(ParseError::ExpectedColon(Option::Some(t.clone()), span))
+            }
+            Option::None => {
+                Result::Err(ParseError::ExpectedColon(Option::None, self.end_of_input()))
+            }
+        }
     }
 
-    fn remaining_tokens(&mut self) -> Vec<Token> {
+    fn peek(&mut self) -> Option<&Token> {
+        self.tokens.peek().map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<(&'a Token, Span)> {
+        self.tokens.next().map(|(token, span)| (token, *span))
+    }
+
+    fn end_of_input(&self) -> Span {
+        Span::new(self.input_length, self.input_length)
+    }
+
+    fn remaining_tokens(&mut self) -> (Vec<Token>, Span) {
         let mut result = Vec::new();
+        let mut span = Option::None;
 
-        while let Option::Some(t) = self.tokens.next() {
+        for (t, s) in self.tokens.by_ref() {
             result.push(t.clone());
+            span = Option::Some(span.map_or(*s, |span: Span| span.to(*s)));
         }
 
-        result
+        (result, span.unwrap_or_else(|| self.end_of_input()))
     }
 }
 
-pub(crate) fn parse_expression(expr: &str) -> Result<Expr, ParseError> {
-    Parser::new(&Lexer::new(expr).scan()?).parse()
+/// Returns a binary expression which spans both operands.
+fn binary(left: Expr, op: BinaryOp, right: Expr) -> Expr {
+    let span = left.span().to(right.span());
+    Expr::Binary(Box::new(left), op, Box::new(right), span)
+}
+
+/// Returns `true` if `token` can be the first token of a value, such as a number, a time, a
+/// variable or a parenthesised expression.
+fn starts_value(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Number(_)
+            | Token::Percentage(_)
+            | Token::Time(_)
+            | Token::LeftParen
+            | Token::Identifier(_)
+            | Token::Answer
+            | Token::History(_)
+    )
+}
+
+/// Parses a statement, which is either an expression or an assignment of an expression to a
+/// variable.
+///
+/// If `implicit_multiplication` is `true`, values which are written next to each other are
+/// multiplied, so `3 01:30` is `3 * 01:30` and `2(45:00)` is `2 * (45:00)`.
+pub(crate) fn parse_statement(
+    stmt: &str,
+    implicit_multiplication: bool,
+) -> Result<Stmt, ParseError> {
+    Parser::new(
+        &Lexer::new(stmt).scan()?,
+        stmt.len(),
+        implicit_multiplication,
+    )
+    .parse()
 }
 
 #[cfg(test)]
 #[rustfmt::skip]
 mod tests {
-    use super::parse_expression;
+    use super::parse_statement;
+    use super::ComparisonOp;
+    use super::Conversion;
     use super::Expr;
     use super::Lexer;
     use super::Literal;
+    use super::ParseError;
+    use super::Stmt;
     use super::Token;
     use super::Token::*;
     use crate::calc::parse::BinaryOp;
     use crate::calc::parse::UnaryOp;
+    use crate::span::Span;
+    use crate::time::format::TimeFormat;
+    use crate::time::format::TimeStyle;
     use crate::time::Time;
+    use crate::time::TimeUnit;
     use rust_decimal_macros::dec;
 
+    const S: Span = Span { start: 0, end: 0 };
+
     #[test]
     fn scan_single_token() {
         // Single character token.
@@ -328,6 +1080,8 @@ mod tests {
         assert_scan_tokens("-", vec![Hyphen]);
         assert_scan_tokens("/", vec![Slash]);
         assert_scan_tokens("*", vec![Asterisk]);
+        assert_scan_tokens("//", vec![DoubleSlash]);
+        assert_scan_tokens("%", vec![Percent]);
         assert_scan_tokens("(", vec![LeftParen]);
         assert_scan_tokens(")", vec![RightParen]);
 
@@ -348,6 +1102,27 @@ mod tests {
         assert_scan_tokens("123.456:789", vec![Time("123.456:789".to_string())]);
         assert_scan_tokens("123:456.789", vec![Time("123:456.789".to_string())]);
 
+        // ISO 8601 durations.
+        assert_scan_tokens("PT1H30M", vec![Time("PT1H30M".to_string())]);
+        assert_scan_tokens("P2DT3H4.5S", vec![Time("P2DT3H4.5S".to_string())]);
+        assert_scan_tokens("P1Y", vec![Time("P1Y".to_string())]);
+        assert_scan_tokens("P1W", vec![Time("P1W".to_string())]);
+
+        // Other words starting with 'P' are identifiers.
+        assert_scan_tokens("PTO = 1h", vec![Identifier("PTO".to_string()), Equals, Time("1h".to_string())]);
+        assert_scan_tokens("P2 = 3", vec![Identifier("P2".to_string()), Equals, Number("3".to_string())]);
+        assert_scan_tokens("PT P1 PT1H2 P1Dx", vec![Identifier("PT".to_string()), Identifier("P1".to_string()),
+            Identifier("PT1H2".to_string()), Identifier("P1Dx".to_string())]);
+
+        // Unit-suffixed times.
+        assert_scan_tokens("2.5h", vec![Time("2.5h".to_string())]);
+        assert_scan_tokens("250ms", vec![Time("250ms".to_string())]);
+        assert_scan_tokens("3µs", vec![Time("3µs".to_string())]);
+        assert_scan_tokens("1h2m3.5s", vec![Time("1h2m3.5s".to_string())]);
+        assert_scan_tokens("1h 30m", vec![Time("1h 30m".to_string())]);
+        assert_scan_tokens("1d  2h\t3m", vec![Time("1d  2h\t3m".to_string())]);
+        assert_scan_tokens("2d 03:04:05", vec![Time("2d 03:04:05".to_string())]);
+
 
         // Valid numbers.
         assert_scan_tokens("0", vec![Number(0.to_string())]);
@@ -368,11 +1143,21 @@ mod tests {
         // Single character tokens.
         assert_scan_tokens("+-", vec![Plus, Hyphen]);
         assert_scan_tokens("/*", vec![Slash, Asterisk]);
+        assert_scan_tokens("///%", vec![DoubleSlash, Slash, Percent]);
+        assert_scan_tokens("/ /", vec![Slash, Slash]);
         assert_scan_tokens("+-/*", vec![Plus, Hyphen, Slash, Asterisk]);
         assert_scan_tokens("*+/-", vec![Asterisk, Plus, Slash, Hyphen]);
 
         // Valid expressions.
         assert_scan_tokens("-123", vec![Hyphen, Number("123".to_string())]);
+        assert_scan_tokens("1h 30m + 45m", vec![Time("1h 30m".to_string()), Plus, Time("45m".to_string())]);
+        assert_scan_tokens("250ms * 12", vec![Time("250ms".to_string()), Asterisk, Number("12".to_string())]);
+        assert_scan_tokens("1h 2", vec![Time("1h".to_string()), Number("2".to_string())]);
+        assert_scan_tokens("1h (2m)", vec![Time("1h".to_string()), LeftParen, Time("2m".to_string()), RightParen]);
+        assert_scan_tokens("round(1h, 15m)",
+            vec![Identifier("round".to_string()), LeftParen, Time("1h".to_string()), Comma, Time("15m".to_string()), RightParen]);
+        assert_scan_tokens("floor_2(x)",
+            vec![Identifier("floor_2".to_string()), LeftParen, Identifier("x".to_string()), RightParen]);
         assert_scan_tokens("-97:53:10.2468", vec![Hyphen, Time("97:53:10.2468".to_string())]);
         assert_scan_tokens("11:11:11 + 11:11:11",
             vec![Time("11:11:11".to_string()), Plus, Time("11:11:11".to_string())]);
@@ -436,77 +1221,109 @@ mod tests {
     fn parse_simple_expression() {
         assert_parse_expression("1 + 2",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Number(dec!(1)))),
+                Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
                 BinaryOp::Add,
-                Box::new(Expr::Literal(Literal::Number(dec!(2))))
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), S)), S
             )
         );
 
         assert_parse_expression("3 - 4",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Number(dec!(3)))),
+                Box::new(Expr::Literal(Literal::Number(dec!(3)), S)),
                 BinaryOp::Subtract,
-                Box::new(Expr::Literal(Literal::Number(dec!(4))))
+                Box::new(Expr::Literal(Literal::Number(dec!(4)), S)), S
             )
         );
 
         assert_parse_expression("5 * 6",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Number(dec!(5)))),
+                Box::new(Expr::Literal(Literal::Number(dec!(5)), S)),
                 BinaryOp::Multiply,
-                Box::new(Expr::Literal(Literal::Number(dec!(6))))
+                Box::new(Expr::Literal(Literal::Number(dec!(6)), S)), S
             )
         );
 
         assert_parse_expression("7 / 8",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Number(dec!(7)))),
+                Box::new(Expr::Literal(Literal::Number(dec!(7)), S)),
                 BinaryOp::Divide,
-                Box::new(Expr::Literal(Literal::Number(dec!(8))))
+                Box::new(Expr::Literal(Literal::Number(dec!(8)), S)), S
             )
         );
 
         assert_parse_expression("-9",
-            Expr::Unary(UnaryOp::Negative, Box::new(Expr::Literal(Literal::Number(dec!(9))))));
+            Expr::Unary(UnaryOp::Negative, Box::new(Expr::Literal(Literal::Number(dec!(9)), S)), S));
 
 
         assert_parse_expression("10s + 00:20",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Time(Time::builder().seconds(10).build()))),
+                Box::new(Expr::Literal(Literal::Time(Time::builder().seconds(10).build()), S)),
                 BinaryOp::Add,
-                Box::new(Expr::Literal(Literal::Time(Time::builder().seconds(20).build())))
+                Box::new(Expr::Literal(Literal::Time(Time::builder().seconds(20).build()), S)), S
             )
         );
 
         assert_parse_expression("30:00 - 00:40:00",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(30).build()))),
+                Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(30).build()), S)),
                 BinaryOp::Subtract,
-                Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(40).build())))
+                Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(40).build()), S)), S
             )
         );
 
         assert_parse_expression("5:00:00 * 123.456",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(5).build()))),
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(5).build()), S)),
                 BinaryOp::Multiply,
-                Box::new(Expr::Literal(Literal::Number(dec!(123.456))))
+                Box::new(Expr::Literal(Literal::Number(dec!(123.456)), S)), S
             )
         );
 
         assert_parse_expression("66:00:00 / 77:00:00",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(66).build()))),
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(66).build()), S)),
                 BinaryOp::Divide,
-                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(77).build())))
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(77).build()), S)), S
             )
         );
 
         assert_parse_expression("8:08:08 / 987.654",
             Expr::Binary(
-                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(8).minutes(8).seconds(8).build()))),
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(8).minutes(8).seconds(8).build()), S)),
                 BinaryOp::Divide,
-                Box::new(Expr::Literal(Literal::Number(dec!(987.654))))
+                Box::new(Expr::Literal(Literal::Number(dec!(987.654)), S)), S
+            )
+        );
+
+        assert_parse_expression("3:10:00 // 25:00",
+            Expr::Binary(
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(3).minutes(10).build()), S)),
+                BinaryOp::IntegerDivide,
+                Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(25).build()), S)), S
+            )
+        );
+
+        assert_parse_expression("3:10:00 % 25:00",
+            Expr::Binary(
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(3).minutes(10).build()), S)),
+                BinaryOp::Remainder,
+                Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(25).build()), S)), S
+            )
+        );
+
+        assert_parse_expression("1 + 7 % 2 * 3",
+            Expr::Binary(
+                Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                BinaryOp::Add,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Binary(
+                        Box::new(Expr::Literal(Literal::Number(dec!(7)), S)),
+                        BinaryOp::Remainder,
+                        Box::new(Expr::Literal(Literal::Number(dec!(2)), S)), S
+                    )),
+                    BinaryOp::Multiply,
+                    Box::new(Expr::Literal(Literal::Number(dec!(3)), S)), S
+                )), S
             )
         );
 
@@ -515,7 +1332,7 @@ mod tests {
                 UnaryOp::Negative,
                 Box::new(Expr::Literal(Literal::Time(
                     Time::builder().hours(9876).minutes(54).seconds(32).nanoseconds(100000000).build()
-                )))
+                ), S)), S
             )
         );
     }
@@ -525,20 +1342,20 @@ mod tests {
         assert_parse_expression("1+2*3-4/5",
             Expr::Binary(
                 Box::new(Expr::Binary(
-                    Box::new(Expr::Literal(Literal::Number(dec!(1)))),
+                    Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
                     BinaryOp::Add,
                     Box::new(Expr::Binary(
-                        Box::new(Expr::Literal(Literal::Number(dec!(2)))),
+                        Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
                         BinaryOp::Multiply,
-                        Box::new(Expr::Literal(Literal::Number(dec!(3))))
-                    ))
+                        Box::new(Expr::Literal(Literal::Number(dec!(3)), S)), S
+                    )), S
                 )),
                 BinaryOp::Subtract,
                 Box::new(Expr::Binary(
-                    Box::new(Expr::Literal(Literal::Number(dec!(4)))),
+                    Box::new(Expr::Literal(Literal::Number(dec!(4)), S)),
                     BinaryOp::Divide,
-                    Box::new(Expr::Literal(Literal::Number(dec!(5))))
-                ))
+                    Box::new(Expr::Literal(Literal::Number(dec!(5)), S)), S
+                )), S
             )
         );
 
@@ -547,28 +1364,28 @@ mod tests {
                 Box::new(Expr::Binary(
                     Box::new(Expr::Literal(Literal::Time(
                         Time::builder().hours(1).build()
-                    ))),
+                    ), S)),
                     BinaryOp::Subtract,
                     Box::new(Expr::Binary(
                         Box::new(Expr::Binary(
                             Box::new(Expr::Literal(Literal::Time(
                                 Time::builder().minutes(22).seconds(22).build()
-                            ))),
+                            ), S)),
                             BinaryOp::Divide,
                             Box::new(Expr::Literal(Literal::Time(
                                 Time::builder().minutes(30).build()
-                            )))
+                            ), S)), S
                         )),
                         BinaryOp::Multiply,
                         Box::new(Expr::Literal(Literal::Time(
                             Time::builder().hours(4).minutes(4).seconds(4).build()
-                        )))
-                    ))
+                        ), S)), S
+                    )), S
                 )),
                 BinaryOp::Add,
                 Box::new(Expr::Literal(Literal::Time(
                     Time::builder().hours(5).minutes(55).seconds(55).build()
-                )))
+                ), S)), S
             )
         );
 
@@ -581,45 +1398,86 @@ mod tests {
                             UnaryOp::Negative,
                             Box::new(Expr::Literal(Literal::Time(
                                 Time::builder().hours(10).minutes(10).seconds(10).build()
-                            )))
+                            ), S)), S
                         )),
                         BinaryOp::Add,
                         Box::new(Expr::Literal(Literal::Time(
                             Time::builder().minutes(22).seconds(22).build()
-                        )))
+                        ), S)), S
                     )),
                     BinaryOp::Multiply,
                     Box::new(Expr::Unary(
                         UnaryOp::Negative,
                         Box::new(Expr::Binary(
-                            Box::new(Expr::Literal(Literal::Number(dec!(3.33)))),
+                            Box::new(Expr::Literal(Literal::Number(dec!(3.33)), S)),
                             BinaryOp::Divide,
                             Box::new(Expr::Unary(
                                 UnaryOp::Negative,
-                                Box::new(Expr::Literal(Literal::Number(dec!(4.44))))
-                            ))
-                        ))
-                    ))
+                                Box::new(Expr::Literal(Literal::Number(dec!(4.44)), S)), S
+                            )), S
+                        )), S
+                    )), S
                 )),
                 BinaryOp::Subtract,
                 Box::new(Expr::Binary(
                     Box::new(Expr::Binary(
                         Box::new(Expr::Literal(Literal::Time(
                             Time::builder().hours(5).build()
-                        ))),
+                        ), S)),
                         BinaryOp::Add,
                         Box::new(Expr::Unary(
                             UnaryOp::Negative,
                             Box::new(Expr::Literal(Literal::Time(
                                 Time::builder().minutes(6).seconds(6).build()
-                            )))
-                        ))
+                            ), S)), S
+                        )), S
                     )),
                     BinaryOp::Divide,
-                    Box::new(Expr::Literal(Literal::Number(dec!(7.77))))
-                ))
+                    Box::new(Expr::Literal(Literal::Number(dec!(7.77)), S)), S
+                )), S
+            )
+        );
+    }
+
+    #[test]
+    fn parse_call() {
+        assert_parse_expression("round(1:30:00, 15m)",
+            Expr::Call(
+                "round".to_string(),
+                vec![
+                    Expr::Literal(Literal::Time(Time::builder().hours(1).minutes(30).build()), S),
+                    Expr::Literal(Literal::Time(Time::builder().minutes(15).build()), S),
+                ],
+                S
             )
         );
+        assert_parse_expression("trunc()", Expr::Call("trunc".to_string(), vec![], S));
+        assert_parse_expression("-floor(1 + 2, 1) * 3",
+            Expr::Binary(
+                Box::new(Expr::Unary(
+                    UnaryOp::Negative,
+                    Box::new(Expr::Call(
+                        "floor".to_string(),
+                        vec![
+                            Expr::Binary(
+                                Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                                BinaryOp::Add,
+                                Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                                S
+                            ),
+                            Expr::Literal(Literal::Number(dec!(1)), S),
+                        ],
+                        S
+                    )),
+                    S
+                )),
+                BinaryOp::Multiply,
+                Box::new(Expr::Literal(Literal::Number(dec!(3)), S)),
+                S
+            )
+        );
+
+        assert_eq!(parse_expression("ceil(2.5, 0)").unwrap().span(), Span::new(0, 12));
     }
 
     #[test]
@@ -635,11 +1493,511 @@ mod tests {
         assert!(parse_expression("9.8.7 / 6").is_err());
     }
 
+    #[test]
+    fn parse_span() {
+        assert_eq!(parse_expression("1 + 2").unwrap(),
+            Expr::Binary(
+                Box::new(Expr::Literal(Literal::Number(dec!(1)), Span::new(0, 1))),
+                BinaryOp::Add,
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), Span::new(4, 5))),
+                Span::new(0, 5)
+            )
+        );
+
+        assert_eq!(parse_expression(" -(10:00 * 2)").unwrap(),
+            Expr::Unary(
+                UnaryOp::Negative,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(10).build()), Span::new(3, 8))),
+                    BinaryOp::Multiply,
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), Span::new(11, 12))),
+                    Span::new(3, 12)
+                )),
+                Span::new(1, 12)
+            )
+        );
+    }
+
+    #[test]
+    fn parse_error_span() {
+        assert_parse_error("24:3x + 1s", "unexpected character 'x'", 4, 5);
+        assert_parse_error("1 # 2 ! 3", "unexpected character '#', unexpected character '!'", 2, 7);
+        assert_parse_error("1 + 12:60", "invalid time '12:60': seconds must be less than 60, found 60", 7, 9);
+        assert_parse_error("1 + 1:2:03", "invalid time '1:2:03': expected two digit minutes, found '2'", 6, 7);
+        assert_parse_error("9.8.7 / 6", "invalid number '9.8.7'", 0, 5);
+        assert_parse_error("1 + 2 3 4", "unexpected '3' after expression", 6, 9);
+        assert_parse_error("(3 * 4", "expected ')', found end of input", 6, 6);
+        assert_parse_error("(3 * 4 5", "expected ')', found '5'", 7, 8);
+        assert_parse_error("+ 1", "expected a time or number, found '+'", 0, 1);
+        assert_parse_error("20:00:02 - ", "expected a time or number, found end of input", 11, 11);
+        assert_parse_error("round(1h, 15m", "expected ')', found end of input", 13, 13);
+        assert_parse_error("round(1h 2)", "expected ')', found '2'", 9, 10);
+        assert_parse_error("round(1h,)", "expected a time or number, found ')'", 9, 10);
+        // Digits other than ASCII digits are not numbers.
+        assert_parse_error("٣", "unexpected character '٣'", 0, 2);
+        assert_parse_error("1٣ + 2", "unexpected character '٣'", 1, 3);
+    }
+
+    #[test]
+    fn parse_variable() {
+        assert_parse_expression("lap1", Expr::Variable("lap1".to_string(), S));
+        assert_parse_expression("round + 1",
+            Expr::Binary(
+                Box::new(Expr::Variable("round".to_string(), S)),
+                BinaryOp::Add,
+                Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                S
+            )
+        );
+        assert_parse_expression("budget / (a - b)",
+            Expr::Binary(
+                Box::new(Expr::Variable("budget".to_string(), S)),
+                BinaryOp::Divide,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Variable("a".to_string(), S)),
+                    BinaryOp::Subtract,
+                    Box::new(Expr::Variable("b".to_string(), S)),
+                    S
+                )),
+                S
+            )
+        );
+    }
+
+    #[test]
+    fn parse_assignment() {
+        assert_eq!(parse_statement("lap1 = 01:02.3", false).unwrap(),
+            Stmt::Assign(
+                "lap1".to_string(),
+                Expr::Literal(Literal::Time(Time::builder().minutes(1).seconds(2).nanoseconds(300000000).build()), Span::new(7, 14)),
+                Span::new(0, 14)
+            )
+        );
+        assert_eq!(parse_statement("total=lap1 + lap2", false).unwrap(),
+            Stmt::Assign(
+                "total".to_string(),
+                Expr::Binary(
+                    Box::new(Expr::Variable("lap1".to_string(), Span::new(6, 10))),
+                    BinaryOp::Add,
+                    Box::new(Expr::Variable("lap2".to_string(), Span::new(13, 17))),
+                    Span::new(6, 17)
+                ),
+                Span::new(0, 17)
+            )
+        );
+
+        assert_parse_error("x =", "expected a time or number, found end of input", 3, 3);
+        assert_parse_error("x = y = 1", "unexpected '=' after expression", 6, 9);
+        assert_parse_error("1 = 2", "unexpected '=' after expression", 2, 5);
+        assert_parse_error("(x) = 2", "unexpected '=' after expression", 4, 7);
+    }
+
+    #[test]
+    fn parse_history() {
+        assert_scan_tokens("ans + _", vec![Answer, Plus, Answer]);
+        assert_scan_tokens("$1 * $23", vec![History("1".to_string()), Asterisk, History("23".to_string())]);
+        assert_scan_tokens("answer _x", vec![Identifier("answer".to_string()), Identifier("_x".to_string())]);
+
+        assert_parse_expression("ans / 2",
+            Expr::Binary(
+                Box::new(Expr::Answer(S)),
+                BinaryOp::Divide,
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                S
+            )
+        );
+        assert_parse_expression("$2 - _",
+            Expr::Binary(
+                Box::new(Expr::History(2, S)),
+                BinaryOp::Subtract,
+                Box::new(Expr::Answer(S)),
+                S
+            )
+        );
+        assert_eq!(parse_statement("total = $12", false).unwrap(),
+            Stmt::Assign("total".to_string(), Expr::History(12, Span::new(8, 11)), Span::new(0, 11)));
+
+        assert_parse_error("$0 + 1", "invalid result number '$0'", 0, 2);
+        assert_parse_error("1 + $99999999999999999999999", "invalid result number '$99999999999999999999999'", 4, 28);
+        assert_parse_error("1 + $a", "unexpected character '$'", 4, 5);
+        assert_parse_error("ans = 1", "unexpected '=' after expression", 4, 7);
+    }
+
+    #[test]
+    fn parse_conversion() {
+        assert_scan_tokens("1:30:00 in minutes", vec![Time("1:30:00".to_string()), In, Target("minutes".to_string())]);
+        assert_scan_tokens("(90m to  hh:mm:ss.3)",
+            vec![LeftParen, Time("90m".to_string()), To, Target("hh:mm:ss.3".to_string()), RightParen]);
+        assert_scan_tokens("round(1h in m, 2)",
+            vec![Identifier("round".to_string()), LeftParen, Time("1h".to_string()), In, Target("m".to_string()), Comma,
+                Number("2".to_string()), RightParen]);
+
+        assert_parse_expression("1:30:00 in minutes",
+            Expr::Convert(
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(1).minutes(30).build()), S)),
+                Conversion::Unit(TimeUnit::Minutes),
+                S
+            )
+        );
+        assert_parse_expression("1h + 30m to iso8601",
+            Expr::Convert(
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Time(Time::builder().hours(1).build()), S)),
+                    BinaryOp::Add,
+                    Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(30).build()), S)),
+                    S
+                )),
+                Conversion::Format(TimeFormat::new(TimeStyle::Iso8601)),
+                S
+            )
+        );
+        assert_parse_expression("(90m to h) * 2",
+            Expr::Binary(
+                Box::new(Expr::Convert(
+                    Box::new(Expr::Literal(Literal::Time(Time::builder().hours(1).minutes(30).build()), S)),
+                    Conversion::Unit(TimeUnit::Hours),
+                    S
+                )),
+                BinaryOp::Multiply,
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                S
+            )
+        );
+        assert_eq!(parse_expression("1h in s").unwrap().span(), Span::new(0, 7));
+
+        assert_parse_error("1h in", "expected a unit or format, found end of input", 5, 5);
+        assert_parse_error("(1h to) + 1", "expected a unit or format, found ')'", 6, 7);
+        assert_parse_error("1h in fortnights", "unknown unit or format 'fortnights'", 6, 16);
+        assert_parse_error("1h to s + 1", "unexpected '+' after expression", 8, 11);
+    }
+
+    #[test]
+    fn parse_comparison() {
+        assert_scan_tokens("< <= == != > >=", vec![Less, LessEqual, EqualEqual, BangEqual, Greater, GreaterEqual]);
+        assert_scan_tokens("1<=2", vec![Number("1".to_string()), LessEqual, Number("2".to_string())]);
+        assert_scan_tokens("a==b", vec![Identifier("a".to_string()), EqualEqual, Identifier("b".to_string())]);
+        assert_scan_tokens("= =", vec![Equals, Equals]);
+        assert_scan_tokens("=<>", vec![Equals, Less, Greater]);
+
+        assert_parse_expression("1 + 2 < 4 * 5",
+            Expr::Comparison(
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                    BinaryOp::Add,
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                    S
+                )),
+                ComparisonOp::Less,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Number(dec!(4)), S)),
+                    BinaryOp::Multiply,
+                    Box::new(Expr::Literal(Literal::Number(dec!(5)), S)),
+                    S
+                )),
+                S
+            )
+        );
+        assert_parse_expression("90m in minutes >= 60",
+            Expr::Comparison(
+                Box::new(Expr::Convert(
+                    Box::new(Expr::Literal(Literal::Time(Time::builder().hours(1).minutes(30).build()), S)),
+                    Conversion::Unit(TimeUnit::Minutes),
+                    S
+                )),
+                ComparisonOp::GreaterEqual,
+                Box::new(Expr::Literal(Literal::Number(dec!(60)), S)),
+                S
+            )
+        );
+        assert_parse_expression("(1 != 2)",
+            Expr::Comparison(
+                Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                ComparisonOp::NotEqual,
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                S
+            )
+        );
+        assert_eq!(parse_expression("1h > 2m").unwrap().span(), Span::new(0, 7));
+
+        assert_parse_error("1 < 2 < 3", "unexpected '<' after expression", 6, 9);
+        assert_parse_error("1 <", "expected a time or number, found end of input", 3, 3);
+        assert_parse_error("1 ! 2", "unexpected character '!'", 2, 3);
+    }
+
+    #[test]
+    fn parse_conditional() {
+        assert_scan_tokens("a ? 1h : 2h", vec![Identifier("a".to_string()), Question, Time("1h".to_string()), Colon,
+            Time("2h".to_string())]);
+        assert_scan_tokens("(x)?(y):(z)", vec![LeftParen, Identifier("x".to_string()), RightParen, Question, LeftParen,
+            Identifier("y".to_string()), RightParen, Colon, LeftParen, Identifier("z".to_string()), RightParen]);
+
+        assert_parse_expression("1h > 30m ? 1 + 2 : 3",
+            Expr::Conditional(
+                Box::new(Expr::Comparison(
+                    Box::new(Expr::Literal(Literal::Time(Time::builder().hours(1).build()), S)),
+                    ComparisonOp::Greater,
+                    Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(30).build()), S)),
+                    S
+                )),
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                    BinaryOp::Add,
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                    S
+                )),
+                Box::new(Expr::Literal(Literal::Number(dec!(3)), S)),
+                S
+            )
+        );
+        assert_parse_expression("a ? b : c ? d : e",
+            Expr::Conditional(
+                Box::new(Expr::Variable("a".to_string(), S)),
+                Box::new(Expr::Variable("b".to_string(), S)),
+                Box::new(Expr::Conditional(
+                    Box::new(Expr::Variable("c".to_string(), S)),
+                    Box::new(Expr::Variable("d".to_string(), S)),
+                    Box::new(Expr::Variable("e".to_string(), S)),
+                    S
+                )),
+                S
+            )
+        );
+        assert_parse_expression("a ? b ? c : d : e",
+            Expr::Conditional(
+                Box::new(Expr::Variable("a".to_string(), S)),
+                Box::new(Expr::Conditional(
+                    Box::new(Expr::Variable("b".to_string(), S)),
+                    Box::new(Expr::Variable("c".to_string(), S)),
+                    Box::new(Expr::Variable("d".to_string(), S)),
+                    S
+                )),
+                Box::new(Expr::Variable("e".to_string(), S)),
+                S
+            )
+        );
+        assert_parse_expression("if(a, b, c)",
+            Expr::Call("if".to_string(), vec![
+                Expr::Variable("a".to_string(), S),
+                Expr::Variable("b".to_string(), S),
+                Expr::Variable("c".to_string(), S),
+            ], S)
+        );
+        assert_eq!(parse_expression("a ? 1 : 2").unwrap().span(), Span::new(0, 9));
+
+        assert_parse_error("a ? 1", "expected ':', found end of input", 5, 5);
+        assert_parse_error("a ? 1 , 2", "expected ':', found ','", 6, 7);
+        assert_parse_error("a ? : 2", "expected a time or number, found ':'", 4, 5);
+        assert_parse_error("a : 2", "unexpected ':' after expression", 2, 5);
+    }
+
+    #[test]
+    fn parse_percentage() {
+        assert_scan_tokens("15%", vec![Percentage("15".to_string())]);
+        assert_scan_tokens("1h * 12.5%", vec![Time("1h".to_string()), Asterisk, Percentage("12.5".to_string())]);
+        assert_scan_tokens("20%+1", vec![Percentage("20".to_string()), Plus, Number("1".to_string())]);
+        assert_scan_tokens("(20%)", vec![LeftParen, Percentage("20".to_string()), RightParen]);
+        assert_scan_tokens("20%3", vec![Number("20".to_string()), Percent, Number("3".to_string())]);
+        assert_scan_tokens("20% x", vec![Number("20".to_string()), Percent, Identifier("x".to_string())]);
+        assert_scan_tokens("20%(3)", vec![Number("20".to_string()), Percent, LeftParen, Number("3".to_string()), RightParen]);
+        assert_scan_tokens("20 % 3", vec![Number("20".to_string()), Percent, Number("3".to_string())]);
+        assert_scan_tokens("20 %", vec![Number("20".to_string()), Percent]);
+        assert_scan_tokens("1h%", vec![Time("1h".to_string()), Percent]);
+
+        assert_parse_expression("1h + 20%",
+            Expr::Binary(
+                Box::new(Expr::Literal(Literal::Time(Time::builder().hours(1).build()), S)),
+                BinaryOp::Add,
+                Box::new(Expr::Literal(Literal::Percentage(dec!(20)), S)),
+                S
+            )
+        );
+        assert_eq!(parse_expression("1h + 20%").unwrap().span(), Span::new(0, 8));
+        assert_parse_error("1.2.3%", "invalid number '1.2.3'", 0, 6);
+    }
+
+    #[test]
+    fn parse_power() {
+        assert_scan_tokens("2^3", vec![Number("2".to_string()), Caret, Number("3".to_string())]);
+
+        assert_parse_expression("2 ^ 3 ^ 2",
+            Expr::Binary(
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                BinaryOp::Power,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Number(dec!(3)), S)),
+                    BinaryOp::Power,
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                    S
+                )),
+                S
+            )
+        );
+        assert_parse_expression("-2^2",
+            Expr::Unary(
+                UnaryOp::Negative,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                    BinaryOp::Power,
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                    S
+                )),
+                S
+            )
+        );
+        assert_parse_expression("2^-1 * 3",
+            Expr::Binary(
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                    BinaryOp::Power,
+                    Box::new(Expr::Unary(
+                        UnaryOp::Negative,
+                        Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                        S
+                    )),
+                    S
+                )),
+                BinaryOp::Multiply,
+                Box::new(Expr::Literal(Literal::Number(dec!(3)), S)),
+                S
+            )
+        );
+        assert_parse_expression("(1 + 2)^2",
+            Expr::Binary(
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Literal::Number(dec!(1)), S)),
+                    BinaryOp::Add,
+                    Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                    S
+                )),
+                BinaryOp::Power,
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), S)),
+                S
+            )
+        );
+        assert_eq!(parse_expression("-2^2").unwrap().span(), Span::new(0, 4));
+
+        assert_parse_error("2^", "expected a time or number, found end of input", 2, 2);
+        assert_parse_error("^2", "expected a time or number, found '^'", 0, 1);
+    }
+
+    #[test]
+    fn parse_implicit_multiplication() {
+        let number = |n| Box::new(Expr::Literal(Literal::Number(n), S));
+        let time = |t| Box::new(Expr::Literal(Literal::Time(t), S));
+        let implicit = |input| match parse_statement(input, true).unwrap() {
+            Stmt::Expr(expr) => strip_spans(expr),
+            stmt => panic!("expected an expression, found {:?}", stmt),
+        };
+
+        assert_eq!(implicit("3 01:30"),
+            Expr::Binary(number(dec!(3)), BinaryOp::Multiply, time(Time::builder().minutes(1).seconds(30).build()), S));
+        assert_eq!(implicit("2(45:00)"),
+            Expr::Binary(number(dec!(2)), BinaryOp::Multiply, time(Time::builder().minutes(45).build()), S));
+        assert_eq!(implicit("(1 + 2)(3)"),
+            Expr::Binary(
+                Box::new(Expr::Binary(number(dec!(1)), BinaryOp::Add, number(dec!(2)), S)),
+                BinaryOp::Multiply,
+                number(dec!(3)),
+                S
+            )
+        );
+        assert_eq!(implicit("2 x ans $1"),
+            Expr::Binary(
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Binary(number(dec!(2)), BinaryOp::Multiply, Box::new(Expr::Variable("x".to_string(), S)), S)),
+                    BinaryOp::Multiply,
+                    Box::new(Expr::Answer(S)),
+                    S
+                )),
+                BinaryOp::Multiply,
+                Box::new(Expr::History(1, S)),
+                S
+            )
+        );
+        assert_eq!(implicit("1 + 2 3"),
+            Expr::Binary(
+                number(dec!(1)),
+                BinaryOp::Add,
+                Box::new(Expr::Binary(number(dec!(2)), BinaryOp::Multiply, number(dec!(3)), S)),
+                S
+            )
+        );
+        assert_eq!(implicit("2 3^2"),
+            Expr::Binary(
+                number(dec!(2)),
+                BinaryOp::Multiply,
+                Box::new(Expr::Binary(number(dec!(3)), BinaryOp::Power, number(dec!(2)), S)),
+                S
+            )
+        );
+        assert_eq!(implicit("3 -2"), Expr::Binary(number(dec!(3)), BinaryOp::Subtract, number(dec!(2)), S));
+        assert_eq!(implicit("2 round(1.5)"),
+            Expr::Binary(number(dec!(2)), BinaryOp::Multiply, Box::new(Expr::Call("round".to_string(), vec![*number(dec!(1.5))], S)), S));
+
+        match parse_statement("x = 2 3", true).unwrap() {
+            Stmt::Assign(name, expr, _) => {
+                assert_eq!(name, "x");
+                assert_eq!(strip_spans(expr), Expr::Binary(number(dec!(2)), BinaryOp::Multiply, number(dec!(3)), S));
+            }
+            stmt => panic!("expected an assignment, found {:?}", stmt),
+        }
+        assert_eq!(parse_statement("2(45:00)", true).unwrap(),
+            Stmt::Expr(Expr::Binary(
+                Box::new(Expr::Literal(Literal::Number(dec!(2)), Span::new(0, 1))),
+                BinaryOp::Multiply,
+                Box::new(Expr::Literal(Literal::Time(Time::builder().minutes(45).build()), Span::new(2, 7))),
+                Span::new(0, 7)
+            ))
+        );
+
+        assert_parse_error("3 01:30", "unexpected '01:30' after expression", 2, 7);
+        assert_parse_error("2(45:00)", "unexpected '(' after expression", 1, 8);
+    }
+
+    fn parse_expression(input: &str) -> Result<Expr, ParseError> {
+        match parse_statement(input, false)? {
+            Stmt::Expr(expr) => Result::Ok(expr),
+            stmt => panic!("expected an expression, found {:?}", stmt),
+        }
+    }
+
+    fn assert_parse_error(input: &str, message: &str, start: usize, end: usize) {
+        let error = parse_expression(input).unwrap_err();
+        assert_eq!(error.to_string(), message);
+        assert_eq!(error.span(), Span::new(start, end));
+    }
+
     fn assert_scan_tokens(input: &str, tokens: Vec<Token>) {
-        assert_eq!(Lexer::new(input).scan().unwrap(), tokens);
+        let scanned: Vec<Token> = Lexer::new(input).scan().unwrap().into_iter().map(|(t, _)| t).collect();
+        assert_eq!(scanned, tokens);
     }
 
     fn assert_parse_expression(input: &str, expr: Expr) {
-        assert_eq!(parse_expression(input).unwrap(), expr);
+        assert_eq!(strip_spans(parse_expression(input).unwrap()), expr);
+    }
+
+    /// Replaces the span of each expression with `S` so that expressions can be compared by
+    /// structure only.
+    fn strip_spans(expr: Expr) -> Expr {
+        match expr {
+            Expr::Literal(literal, _) => Expr::Literal(literal, S),
+            Expr::Unary(op, operand, _) => Expr::Unary(op, Box::new(strip_spans(*operand)), S),
+            Expr::Binary(left, op, right, _) =>
+                Expr::Binary(Box::new(strip_spans(*left)), op, Box::new(strip_spans(*right)), S),
+            Expr::Comparison(left, op, right, _) =>
+                Expr::Comparison(Box::new(strip_spans(*left)), op, Box::new(strip_spans(*right)), S),
+            Expr::Conditional(condition, then_expr, else_expr, _) =>
+                Expr::Conditional(
+                    Box::new(strip_spans(*condition)),
+                    Box::new(strip_spans(*then_expr)),
+                    Box::new(strip_spans(*else_expr)),
+                    S
+                ),
+            Expr::Call(name, args, _) => Expr::Call(name, args.into_iter().map(strip_spans).collect(), S),
+            Expr::Variable(name, _) => Expr::Variable(name, S),
+            Expr::Answer(_) => Expr::Answer(S),
+            Expr::History(n, _) => Expr::History(n, S),
+            Expr::Convert(operand, conversion, _) => Expr::Convert(Box::new(strip_spans(*operand)), conversion, S),
+        }
     }
 }
@@ -0,0 +1,488 @@
+//! A C API for evaluating expressions and working with times.
+//!
+//! The C declarations are in `include/time_calc.h`, which is generated from this module with
+//! [cbindgen](https://github.com/eqrion/cbindgen). Evaluators and times are opaque pointers which
+//! are created by this library and must be freed with
+//! [`time_calc_evaluator_free`](fn.time_calc_evaluator_free.html) or
+//! [`time_calc_time_free`](fn.time_calc_time_free.html) respectively.
+//!
+//! Strings are written to a buffer provided by the caller, as a NUL-terminated UTF-8 string. If the
+//! buffer is too small, nothing is written and
+//! [`BufferTooSmall`](enum.TimeCalcStatus.html#variant.BufferTooSmall) is returned. The length of
+//! the string, not including the NUL terminator, is always written to `length` if it is not null,
+//! so the string can be retrieved again with a large enough buffer. An expression is not evaluated
+//! again to do this, since evaluating it may assign a variable. Instead, the result is retrieved
+//! with [`time_calc_evaluator_output`](fn.time_calc_evaluator_output.html).
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+
+use rust_decimal::Decimal;
+
+use crate::Evaluator;
+use crate::Time;
+use crate::TimeFormat;
+
+/// The status of a call to the C API.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimeCalcStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// A string was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The buffer was too small to hold the string.
+    BufferTooSmall = 3,
+    /// The expression could not be evaluated. The error message was written to the buffer.
+    EvalError = 4,
+    /// The time format was not valid.
+    InvalidFormat = 5,
+}
+
+/// Evaluates expressions containing times and numbers, keeping the output of the most recent
+/// expression so that it can be retrieved again.
+pub struct TimeCalcEvaluator {
+    evaluator: Evaluator,
+    status: TimeCalcStatus,
+    output: String,
+}
+
+/// Returns a new evaluator, with the default options and an empty history.
+#[no_mangle]
+pub extern "C" fn time_calc_evaluator_new() -> *mut TimeCalcEvaluator {
+    Box::into_raw(Box::new(TimeCalcEvaluator {
+        evaluator: Evaluator::new(),
+        status: TimeCalcStatus::Ok,
+        output: String::new(),
+    }))
+}
+
+/// Frees an evaluator. Does nothing if `evaluator` is null.
+///
+/// # Safety
+///
+/// `evaluator` must be null or have been returned by `time_calc_evaluator_new`, and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_evaluator_free(evaluator: *mut TimeCalcEvaluator) {
+    if !evaluator.is_null() {
+        drop(Box::from_raw(evaluator));
+    }
+}
+
+/// Evaluates an expression, writing the result to `buffer` and recording it in the history of the
+/// evaluator.
+///
+/// If the expression cannot be evaluated, the error message is written to `buffer` and `EvalError`
+/// is returned. If `buffer` is too small, the expression is still evaluated, and the result or
+/// error message can be retrieved with `time_calc_evaluator_output`.
+///
+/// # Safety
+///
+/// `evaluator` must have been returned by `time_calc_evaluator_new`. `expression` must be a
+/// NUL-terminated string. `buffer` must be valid for writes of `buffer_len` bytes. `length` must
+/// be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_evaluator_eval(
+    evaluator: *mut TimeCalcEvaluator,
+    expression: *const c_char,
+    buffer: *mut c_char,
+    buffer_len: usize,
+    length: *mut usize,
+) -> TimeCalcStatus {
+    if evaluator.is_null() {
+        return TimeCalcStatus::NullPointer;
+    }
+    let evaluator = &mut *evaluator;
+    let expression = match to_str(expression) {
+        Result::Ok(expression) => expression,
+        Result::Err(status) => return status,
+    };
+
+    match evaluator.evaluator.eval(expression) {
+        Result::Ok(result) => {
+            evaluator.status = TimeCalcStatus::Ok;
+            evaluator.output = result.to_string();
+            evaluator.evaluator.record(result);
+        }
+        Result::Err(error) => {
+            evaluator.status = TimeCalcStatus::EvalError;
+            evaluator.output = error.to_string();
+        }
+    }
+    time_calc_evaluator_output(evaluator, buffer, buffer_len, length)
+}
+
+/// Writes the result or error message of the most recently evaluated expression to `buffer`,
+/// without evaluating it again. Returns the status which `time_calc_evaluator_eval` would have
+/// returned with a large enough buffer, or `BufferTooSmall`. Before an expression is evaluated, the
+/// output is an empty string.
+///
+/// # Safety
+///
+/// `evaluator` must have been returned by `time_calc_evaluator_new`. `buffer` must be valid for
+/// writes of `buffer_len` bytes. `length` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_evaluator_output(
+    evaluator: *const TimeCalcEvaluator,
+    buffer: *mut c_char,
+    buffer_len: usize,
+    length: *mut usize,
+) -> TimeCalcStatus {
+    if evaluator.is_null() {
+        return TimeCalcStatus::NullPointer;
+    }
+    let evaluator = &*evaluator;
+    match write_string(&evaluator.output, buffer, buffer_len, length) {
+        TimeCalcStatus::Ok => evaluator.status,
+        status => status,
+    }
+}
+
+/// Returns a new time with a whole number of `seconds`, rounded toward negative infinity, plus a
+/// positive offset of `nanoseconds`. For example, -1.2 seconds is -2 seconds plus 800,000,000
+/// nanoseconds. Returns null if `nanoseconds` is not less than one second or the time is out of
+/// range.
+#[no_mangle]
+pub extern "C" fn time_calc_time_new(seconds: i64, nanoseconds: u32) -> *mut Time {
+    if nanoseconds >= 1_000_000_000 {
+        return ptr::null_mut();
+    }
+    let decimal = Decimal::from(seconds) + Decimal::new(i64::from(nanoseconds), 9);
+    match Time::try_from(decimal) {
+        Result::Ok(time) => into_raw(time),
+        Result::Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Parses a time such as `1:30:00`, `1h30m` or `PT1H30M`. Returns null if the string is not a
+/// valid time.
+///
+/// # Safety
+///
+/// `s` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_time_parse(s: *const c_char) -> *mut Time {
+    match to_str(s).map(str::parse) {
+        Result::Ok(Result::Ok(time)) => into_raw(time),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Returns the whole number of seconds of a time, rounded toward negative infinity.
+///
+/// # Safety
+///
+/// `time` must be a valid time.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_time_seconds(time: *const Time) -> i64 {
+    (*time).total_seconds()
+}
+
+/// Returns the positive nanoseconds offset of a time, which is added to the seconds.
+///
+/// # Safety
+///
+/// `time` must be a valid time.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_time_nanoseconds(time: *const Time) -> u32 {
+    (*time).nanoseconds_offset()
+}
+
+/// Returns a new time which is the sum of two times. Returns null if either time is null or the
+/// result overflows.
+///
+/// # Safety
+///
+/// `a` and `b` must be null or valid times.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_time_add(a: *const Time, b: *const Time) -> *mut Time {
+    if a.is_null() || b.is_null() {
+        return ptr::null_mut();
+    }
+    (*a).checked_add(*b).map_or(ptr::null_mut(), into_raw)
+}
+
+/// Returns a new time which is the difference of two times. Returns null if either time is null or
+/// the result overflows.
+///
+/// # Safety
+///
+/// `a` and `b` must be null or valid times.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_time_sub(a: *const Time, b: *const Time) -> *mut Time {
+    if a.is_null() || b.is_null() {
+        return ptr::null_mut();
+    }
+    (*a).checked_sub(*b).map_or(ptr::null_mut(), into_raw)
+}
+
+/// Formats a time, writing it to `buffer`. `format` is a format name such as `hh:mm:ss` or
+/// `hours.2`, as used by the `--format` option, or null for the default format.
+///
+/// # Safety
+///
+/// `time` must be a valid time. `format` must be null or a NUL-terminated string. `buffer` must
+/// be valid for writes of `buffer_len` bytes. `length` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_time_format(
+    time: *const Time,
+    format: *const c_char,
+    buffer: *mut c_char,
+    buffer_len: usize,
+    length: *mut usize,
+) -> TimeCalcStatus {
+    if time.is_null() {
+        return TimeCalcStatus::NullPointer;
+    }
+    let format = if format.is_null() {
+        TimeFormat::default()
+    } else {
+        match to_str(format).map(str::parse) {
+            Result::Ok(Result::Ok(format)) => format,
+            Result::Ok(Result::Err(_)) => return TimeCalcStatus::InvalidFormat,
+            Result::Err(status) => return status,
+        }
+    };
+    write_string(&(*time).format(&format), buffer, buffer_len, length)
+}
+
+/// Frees a time. Does nothing if `time` is null.
+///
+/// # Safety
+///
+/// `time` must be null or have been returned by this library, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn time_calc_time_free(time: *mut Time) {
+    if !time.is_null() {
+        drop(Box::from_raw(time));
+    }
+}
+
+fn into_raw(time: Time) -> *mut Time {
+    Box::into_raw(Box::new(time))
+}
+
+/// Converts a NUL-terminated string to a `&str`.
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str, TimeCalcStatus> {
+    if s.is_null() {
+        return Result::Err(TimeCalcStatus::NullPointer);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| TimeCalcStatus::InvalidUtf8)
+}
+
+/// Writes a string to a buffer, followed by a NUL terminator, and writes its length to `length`.
+unsafe fn write_string(
+    s: &str,
+    buffer: *mut c_char,
+    buffer_len: usize,
+    length: *mut usize,
+) -> TimeCalcStatus {
+    if !length.is_null() {
+        *length = s.len();
+    }
+    if buffer.is_null() || s.len() >= buffer_len {
+        return TimeCalcStatus::BufferTooSmall;
+    }
+    ptr::copy_nonoverlapping(s.as_ptr() as *const c_char, buffer, s.len());
+    *buffer.add(s.len()) = 0;
+    TimeCalcStatus::Ok
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use std::ffi::CStr;
+    use std::ffi::CString;
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    use rust_decimal_macros::dec;
+
+    use crate::ffi::*;
+    use crate::EvalResult;
+
+    #[test]
+    fn ffi_evaluator_eval() {
+        unsafe {
+            let evaluator = time_calc_evaluator_new();
+            assert_eq!(eval(evaluator, "1:30:00 + 45:00"), (TimeCalcStatus::Ok, "2:15:00".to_string()));
+            assert_eq!(eval(evaluator, "$1 / 5"), (TimeCalcStatus::Ok, "27:00".to_string()));
+            assert_eq!(eval(evaluator, "2 * 3 > 5"), (TimeCalcStatus::Ok, "true".to_string()));
+            assert_eq!(eval(evaluator, "1 / 0"), (TimeCalcStatus::EvalError, "division by zero".to_string()));
+            time_calc_evaluator_free(evaluator);
+        }
+    }
+
+    #[test]
+    fn ffi_evaluator_eval_invalid_arguments() {
+        unsafe {
+            let evaluator = time_calc_evaluator_new();
+            let mut buffer = [0 as c_char; 16];
+            let expression = CString::new("1 + 2").unwrap();
+            assert_eq!(time_calc_evaluator_eval(ptr::null_mut(), expression.as_ptr(), buffer.as_mut_ptr(), buffer.len(), ptr::null_mut()), TimeCalcStatus::NullPointer);
+            assert_eq!(time_calc_evaluator_eval(evaluator, ptr::null(), buffer.as_mut_ptr(), buffer.len(), ptr::null_mut()), TimeCalcStatus::NullPointer);
+
+            let invalid = [0xff_u8 as c_char, 0];
+            assert_eq!(time_calc_evaluator_eval(evaluator, invalid.as_ptr(), buffer.as_mut_ptr(), buffer.len(), ptr::null_mut()), TimeCalcStatus::InvalidUtf8);
+            time_calc_evaluator_free(evaluator);
+            time_calc_evaluator_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn ffi_buffer_too_small() {
+        unsafe {
+            let evaluator = time_calc_evaluator_new();
+            let expression = CString::new("12:34:56 * 2").unwrap();
+            let mut length = 0;
+            assert_eq!(time_calc_evaluator_eval(evaluator, expression.as_ptr(), ptr::null_mut(), 0, &mut length), TimeCalcStatus::BufferTooSmall);
+            assert_eq!(length, "25:09:52".len());
+
+            // The buffer must have room for the NUL terminator.
+            let mut buffer = [1 as c_char; 8];
+            assert_eq!(time_calc_evaluator_output(evaluator, buffer.as_mut_ptr(), buffer.len(), &mut length), TimeCalcStatus::BufferTooSmall);
+            assert_eq!(buffer, [1; 8]);
+
+            let mut buffer = [1 as c_char; 9];
+            assert_eq!(time_calc_evaluator_output(evaluator, buffer.as_mut_ptr(), buffer.len(), &mut length), TimeCalcStatus::Ok);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str(), Result::Ok("25:09:52"));
+
+            // Retrieving the output does not evaluate the expression again.
+            assert_eq!(eval(evaluator, "x = 1"), (TimeCalcStatus::Ok, "1".to_string()));
+            let expression = CString::new("x = x + 1").unwrap();
+            assert_eq!(time_calc_evaluator_eval(evaluator, expression.as_ptr(), ptr::null_mut(), 0, &mut length), TimeCalcStatus::BufferTooSmall);
+            assert_eq!(time_calc_evaluator_output(evaluator, buffer.as_mut_ptr(), buffer.len(), &mut length), TimeCalcStatus::Ok);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str(), Result::Ok("2"));
+            assert_eq!((*evaluator).evaluator.history().len(), 3);
+            assert_eq!((*evaluator).evaluator.variable("x"), Option::Some(EvalResult::Number(dec!(2))));
+
+            let expression = CString::new("1 / 0").unwrap();
+            assert_eq!(time_calc_evaluator_eval(evaluator, expression.as_ptr(), ptr::null_mut(), 0, &mut length), TimeCalcStatus::BufferTooSmall);
+            assert_eq!(time_calc_evaluator_output(evaluator, buffer.as_mut_ptr(), 0, &mut length), TimeCalcStatus::BufferTooSmall);
+            assert_eq!(length, "division by zero".len());
+            time_calc_evaluator_free(evaluator);
+        }
+    }
+
+    #[test]
+    fn ffi_evaluator_output() {
+        unsafe {
+            let evaluator = time_calc_evaluator_new();
+            assert_eq!(output(evaluator), (TimeCalcStatus::Ok, "".to_string()));
+            eval(evaluator, "1:30:00 + 45:00");
+            assert_eq!(output(evaluator), (TimeCalcStatus::Ok, "2:15:00".to_string()));
+            eval(evaluator, "1 / 0");
+            assert_eq!(output(evaluator), (TimeCalcStatus::EvalError, "division by zero".to_string()));
+            assert_eq!((*evaluator).evaluator.history().len(), 1);
+
+            let mut buffer = [0 as c_char; 16];
+            assert_eq!(time_calc_evaluator_output(ptr::null(), buffer.as_mut_ptr(), buffer.len(), ptr::null_mut()), TimeCalcStatus::NullPointer);
+            time_calc_evaluator_free(evaluator);
+        }
+    }
+
+    #[test]
+    fn ffi_time_new() {
+        unsafe {
+            let time = time_calc_time_new(-2, 800_000_000);
+            assert_eq!(time_calc_time_seconds(time), -2);
+            assert_eq!(time_calc_time_nanoseconds(time), 800_000_000);
+            assert_eq!(format(time, Option::None), (TimeCalcStatus::Ok, "-1.2s".to_string()));
+            time_calc_time_free(time);
+
+            let time = time_calc_time_new(i64::MAX, 999_999_999);
+            assert_eq!(time_calc_time_seconds(time), i64::MAX);
+            assert_eq!(time_calc_time_nanoseconds(time), 999_999_999);
+            time_calc_time_free(time);
+
+            assert!(time_calc_time_new(0, 1_000_000_000).is_null());
+            assert!(time_calc_time_new(i64::MIN, 0).is_null());
+        }
+    }
+
+    #[test]
+    fn ffi_time_parse() {
+        unsafe {
+            let s = CString::new("1:02:03.5").unwrap();
+            let time = time_calc_time_parse(s.as_ptr());
+            assert_eq!(time_calc_time_seconds(time), 3723);
+            assert_eq!(time_calc_time_nanoseconds(time), 500_000_000);
+            time_calc_time_free(time);
+
+            let s = CString::new("PT1H30M").unwrap();
+            let time = time_calc_time_parse(s.as_ptr());
+            assert_eq!(time_calc_time_seconds(time), 5400);
+            time_calc_time_free(time);
+
+            let s = CString::new("1:x").unwrap();
+            assert!(time_calc_time_parse(s.as_ptr()).is_null());
+            assert!(time_calc_time_parse(ptr::null()).is_null());
+        }
+    }
+
+    #[test]
+    fn ffi_time_add_sub() {
+        unsafe {
+            let a = time_calc_time_new(5400, 0);
+            let b = time_calc_time_new(30, 500_000_000);
+            let sum = time_calc_time_add(a, b);
+            assert_eq!(format(sum, Option::None), (TimeCalcStatus::Ok, "1:30:30.5".to_string()));
+            let difference = time_calc_time_sub(b, a);
+            assert_eq!(format(difference, Option::None), (TimeCalcStatus::Ok, "-1:29:29.5".to_string()));
+            assert!(time_calc_time_add(a, ptr::null()).is_null());
+            assert!(time_calc_time_sub(ptr::null(), b).is_null());
+
+            let max = time_calc_time_new(i64::MAX, 0);
+            assert!(time_calc_time_add(max, a).is_null());
+
+            for time in [a, b, sum, difference, max].iter() {
+                time_calc_time_free(*time);
+            }
+            time_calc_time_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn ffi_time_format() {
+        unsafe {
+            let time = time_calc_time_new(5400, 0);
+            assert_eq!(format(time, Option::Some("hh:mm:ss")), (TimeCalcStatus::Ok, "01:30:00".to_string()));
+            assert_eq!(format(time, Option::Some("hours.2")), (TimeCalcStatus::Ok, "1.50".to_string()));
+            assert_eq!(format(time, Option::Some("fancy")), (TimeCalcStatus::InvalidFormat, "".to_string()));
+            time_calc_time_free(time);
+
+            let mut buffer = [0 as c_char; 16];
+            assert_eq!(time_calc_time_format(ptr::null(), ptr::null(), buffer.as_mut_ptr(), buffer.len(), ptr::null_mut()), TimeCalcStatus::NullPointer);
+        }
+    }
+
+    unsafe fn eval(evaluator: *mut TimeCalcEvaluator, expression: &str) -> (TimeCalcStatus, String) {
+        let expression = CString::new(expression).unwrap();
+        let mut buffer = [0 as c_char; 64];
+        let mut length = 0;
+        let status = time_calc_evaluator_eval(evaluator, expression.as_ptr(), buffer.as_mut_ptr(), buffer.len(), &mut length);
+        let result = CStr::from_ptr(buffer.as_ptr()).to_str().unwrap().to_string();
+        assert_eq!(length, result.len());
+        (status, result)
+    }
+
+    unsafe fn output(evaluator: *const TimeCalcEvaluator) -> (TimeCalcStatus, String) {
+        let mut buffer = [0 as c_char; 64];
+        let status = time_calc_evaluator_output(evaluator, buffer.as_mut_ptr(), buffer.len(), ptr::null_mut());
+        (status, CStr::from_ptr(buffer.as_ptr()).to_str().unwrap().to_string())
+    }
+
+    unsafe fn format(time: *const Time, format: Option<&str>) -> (TimeCalcStatus, String) {
+        let format = format.map(|f| CString::new(f).unwrap());
+        let format_ptr = format.as_ref().map_or(ptr::null(), |f| f.as_ptr());
+        let mut buffer = [0 as c_char; 64];
+        let status = time_calc_time_format(time, format_ptr, buffer.as_mut_ptr(), buffer.len(), ptr::null_mut());
+        (status, CStr::from_ptr(buffer.as_ptr()).to_str().unwrap().to_string())
+    }
+}
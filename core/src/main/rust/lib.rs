@@ -0,0 +1,30 @@
+//! A calculator for performing arithmetic on times and numbers.
+//!
+//! Expressions are evaluated with an [`Evaluator`](struct.Evaluator.html). For example:
+//!
+//! ```
+//! use time_calc::{EvalResult, Evaluator, Time};
+//!
+//! let result = Evaluator::new().eval("1:30:00 + 45:00").unwrap();
+//! assert_eq!(result, EvalResult::Time(Time::builder().hours(2).minutes(15).build()));
+//! ```
+mod calc;
+pub mod ffi;
+mod span;
+pub mod time;
+
+pub use crate::calc::eval::EvalError;
+pub use crate::calc::eval::EvalResult;
+pub use crate::calc::eval::Evaluator;
+pub use crate::calc::eval::OverflowPolicy;
+pub use crate::calc::parse::LexError;
+pub use crate::calc::parse::ParseError;
+pub use crate::calc::parse::Token;
+pub use crate::span::Span;
+pub use crate::time::format::TimeFormat;
+pub use crate::time::format::TimeFormatError;
+pub use crate::time::format::TimeStyle;
+pub use crate::time::Time;
+pub use crate::time::TimeBuilder;
+pub use crate::time::TimeError;
+pub use crate::time::TimeUnit;
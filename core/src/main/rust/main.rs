@@ -1,44 +1,296 @@
-mod calc;
-mod time;
+mod args;
+mod repl;
+mod serve;
 
 use std::env;
+use std::fs::File;
 use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::IsTerminal;
 use std::process;
 
-use crate::calc::eval::eval;
+use rust_decimal::RoundingStrategy;
+use serde_json::json;
+use serde_json::Value;
+use time_calc::EvalError;
+use time_calc::EvalResult;
+use time_calc::Evaluator;
+use time_calc::OverflowPolicy;
+use time_calc::ParseError;
+use time_calc::Span;
+use time_calc::TimeFormat;
+
+use crate::args::parse_args;
+use crate::args::Command;
+use crate::args::Config;
+use crate::args::Mode;
+use crate::args::USAGE;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let config = match parse_args(env::args().skip(1)) {
+        Result::Ok(Command::Run(config)) => config,
+        Result::Ok(Command::Help) => {
+            print!("{}", USAGE);
+            process::exit(0);
+        }
+        Result::Ok(Command::Version) => {
+            println!("time-calc {}", env!("CARGO_PKG_VERSION"));
+            process::exit(0);
+        }
+        Result::Err(error) => {
+            eprintln!("time-calc: {}", error);
+            eprintln!("Try 'time-calc --help' for more information.");
+            process::exit(2);
+        }
+    };
+    let Config {
+        mut evaluator,
+        mut time_format,
+        json_output,
+        mode,
+    } = config;
 
-    // Interactive mode.
-    if args.len() == 1 {
-        match interactive_mode() {
+    match mode {
+        // Evaluate expressions from the command line.
+        Option::Some(Mode::Expressions(expressions)) => process::exit(expression_mode(
+            &mut evaluator,
+            &expressions,
+            &time_format,
+            json_output,
+        )),
+        // Evaluate each line of a file.
+        Option::Some(Mode::File(path)) => match File::open(&path) {
+            Result::Ok(file) => {
+                let input = BufReader::new(file);
+                match batch_mode(&mut evaluator, input, &time_format, json_output) {
+                    Err(_) => process::exit(1),
+                    _ => process::exit(0),
+                }
+            }
+            Result::Err(error) => {
+                eprintln!("time-calc: cannot read {}: {}", path.display(), error);
+                process::exit(2);
+            }
+        },
+        // JSON-RPC server mode.
+        Option::Some(Mode::Serve) => match serve::serve_mode(&mut evaluator, &mut time_format) {
             Err(_) => process::exit(1),
             _ => process::exit(0),
+        },
+        // Interactive mode, with a prompt and line editing.
+        Option::Some(Mode::Interactive) => {
+            match repl::interactive_mode(evaluator, &time_format, json_output) {
+                Err(_) => process::exit(1),
+                _ => process::exit(0),
+            }
+        }
+        // Batch mode, which reads expressions from stdin without a prompt.
+        Option::Some(Mode::Batch) => {
+            let stdin = io::stdin();
+            match batch_mode(&mut evaluator, stdin.lock(), &time_format, json_output) {
+                Err(_) => process::exit(1),
+                _ => process::exit(0),
+            }
+        }
+        // With no mode, use interactive mode if stdin is a terminal, and batch mode otherwise, so
+        // that input from a pipe or a file is never prompted for.
+        Option::None => {
+            let stdin = io::stdin();
+            let result = if stdin.is_terminal() {
+                repl::interactive_mode(evaluator, &time_format, json_output).map_err(|_| ())
+            } else {
+                batch_mode(&mut evaluator, stdin.lock(), &time_format, json_output).map_err(|_| ())
+            };
+            match result {
+                Err(_) => process::exit(1),
+                _ => process::exit(0),
+            }
         }
     }
+}
 
-    // Evaluate single expression.
-    match eval(&args[1..].join(" ")) {
-        Ok(result) => println!("{}", result),
-        Err(error) => {
-            eprintln!("{:?}", error);
-            process::exit(2);
+/// Evaluates expressions in order, recording each result so that later expressions can refer to
+/// it, and returns the exit status. Evaluation stops at the first error, which exits with a status
+/// of 2. A last result which is false exits with a status of 1, so that the tool can be used as a
+/// condition in a shell script.
+fn expression_mode(
+    evaluator: &mut Evaluator,
+    expressions: &[String],
+    time_format: &TimeFormat,
+    json_output: bool,
+) -> i32 {
+    let mut status = 0;
+    for expression in expressions {
+        match evaluator.eval(expression) {
+            Ok(result) => {
+                if json_output {
+                    println!("{}", json_result(&result, time_format));
+                } else {
+                    println!("{}", format_result(&result, time_format));
+                }
+                status = if result == EvalResult::Bool(false) {
+                    1
+                } else {
+                    0
+                };
+                evaluator.record(result);
+            }
+            Err(error) => {
+                if json_output {
+                    println!("{}", json_error(&error));
+                } else {
+                    eprint!("{}", render_error(expression, &error));
+                }
+                return 2;
+            }
         }
     }
+    status
+}
+
+fn parse_overflow_policy(policy: &str) -> Option<OverflowPolicy> {
+    match policy {
+        "checked" => Option::Some(OverflowPolicy::Checked),
+        "saturating" => Option::Some(OverflowPolicy::Saturating),
+        "wrapping" => Option::Some(OverflowPolicy::Wrapping),
+        _ => Option::None,
+    }
+}
+
+fn parse_rounding_strategy(strategy: &str) -> Option<RoundingStrategy> {
+    match strategy {
+        "half-up" => Option::Some(RoundingStrategy::MidpointAwayFromZero),
+        "half-down" => Option::Some(RoundingStrategy::MidpointTowardZero),
+        "half-even" => Option::Some(RoundingStrategy::MidpointNearestEven),
+        "up" => Option::Some(RoundingStrategy::AwayFromZero),
+        "down" => Option::Some(RoundingStrategy::ToZero),
+        "floor" => Option::Some(RoundingStrategy::ToNegativeInfinity),
+        "ceiling" => Option::Some(RoundingStrategy::ToPositiveInfinity),
+        _ => Option::None,
+    }
+}
+
+/// Formats a result for output, writing times according to `time_format`.
+fn format_result(result: &EvalResult, time_format: &TimeFormat) -> String {
+    match result {
+        EvalResult::Time(time) => time.format(time_format),
+        _ => result.to_string(),
+    }
 }
 
-fn interactive_mode() -> Result<(), io::Error> {
-    let stdin = io::stdin();
+/// Returns a result as a JSON object with the kind of result, the formatted value and the exact
+/// value. The exact value of a time is its total seconds and nanoseconds offset.
+fn json_result(result: &EvalResult, time_format: &TimeFormat) -> Value {
+    let value = format_result(result, time_format);
+    match result {
+        EvalResult::Time(time) | EvalResult::FormattedTime(time, _) => json!({
+            "kind": "time",
+            "value": value,
+            "exact": {
+                "seconds": time.total_seconds(),
+                "nanoseconds": time.nanoseconds_offset(),
+            },
+        }),
+        EvalResult::Number(number) => json!({
+            "kind": "number",
+            "value": value,
+            "exact": number.to_string(),
+        }),
+        EvalResult::Bool(b) => json!({
+            "kind": "bool",
+            "value": value,
+            "exact": b,
+        }),
+        // A kind of result added to the library later is written without an exact value.
+        _ => json!({
+            "kind": "unknown",
+            "value": value,
+        }),
+    }
+}
+
+/// Returns an error as a JSON object of the form `{"error": {...}}`.
+fn json_error(error: &EvalError) -> Value {
+    json!({ "error": json_eval_error(error) })
+}
+
+/// Returns a JSON object with the kind of error, the message and the location of the error as byte
+/// offsets. An error made up of several lexer errors also includes each of those errors.
+fn json_eval_error(error: &EvalError) -> Value {
+    let mut object = json_error_object(error.kind(), &error.to_string(), error.span());
+    if let EvalError::ParseError(ParseError::LexError(errors)) = error {
+        object["errors"] = errors
+            .iter()
+            .map(|e| json_error_object(e.kind(), &e.to_string(), e.span()))
+            .collect();
+    }
+    object
+}
+
+fn json_error_object(kind: &str, message: &str, span: Span) -> Value {
+    json!({
+        "kind": kind,
+        "message": message,
+        "span": { "start": span.start, "end": span.end },
+    })
+}
+
+/// Evaluates each line of `input`, writing one line of output per expression.
+fn batch_mode<R: BufRead>(
+    evaluator: &mut Evaluator,
+    mut input: R,
+    time_format: &TimeFormat,
+    json_output: bool,
+) -> Result<(), io::Error> {
     loop {
         let mut expression = String::new();
-        match stdin.read_line(&mut expression) {
+        match input.read_line(&mut expression) {
             Ok(0) => return Result::Ok(()),
-            Ok(_) => match eval(expression.as_str()) {
-                Ok(result) => println!("{}", result),
-                Err(error) => eprintln!("{:?}", error),
+            Ok(_) => match evaluator.eval(expression.as_str()) {
+                Ok(result) => {
+                    if json_output {
+                        println!("{}", json_result(&result, time_format));
+                    } else {
+                        println!("{}", format_result(&result, time_format));
+                    }
+                    evaluator.record(result);
+                }
+                // With JSON output, errors are written to stdout with results, so that they can be
+                // read in order from a single stream.
+                Err(error) if json_output => println!("{}", json_error(&error)),
+                // Errors are kept to a single line so that each expression produces exactly one
+                // line of output.
+                Err(error) => eprintln!("{}", error),
             },
             Err(e) => return Result::Err(e),
         }
     }
 }
+
+/// Renders an error as the expression followed by a caret underline of the offending input and the
+/// error message. An error made up of several lexer errors is rendered as one snippet per error.
+fn render_error(expression: &str, error: &EvalError) -> String {
+    let mut rendered = String::new();
+    match error {
+        EvalError::ParseError(ParseError::LexError(errors)) => {
+            for error in errors {
+                rendered += &render_snippet(expression, error.span(), &error.to_string());
+            }
+        }
+        _ => rendered += &render_snippet(expression, error.span(), &error.to_string()),
+    }
+    rendered
+}
+
+fn render_snippet(expression: &str, span: Span, message: &str) -> String {
+    let column = expression[..span.start].chars().count();
+    let width = expression[span.start..span.end].chars().count().max(1);
+    format!(
+        "{}\n{}{} {}\n",
+        expression,
+        " ".repeat(column),
+        "^".repeat(width),
+        message
+    )
+}
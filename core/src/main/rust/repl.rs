@@ -0,0 +1,257 @@
+//! Interactive mode, which reads expressions typed at a terminal with a prompt, line editing and
+//! history.
+//!
+//! An expression which is incomplete when Enter is pressed, such as `(1:30:00 +`, continues on the
+//! next line. History is saved to `~/.time_calc_history`, or to the file named by the
+//! `TIME_CALC_HISTORY` environment variable. If that variable is empty, history is not saved.
+use std::env;
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::ValidationContext;
+use rustyline::validate::ValidationResult;
+use rustyline::validate::Validator;
+use rustyline::Config;
+use rustyline::Editor;
+use rustyline::Helper;
+use time_calc::Evaluator;
+use time_calc::TimeFormat;
+
+use crate::format_result;
+use crate::json_error;
+use crate::json_result;
+use crate::render_error;
+
+const PROMPT: &str = "> ";
+const HISTORY_FILE: &str = ".time_calc_history";
+const HISTORY_VAR: &str = "TIME_CALC_HISTORY";
+
+/// Holds the evaluator, so that the editor can check whether an expression is complete.
+struct ReplHelper {
+    evaluator: Evaluator,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if needs_more_input(&self.evaluator, ctx.input()) {
+            Result::Ok(ValidationResult::Incomplete)
+        } else {
+            Result::Ok(ValidationResult::Valid(Option::None))
+        }
+    }
+}
+
+impl Helper for ReplHelper {}
+
+/// The text written after evaluating an expression.
+#[derive(Debug, PartialEq, Eq)]
+enum Output {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Reads and evaluates expressions until the end of input. Ctrl-C cancels the expression being
+/// typed rather than exiting. History is saved however the session ends.
+pub fn interactive_mode(
+    evaluator: Evaluator,
+    time_format: &TimeFormat,
+    json_output: bool,
+) -> rustyline::Result<()> {
+    let config = Config::builder()
+        .auto_add_history(false)
+        .history_ignore_dups(true)?
+        .build();
+    let mut editor: Editor<ReplHelper, FileHistory> = Editor::with_config(config)?;
+    editor.set_helper(Option::Some(ReplHelper { evaluator }));
+
+    let history_path = history_path();
+    if let Option::Some(path) = &history_path {
+        // There is no history file the first time the calculator is used.
+        let _ = editor.load_history(path);
+    }
+
+    let result = run(&mut editor, time_format, json_output);
+
+    if let Option::Some(path) = &history_path {
+        if let Result::Err(error) = editor.save_history(path) {
+            eprintln!(
+                "time-calc: cannot save history to {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+    result
+}
+
+/// Reads and evaluates expressions until the end of input or an error reading from the terminal.
+fn run(
+    editor: &mut Editor<ReplHelper, FileHistory>,
+    time_format: &TimeFormat,
+    json_output: bool,
+) -> rustyline::Result<()> {
+    loop {
+        let input = match editor.readline(PROMPT) {
+            Result::Ok(input) => input,
+            Result::Err(ReadlineError::Interrupted) => continue,
+            Result::Err(ReadlineError::Eof) => return Result::Ok(()),
+            Result::Err(error) => return Result::Err(error),
+        };
+        if input.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(input.as_str())?;
+
+        let evaluator = &mut editor.helper_mut().unwrap().evaluator;
+        match eval_input(evaluator, &input, time_format, json_output) {
+            Option::Some(Output::Stdout(output)) => print!("{}", output),
+            Option::Some(Output::Stderr(output)) => eprint!("{}", output),
+            Option::None => {}
+        }
+    }
+}
+
+/// Returns whether the input is an incomplete expression which continues on the next line.
+fn needs_more_input(evaluator: &Evaluator, input: &str) -> bool {
+    !evaluator.is_complete(input)
+}
+
+/// Evaluates the input, which may have several lines, recording the result in the history. Returns
+/// the text to write, or `None` if the input is blank.
+fn eval_input(
+    evaluator: &mut Evaluator,
+    input: &str,
+    time_format: &TimeFormat,
+    json_output: bool,
+) -> Option<Output> {
+    if input.trim().is_empty() {
+        return Option::None;
+    }
+
+    // The lines of an expression are joined, so that an error can be shown under a single line.
+    let expression = input.replace(['\r', '\n'], " ");
+    Option::Some(match evaluator.eval(&expression) {
+        Result::Ok(result) => {
+            let output = if json_output {
+                format!("{}\n", json_result(&result, time_format))
+            } else {
+                format!("{}\n", format_result(&result, time_format))
+            };
+            evaluator.record(result);
+            Output::Stdout(output)
+        }
+        Result::Err(error) if json_output => Output::Stdout(format!("{}\n", json_error(&error))),
+        Result::Err(error) => Output::Stderr(render_error(&expression, &error)),
+    })
+}
+
+/// Returns the path of the history file, or `None` if history should not be saved.
+fn history_path() -> Option<PathBuf> {
+    match env::var_os(HISTORY_VAR) {
+        Option::Some(path) if path.is_empty() => Option::None,
+        Option::Some(path) => Option::Some(PathBuf::from(path)),
+        Option::None => env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(HISTORY_FILE)),
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use time_calc::EvalResult;
+    use time_calc::Evaluator;
+    use time_calc::TimeFormat;
+
+    use crate::repl::*;
+
+    #[test]
+    fn repl_eval_input() {
+        let mut evaluator = Evaluator::new();
+        assert_eq!(eval(&mut evaluator, "1h + 30m"), Option::Some(Output::Stdout("1:30:00\n".to_string())));
+        assert_eq!(eval(&mut evaluator, "$1 * 2"), Option::Some(Output::Stdout("3:00:00\n".to_string())));
+        assert_eq!(evaluator.history().len(), 2);
+
+        // Blank input is not evaluated.
+        assert_eq!(eval(&mut evaluator, ""), Option::None);
+        assert_eq!(eval(&mut evaluator, " \n"), Option::None);
+        assert_eq!(evaluator.history().len(), 2);
+
+        // Errors are not recorded.
+        assert_eq!(eval(&mut evaluator, "1 / 0"), Option::Some(Output::Stderr("1 / 0\n    ^ division by zero\n".to_string())));
+        assert_eq!(evaluator.history().len(), 2);
+    }
+
+    #[test]
+    fn repl_eval_input_json() {
+        let mut evaluator = Evaluator::new();
+        let time_format = TimeFormat::default();
+        assert_eq!(eval_input(&mut evaluator, "2 > 1", &time_format, true), Option::Some(Output::Stdout("{\"exact\":true,\"kind\":\"bool\",\"value\":\"true\"}\n".to_string())));
+        match eval_input(&mut evaluator, "1 / 0", &time_format, true) {
+            Option::Some(Output::Stdout(output)) => assert!(output.starts_with("{\"error\":{"), "{}", output),
+            output => panic!("expected a JSON error, found {:?}", output),
+        }
+        assert_eq!(evaluator.history(), &[EvalResult::Bool(true)]);
+    }
+
+    #[test]
+    fn repl_multi_line_input() {
+        let mut evaluator = Evaluator::new();
+        assert_eq!(session(&mut evaluator, &["(1h +", "30m)"]), vec![Option::Some(Output::Stdout("1:30:00\n".to_string()))]);
+        assert_eq!(session(&mut evaluator, &["x =", "", "45:00", "x * 2"]),
+            vec![Option::Some(Output::Stdout("45:00\n".to_string())), Option::Some(Output::Stdout("1:30:00\n".to_string()))]);
+
+        // The lines are joined, so that an error is shown under a single line.
+        assert_eq!(session(&mut evaluator, &["(1h +", "1 / 0)"]),
+            vec![Option::Some(Output::Stderr("(1h + 1 / 0)\n          ^ division by zero\n".to_string()))]);
+    }
+
+    #[test]
+    fn repl_needs_more_input() {
+        let evaluator = Evaluator::new();
+        assert!(!needs_more_input(&evaluator, ""));
+        assert!(!needs_more_input(&evaluator, "1h + 30m"));
+        assert!(!needs_more_input(&evaluator, "1h +* 30m"));
+        assert!(!needs_more_input(&evaluator, "(1h +\n30m)"));
+        assert!(needs_more_input(&evaluator, "(1h +"));
+        assert!(needs_more_input(&evaluator, "(1h +\n30m"));
+        assert!(needs_more_input(&evaluator, "x ="));
+    }
+
+    fn eval(evaluator: &mut Evaluator, input: &str) -> Option<Output> {
+        eval_input(evaluator, input, &TimeFormat::default(), false)
+    }
+
+    /// Evaluates lines as if they were typed at the prompt, continuing incomplete expressions on the
+    /// next line.
+    fn session(evaluator: &mut Evaluator, lines: &[&str]) -> Vec<Option<Output>> {
+        let mut outputs = Vec::new();
+        let mut input = String::new();
+        for line in lines {
+            if !input.is_empty() {
+                input.push('\n');
+            }
+            input.push_str(line);
+            if !needs_more_input(evaluator, &input) {
+                outputs.push(eval(evaluator, &input));
+                input.clear();
+            }
+        }
+        assert!(input.is_empty(), "incomplete input {:?}", input);
+        outputs
+    }
+}
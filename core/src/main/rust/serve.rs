@@ -0,0 +1,472 @@
+//! A JSON-RPC 2.0 server which reads one request per line from stdin and writes one response per
+//! line to stdout.
+//!
+//! The following methods are supported. Parameters are given by name.
+//! * `eval` - evaluates an `expression`, recording the result in the history.
+//! * `format` - formats a `time` using a `format`, or the current format if it is omitted.
+//! * `parse_time` - parses a `time`.
+//! * `set_option` - sets the option `name` to `value`.
+//!
+//! A time parameter is either a string such as `"1:30:00"`, or an exact time such as
+//! `{"seconds": 5400, "nanoseconds": 0}`.
+use std::convert::TryFrom;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde_json::json;
+use serde_json::Value;
+use time_calc::EvalResult;
+use time_calc::Evaluator;
+use time_calc::Time;
+use time_calc::TimeFormat;
+use time_calc::TimeUnit;
+
+use crate::json_error_object;
+use crate::json_eval_error;
+use crate::json_result;
+use crate::parse_overflow_policy;
+use crate::parse_rounding_strategy;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The error code of an expression or time which could not be evaluated or parsed. The error data
+/// is an error object in the same form as the `--json` output.
+const EVAL_ERROR: i64 = 1;
+
+/// An error response to a request.
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: &str) -> RpcError {
+        RpcError {
+            code,
+            message: message.to_string(),
+            data: Option::None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut error = json!({ "code": self.code, "message": self.message });
+        if let Option::Some(data) = &self.data {
+            error["data"] = data.clone();
+        }
+        error
+    }
+}
+
+/// Serves requests until the end of stdin.
+pub fn serve_mode(evaluator: &mut Evaluator, time_format: &mut TimeFormat) -> io::Result<()> {
+    serve(
+        evaluator,
+        time_format,
+        io::stdin().lock(),
+        io::stdout().lock(),
+    )
+}
+
+/// Serves requests read from `input` until the end of input, writing responses to `output`. A line
+/// which is not valid UTF-8 is answered with a parse error, like any other line which is not JSON.
+fn serve<R: BufRead, W: Write>(
+    evaluator: &mut Evaluator,
+    time_format: &mut TimeFormat,
+    mut input: R,
+    mut output: W,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if input.read_until(b'\n', &mut line)? == 0 {
+            return Result::Ok(());
+        }
+        let response = match std::str::from_utf8(&line) {
+            Result::Ok(line) if line.trim().is_empty() => continue,
+            Result::Ok(line) => handle_message(evaluator, time_format, line),
+            Result::Err(_) => Option::Some(parse_error()),
+        };
+        if let Option::Some(response) = response {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+}
+
+/// Handles a single request or a batch of requests, returning the response, or `None` if there is
+/// nothing to respond to because the message only contains notifications.
+fn handle_message(
+    evaluator: &mut Evaluator,
+    time_format: &mut TimeFormat,
+    line: &str,
+) -> Option<Value> {
+    let message = match serde_json::from_str(line) {
+        Result::Ok(message) => message,
+        Result::Err(_) => return Option::Some(parse_error()),
+    };
+    match message {
+        Value::Array(requests) if requests.is_empty() => Option::Some(error_response(
+            Value::Null,
+            &RpcError::new(INVALID_REQUEST, "invalid request"),
+        )),
+        Value::Array(requests) => {
+            let responses: Vec<Value> = requests
+                .into_iter()
+                .filter_map(|request| handle_request(evaluator, time_format, request))
+                .collect();
+            if responses.is_empty() {
+                Option::None
+            } else {
+                Option::Some(Value::Array(responses))
+            }
+        }
+        request => handle_request(evaluator, time_format, request),
+    }
+}
+
+/// Handles a single request, returning the response, or `None` if the request is a notification.
+fn handle_request(
+    evaluator: &mut Evaluator,
+    time_format: &mut TimeFormat,
+    request: Value,
+) -> Option<Value> {
+    // An invalid request is always answered, with the request's id if it could be read.
+    let invalid_request = |id: Option<&Value>| {
+        Option::Some(error_response(
+            id.cloned().unwrap_or(Value::Null),
+            &RpcError::new(INVALID_REQUEST, "invalid request"),
+        ))
+    };
+    let id = match request.get("id") {
+        Option::Some(id @ Value::Null)
+        | Option::Some(id @ Value::Number(_))
+        | Option::Some(id @ Value::String(_)) => Option::Some(id.clone()),
+        Option::Some(_) => return invalid_request(Option::None),
+        Option::None => Option::None,
+    };
+    let method = match (request.get("jsonrpc"), request.get("method")) {
+        (Option::Some(Value::String(version)), Option::Some(Value::String(method)))
+            if version == "2.0" =>
+        {
+            method
+        }
+        _ => return invalid_request(id.as_ref()),
+    };
+    let params = match request.get("params") {
+        Option::Some(params @ Value::Object(_)) => params.clone(),
+        Option::None => json!({}),
+        Option::Some(_) => {
+            let error = RpcError::new(INVALID_PARAMS, "params must be an object");
+            return id.map(|id| error_response(id, &error));
+        }
+    };
+
+    let result = call(evaluator, time_format, method, &params);
+    let id = id?;
+    Option::Some(match result {
+        Result::Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Result::Err(error) => error_response(id, &error),
+    })
+}
+
+fn parse_error() -> Value {
+    error_response(Value::Null, &RpcError::new(PARSE_ERROR, "parse error"))
+}
+
+fn error_response(id: Value, error: &RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": error.to_json() })
+}
+
+fn call(
+    evaluator: &mut Evaluator,
+    time_format: &mut TimeFormat,
+    method: &str,
+    params: &Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "eval" => {
+            let expression = string_param(params, "expression")?;
+            match evaluator.eval(expression) {
+                Result::Ok(result) => {
+                    let value = json_result(&result, time_format);
+                    evaluator.record(result);
+                    Result::Ok(value)
+                }
+                Result::Err(error) => Result::Err(RpcError {
+                    code: EVAL_ERROR,
+                    message: error.to_string(),
+                    data: Option::Some(json_eval_error(&error)),
+                }),
+            }
+        }
+        "format" => {
+            let time = time_param(params, "time")?;
+            let format = match params.get("format") {
+                Option::Some(Value::String(format)) => format.parse().map_err(|e| {
+                    RpcError::new(INVALID_PARAMS, &format!("invalid format: {}", e))
+                })?,
+                Option::Some(_) => {
+                    return Result::Err(RpcError::new(INVALID_PARAMS, "'format' must be a string"))
+                }
+                Option::None => *time_format,
+            };
+            Result::Ok(json!({ "value": time.format(&format) }))
+        }
+        "parse_time" => {
+            let time = time_param(params, "time")?;
+            Result::Ok(json_result(&EvalResult::Time(time), time_format))
+        }
+        "set_option" => {
+            let name = string_param(params, "name")?;
+            let value = params
+                .get("value")
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing parameter 'value'"))?;
+            set_option(evaluator, time_format, name, value)?;
+            Result::Ok(Value::Null)
+        }
+        _ => Result::Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            &format!("unknown method '{}'", method),
+        )),
+    }
+}
+
+/// Sets an option, using the same names and values as the command line options.
+fn set_option(
+    evaluator: &mut Evaluator,
+    time_format: &mut TimeFormat,
+    name: &str,
+    value: &Value,
+) -> Result<(), RpcError> {
+    let invalid = || {
+        RpcError::new(
+            INVALID_PARAMS,
+            &format!("invalid value for option '{}'", name),
+        )
+    };
+    match (name, value) {
+        ("overflow", Value::String(policy)) => {
+            evaluator.overflow_policy(parse_overflow_policy(policy).ok_or_else(invalid)?);
+        }
+        ("precision", Value::String(unit)) => {
+            evaluator.time_precision(TimeUnit::from_suffix(unit).ok_or_else(invalid)?);
+        }
+        ("places", Value::Number(places)) => {
+            let places = places
+                .as_u64()
+                .and_then(|p| u32::try_from(p).ok())
+                .ok_or_else(invalid)?;
+            evaluator.number_precision(places);
+        }
+        ("rounding", Value::String(strategy)) => {
+            evaluator.rounding_strategy(parse_rounding_strategy(strategy).ok_or_else(invalid)?);
+        }
+        ("format", Value::String(format)) => {
+            *time_format = format.parse().map_err(|_| invalid())?
+        }
+        ("implicit_multiplication", Value::Bool(enabled)) => {
+            evaluator.implicit_multiplication(*enabled);
+        }
+        ("default_unit", Value::String(unit)) => {
+            evaluator.default_unit(Option::Some(TimeUnit::from_name(unit).ok_or_else(invalid)?));
+        }
+        ("default_unit", Value::Null) => {
+            evaluator.default_unit(Option::None);
+        }
+        ("overflow", _)
+        | ("precision", _)
+        | ("places", _)
+        | ("rounding", _)
+        | ("format", _)
+        | ("implicit_multiplication", _)
+        | ("default_unit", _) => return Result::Err(invalid()),
+        _ => {
+            return Result::Err(RpcError::new(
+                INVALID_PARAMS,
+                &format!("unknown option '{}'", name),
+            ))
+        }
+    }
+    Result::Ok(())
+}
+
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    match params.get(name) {
+        Option::Some(Value::String(s)) => Result::Ok(s),
+        Option::Some(_) => Result::Err(RpcError::new(
+            INVALID_PARAMS,
+            &format!("'{}' must be a string", name),
+        )),
+        Option::None => Result::Err(RpcError::new(
+            INVALID_PARAMS,
+            &format!("missing parameter '{}'", name),
+        )),
+    }
+}
+
+/// Returns a time parameter, which is either a string to parse or an exact time.
+fn time_param(params: &Value, name: &str) -> Result<Time, RpcError> {
+    match params.get(name) {
+        Option::Some(Value::String(s)) => Time::from_str(s).map_err(|e| {
+            let message = format!("invalid time '{}': {}", s, e);
+            RpcError {
+                code: EVAL_ERROR,
+                data: Option::Some(json_error_object("invalid_time", &message, e.span())),
+                message,
+            }
+        }),
+        Option::Some(exact @ Value::Object(_)) => {
+            let seconds = exact.get("seconds").and_then(Value::as_i64);
+            let nanoseconds = exact
+                .get("nanoseconds")
+                .and_then(Value::as_u64)
+                .filter(|n| *n < 1_000_000_000);
+            match (seconds, nanoseconds) {
+                (Option::Some(seconds), Option::Some(nanoseconds)) => {
+                    let decimal = Decimal::from(seconds) + Decimal::new(nanoseconds as i64, 9);
+                    Time::try_from(decimal).map_err(|_| {
+                        RpcError::new(INVALID_PARAMS, &format!("'{}' is out of range", name))
+                    })
+                }
+                _ => Result::Err(RpcError::new(
+                    INVALID_PARAMS,
+                    &format!(
+                        "'{}' must have whole number 'seconds' and 'nanoseconds' less than one \
+                         second",
+                        name
+                    ),
+                )),
+            }
+        }
+        Option::Some(_) => Result::Err(RpcError::new(
+            INVALID_PARAMS,
+            &format!("'{}' must be a string or an exact time", name),
+        )),
+        Option::None => Result::Err(RpcError::new(
+            INVALID_PARAMS,
+            &format!("missing parameter '{}'", name),
+        )),
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use serde_json::json;
+    use serde_json::Value;
+    use time_calc::Evaluator;
+    use time_calc::TimeFormat;
+
+    use crate::serve::handle_message;
+    use crate::serve::serve;
+
+    #[test]
+    fn serve_eval() {
+        let mut server = Server::new();
+        assert_eq!(server.request(1, "eval", json!({ "expression": "1h + 30m" })), json!({ "jsonrpc": "2.0", "id": 1, "result": { "kind": "time", "value": "1:30:00", "exact": { "seconds": 5400, "nanoseconds": 0 } } }));
+        assert_eq!(server.request(2, "eval", json!({ "expression": "$1 * 2" })), json!({ "jsonrpc": "2.0", "id": 2, "result": { "kind": "time", "value": "3:00:00", "exact": { "seconds": 10800, "nanoseconds": 0 } } }));
+        assert_eq!(server.request(3, "eval", json!({ "expression": "1 / 0" })), json!({ "jsonrpc": "2.0", "id": 3, "error": { "code": 1, "message": "division by zero", "data": { "kind": "divide_by_zero", "message": "division by zero", "span": { "start": 4, "end": 5 } } } }));
+        assert_eq!(server.request(4, "eval", json!({})), error(4, -32602, "missing parameter 'expression'"));
+        // A digit which is not an ASCII digit does not stop the server.
+        assert_eq!(server.request(5, "eval", json!({ "expression": "٣" }))["error"]["message"], "unexpected character '٣'");
+    }
+
+    #[test]
+    fn serve_format() {
+        let mut server = Server::new();
+        assert_eq!(server.request(1, "format", json!({ "time": "90m" })), json!({ "jsonrpc": "2.0", "id": 1, "result": { "value": "1:30:00" } }));
+        assert_eq!(server.request(2, "format", json!({ "time": { "seconds": -2, "nanoseconds": 500000000 }, "format": "seconds" })), json!({ "jsonrpc": "2.0", "id": 2, "result": { "value": "-1.5" } }));
+        assert_eq!(server.request(3, "format", json!({ "time": { "seconds": 0, "nanoseconds": 1000000000 } })), error(3, -32602, "'time' must have whole number 'seconds' and 'nanoseconds' less than one second"));
+        assert_eq!(server.request(4, "format", json!({ "time": "1h", "format": "fancy" })), error(4, -32602, "invalid format: unknown time format 'fancy'"));
+    }
+
+    #[test]
+    fn serve_parse_time() {
+        let mut server = Server::new();
+        assert_eq!(server.request(1, "parse_time", json!({ "time": "1:02:03.5" })), json!({ "jsonrpc": "2.0", "id": 1, "result": { "kind": "time", "value": "1:02:03.5", "exact": { "seconds": 3723, "nanoseconds": 500000000 } } }));
+        assert_eq!(server.request(2, "parse_time", json!({ "time": "1:x" }))["error"]["data"]["kind"], json!("invalid_time"));
+    }
+
+    #[test]
+    fn serve_set_option() {
+        let mut server = Server::new();
+        assert_eq!(server.request(1, "set_option", json!({ "name": "format", "value": "seconds" })), json!({ "jsonrpc": "2.0", "id": 1, "result": null }));
+        assert_eq!(server.request(2, "format", json!({ "time": "90m" })), json!({ "jsonrpc": "2.0", "id": 2, "result": { "value": "5400" } }));
+        assert_eq!(server.request(3, "set_option", json!({ "name": "default_unit", "value": "minutes" })), json!({ "jsonrpc": "2.0", "id": 3, "result": null }));
+        assert_eq!(server.request(4, "eval", json!({ "expression": "1h + 30" }))["result"]["value"], json!("5400"));
+        assert_eq!(server.request(5, "set_option", json!({ "name": "places", "value": "two" })), error(5, -32602, "invalid value for option 'places'"));
+        assert_eq!(server.request(6, "set_option", json!({ "name": "colour", "value": "red" })), error(6, -32602, "unknown option 'colour'"));
+    }
+
+    #[test]
+    fn serve_protocol_errors() {
+        let mut server = Server::new();
+        assert_eq!(server.send("{"), Option::Some(error(Value::Null, -32700, "parse error")));
+        assert_eq!(server.send("[]"), Option::Some(error(Value::Null, -32600, "invalid request")));
+        assert_eq!(server.send(r#"{"jsonrpc":"1.0","id":1,"method":"eval"}"#), Option::Some(error(1, -32600, "invalid request")));
+        assert_eq!(server.send(r#"{"jsonrpc":"2.0","id":"a","method":1}"#), Option::Some(error("a", -32600, "invalid request")));
+        assert_eq!(server.send(r#"{"jsonrpc":"2.0","method":"eval","params":[]}"#), Option::None);
+        assert_eq!(server.send(r#"{"jsonrpc":"2.0"}"#), Option::Some(error(Value::Null, -32600, "invalid request")));
+        assert_eq!(server.send(r#"{"jsonrpc":"2.0","id":[1],"method":"eval"}"#), Option::Some(error(Value::Null, -32600, "invalid request")));
+        assert_eq!(server.send(r#"{"jsonrpc":"2.0","id":1,"method":"eval","params":[]}"#), Option::Some(error(1, -32602, "params must be an object")));
+        assert_eq!(server.request(1, "exit", json!({})), error(1, -32601, "unknown method 'exit'"));
+    }
+
+    #[test]
+    fn serve_notifications_and_batches() {
+        let mut server = Server::new();
+        assert_eq!(server.send(r#"{"jsonrpc":"2.0","method":"eval","params":{"expression":"2"}}"#), Option::None);
+        assert_eq!(server.send(r#"[{"jsonrpc":"2.0","method":"eval","params":{"expression":"3"}}]"#), Option::None);
+        assert_eq!(
+            server.send(r#"[{"jsonrpc":"2.0","id":"a","method":"eval","params":{"expression":"$1 + $2"}},{"jsonrpc":"2.0","method":"eval","params":{"expression":"4"}},{"jsonrpc":"2.0","id":"b","method":"nope"}]"#),
+            Option::Some(json!([
+                { "jsonrpc": "2.0", "id": "a", "result": { "kind": "number", "value": "5", "exact": "5" } },
+                error("b", -32601, "unknown method 'nope'"),
+            ])));
+    }
+
+    #[test]
+    fn serve_invalid_utf8() {
+        let mut evaluator = Evaluator::new();
+        let mut time_format = TimeFormat::default();
+        let input: &[u8] = b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"eval\",\"params\":{\"expression\":\"1h\"}}\n\xff\xfe\n\n{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"eval\",\"params\":{\"expression\":\"$1 * 2\"}}";
+        let mut output = Vec::new();
+        serve(&mut evaluator, &mut time_format, input, &mut output).unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"]["value"], "1:00:00");
+        assert_eq!(responses[1], error(Value::Null, -32700, "parse error"));
+        assert_eq!(responses[2]["result"]["value"], "2:00:00");
+    }
+
+    struct Server {
+        evaluator: Evaluator,
+        time_format: TimeFormat,
+    }
+
+    impl Server {
+        fn new() -> Server {
+            Server { evaluator: Evaluator::new(), time_format: TimeFormat::default() }
+        }
+
+        fn send(&mut self, line: &str) -> Option<Value> {
+            handle_message(&mut self.evaluator, &mut self.time_format, line)
+        }
+
+        fn request(&mut self, id: i64, method: &str, params: Value) -> Value {
+            let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+            self.send(&request.to_string()).unwrap()
+        }
+    }
+
+    fn error<I: Into<Value>>(id: I, code: i64, message: &str) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id.into(), "error": { "code": code, "message": message } })
+    }
+}
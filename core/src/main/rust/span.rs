@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// A range of byte offsets into an input string.
+///
+/// The range includes `start` and excludes `end`. An empty span (where `start == end`) refers to
+/// the position between two characters, such as the end of the input.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Returns a new span from `start` (inclusive) to `end` (exclusive).
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// Returns the smallest span which contains both this span and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns this span moved forward by `offset` bytes.
+    pub fn offset(self, offset: usize) -> Span {
+        Span::new(self.start + offset, self.end + offset)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
@@ -0,0 +1,428 @@
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy;
+
+use crate::time::Time;
+
+/// A style for formatting a time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeStyle {
+    /// `h:mm:ss`, `mm:ss` or `ss` seconds, such as `1:02:03`, `02:03` or `3s`.
+    Default,
+    /// `h:mm:ss`, always showing hours, such as `0:02:03`.
+    Clock,
+    /// `hh:mm:ss`, always showing at least two digit hours, such as `00:02:03`.
+    FixedClock,
+    /// Days followed by `hh:mm:ss` for times of at least one day, such as `5d 03:45:00`.
+    Days,
+    /// A decimal number of seconds, such as `123.5`.
+    DecimalSeconds,
+    /// A decimal number of hours, such as `1.5`.
+    DecimalHours,
+    /// Unit-suffixed components, such as `1h 2m 3s`.
+    Units,
+    /// An ISO 8601 duration, such as `PT1H2M3S`.
+    Iso8601,
+}
+
+/// A specification for formatting a time.
+///
+/// A format is made up of a [`TimeStyle`](enum.TimeStyle.html) and an optional fixed number of
+/// fractional digits. Without a fixed number of digits, fractional seconds are written with
+/// trailing zeros removed, and decimal hours are rounded to 9 decimal places.
+///
+/// Formats can also be parsed from a string naming the style, optionally followed by `.` and the
+/// number of fractional digits, such as `hh:mm:ss.3`. The style names are `default`, `h:mm:ss`,
+/// `hh:mm:ss`, `days`, `seconds`, `hours`, `units` and `iso8601`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeFormat {
+    style: TimeStyle,
+    fraction_digits: Option<u32>,
+}
+
+/// An error which occurred while parsing a time format.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimeFormatError {
+    UnknownStyle(String),
+    InvalidFractionDigits(String),
+}
+
+impl fmt::Display for TimeFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeFormatError::UnknownStyle(style) => write!(f, "unknown time format '{}'", style),
+            TimeFormatError::InvalidFractionDigits(digits) => write!(
+                f,
+                "fractional digits must be a number from 0 to 9, found '{}'",
+                digits
+            ),
+        }
+    }
+}
+
+impl error::Error for TimeFormatError {}
+
+impl TimeFormat {
+    const MAX_FRACTION_DIGITS: u32 = 9;
+    const DECIMAL_HOURS_DIGITS: u32 = 9;
+
+    /// Returns a new format with the given style.
+    pub fn new(style: TimeStyle) -> TimeFormat {
+        TimeFormat {
+            style,
+            fraction_digits: Option::None,
+        }
+    }
+
+    /// Sets the style.
+    pub fn style(&mut self, style: TimeStyle) -> &mut TimeFormat {
+        self.style = style;
+        self
+    }
+
+    /// Sets a fixed number of fractional digits. Times are rounded to this number of digits, with
+    /// midpoints rounded away from zero, and padded with trailing zeros. At most 9 digits are
+    /// written; larger values are treated as 9.
+    pub fn fraction_digits(&mut self, fraction_digits: u32) -> &mut TimeFormat {
+        self.fraction_digits = Option::Some(fraction_digits.min(TimeFormat::MAX_FRACTION_DIGITS));
+        self
+    }
+
+    /// Formats a time.
+    pub fn format(&self, time: Time) -> String {
+        match self.style {
+            TimeStyle::DecimalSeconds => return self.format_decimal(Decimal::from(time)),
+            TimeStyle::DecimalHours => {
+                let hours = Decimal::from(time) / Decimal::from(Time::SECONDS_PER_HOUR);
+                return self.format_decimal(hours);
+            }
+            _ => (),
+        }
+
+        let time = self.round(time);
+        let hours = time.hours();
+        let minutes = time.minutes();
+        let seconds = time.seconds();
+        let fraction = self.fraction(time.nanoseconds());
+
+        let mut formatted = String::new();
+        if time.signum() == -1 {
+            formatted.push('-');
+        }
+        match self.style {
+            TimeStyle::Default => {
+                if hours > 0 {
+                    formatted += &format!("{}:{:02}:{:02}{}", hours, minutes, seconds, fraction);
+                } else if minutes > 0 {
+                    formatted += &format!("{:02}:{:02}{}", minutes, seconds, fraction);
+                } else {
+                    formatted += &format!("{}{}s", seconds, fraction);
+                }
+            }
+            TimeStyle::Days if hours >= Time::HOURS_PER_DAY => {
+                formatted += &format!(
+                    "{}d {:02}:{:02}:{:02}{}",
+                    hours / Time::HOURS_PER_DAY,
+                    hours % Time::HOURS_PER_DAY,
+                    minutes,
+                    seconds,
+                    fraction
+                );
+            }
+            TimeStyle::Days => {
+                return TimeFormat {
+                    style: TimeStyle::Default,
+                    ..*self
+                }
+                .format(time)
+            }
+            TimeStyle::Clock => {
+                formatted += &format!("{}:{:02}:{:02}{}", hours, minutes, seconds, fraction);
+            }
+            TimeStyle::FixedClock => {
+                formatted += &format!("{:02}:{:02}:{:02}{}", hours, minutes, seconds, fraction);
+            }
+            TimeStyle::Units => {
+                let mut components = Vec::new();
+                if hours > 0 {
+                    components.push(format!("{}h", hours));
+                }
+                if minutes > 0 {
+                    components.push(format!("{}m", minutes));
+                }
+                if seconds > 0 || !fraction.is_empty() || components.is_empty() {
+                    components.push(format!("{}{}s", seconds, fraction));
+                }
+                formatted += &components.join(" ");
+            }
+            TimeStyle::Iso8601 => {
+                formatted += "PT";
+                if hours > 0 {
+                    formatted += &format!("{}H", hours);
+                }
+                if minutes > 0 {
+                    formatted += &format!("{}M", minutes);
+                }
+                if seconds > 0 || !fraction.is_empty() || (hours == 0 && minutes == 0) {
+                    formatted += &format!("{}{}S", seconds, fraction);
+                }
+            }
+            TimeStyle::DecimalSeconds | TimeStyle::DecimalHours => unreachable!(),
+        }
+        formatted
+    }
+
+    /// Rounds a time to the fixed number of fractional digits, if there is one. A time which would
+    /// overflow when rounded is truncated instead.
+    fn round(&self, time: Time) -> Time {
+        match self.fraction_digits {
+            Option::Some(digits) if digits < TimeFormat::MAX_FRACTION_DIGITS => {
+                let rounded = Decimal::from(time)
+                    .round_dp_with_strategy(digits, RoundingStrategy::MidpointAwayFromZero);
+                Time::try_from(rounded).unwrap_or_else(|_| {
+                    let truncated = Decimal::from(time)
+                        .round_dp_with_strategy(digits, RoundingStrategy::ToZero);
+                    Time::try_from(truncated).unwrap()
+                })
+            }
+            _ => time,
+        }
+    }
+
+    /// Returns the fractional seconds, including the leading `.`, or an empty string if there are
+    /// no fractional digits to write.
+    fn fraction(&self, nanoseconds: u32) -> String {
+        match self.fraction_digits {
+            Option::Some(0) => String::new(),
+            Option::Some(digits) => {
+                format!(".{}", &format!("{:09}", nanoseconds)[..digits as usize])
+            }
+            Option::None if nanoseconds > 0 => format!(".{}", trim_fraction(nanoseconds)),
+            Option::None => String::new(),
+        }
+    }
+
+    fn format_decimal(&self, decimal: Decimal) -> String {
+        let digits = match (self.fraction_digits, self.style) {
+            (Option::Some(digits), _) => digits,
+            (Option::None, TimeStyle::DecimalHours) => TimeFormat::DECIMAL_HOURS_DIGITS,
+            (Option::None, _) => TimeFormat::MAX_FRACTION_DIGITS,
+        };
+        let mut decimal =
+            decimal.round_dp_with_strategy(digits, RoundingStrategy::MidpointAwayFromZero);
+        if decimal.is_zero() {
+            decimal.set_sign_positive(true);
+        }
+
+        match self.fraction_digits {
+            Option::Some(digits) => format!("{:.*}", digits as usize, decimal),
+            Option::None => decimal.normalize().to_string(),
+        }
+    }
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        TimeFormat::new(TimeStyle::Default)
+    }
+}
+
+impl FromStr for TimeFormat {
+    type Err = TimeFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Split the number of fractional digits from the end, if there is one.
+        let (name, digits) = match s.rfind('.') {
+            Option::Some(i) => (&s[..i], Option::Some(&s[i + 1..])),
+            Option::None => (s, Option::None),
+        };
+
+        let style = match name {
+            "default" => TimeStyle::Default,
+            "h:mm:ss" => TimeStyle::Clock,
+            "hh:mm:ss" => TimeStyle::FixedClock,
+            "days" => TimeStyle::Days,
+            "seconds" => TimeStyle::DecimalSeconds,
+            "hours" => TimeStyle::DecimalHours,
+            "units" => TimeStyle::Units,
+            "iso8601" => TimeStyle::Iso8601,
+            _ => return Result::Err(TimeFormatError::UnknownStyle(name.to_string())),
+        };
+
+        let mut format = TimeFormat::new(style);
+        if let Option::Some(digits) = digits {
+            match digits.parse() {
+                Result::Ok(d) if d <= TimeFormat::MAX_FRACTION_DIGITS => format.fraction_digits(d),
+                _ => {
+                    return Result::Err(TimeFormatError::InvalidFractionDigits(digits.to_string()))
+                }
+            };
+        }
+        Result::Ok(format)
+    }
+}
+
+/// Returns the digits of a fraction of a second, without trailing zeros.
+fn trim_fraction(nanoseconds: u32) -> String {
+    let mut nanos = format!("{:09}", nanoseconds);
+    // Remove trailing zeros.
+    while let Some(c) = nanos.pop() {
+        // Found non-'0'. Put it back and break from loop.
+        if c != '0' {
+            nanos.push(c);
+            break;
+        }
+    }
+    nanos
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use crate::time::format::TimeFormat;
+    use crate::time::format::TimeFormatError;
+    use crate::time::format::TimeStyle;
+    use crate::time::Time;
+
+    #[test]
+    fn format_default() {
+        assert_format(TimeStyle::Default, time(0, 0, 0, 0), "0s");
+        assert_format(TimeStyle::Default, time(0, 0, 3, 500000000), "3.5s");
+        assert_format(TimeStyle::Default, time(0, 2, 3, 0), "02:03");
+        assert_format(TimeStyle::Default, time(1, 2, 3, 40000000), "1:02:03.04");
+        assert_format(TimeStyle::Default, neg_time(1, 2, 3, 0), "-1:02:03");
+    }
+
+    #[test]
+    fn format_clock() {
+        assert_format(TimeStyle::Clock, time(0, 0, 0, 0), "0:00:00");
+        assert_format(TimeStyle::Clock, time(0, 0, 3, 500000000), "0:00:03.5");
+        assert_format(TimeStyle::Clock, time(123, 2, 3, 0), "123:02:03");
+        assert_format(TimeStyle::Clock, neg_time(0, 2, 3, 0), "-0:02:03");
+
+        assert_format(TimeStyle::FixedClock, time(0, 0, 0, 0), "00:00:00");
+        assert_format(TimeStyle::FixedClock, time(1, 2, 3, 0), "01:02:03");
+        assert_format(TimeStyle::FixedClock, time(123, 2, 3, 0), "123:02:03");
+        assert_format(TimeStyle::FixedClock, neg_time(1, 2, 3, 100), "-01:02:03.0000001");
+    }
+
+    #[test]
+    fn format_days() {
+        assert_format(TimeStyle::Days, time(0, 2, 3, 0), "02:03");
+        assert_format(TimeStyle::Days, time(23, 2, 3, 0), "23:02:03");
+        assert_format(TimeStyle::Days, time(123, 45, 0, 0), "5d 03:45:00");
+        assert_format(TimeStyle::Days, neg_time(24, 0, 0, 500000000), "-1d 00:00:00.5");
+    }
+
+    #[test]
+    fn format_decimal() {
+        assert_format(TimeStyle::DecimalSeconds, time(0, 0, 0, 0), "0");
+        assert_format(TimeStyle::DecimalSeconds, time(1, 2, 3, 500000000), "3723.5");
+        assert_format(TimeStyle::DecimalSeconds, neg_time(0, 0, 0, 1), "-0.000000001");
+        assert_format(TimeStyle::DecimalSeconds, Time::MAX, "9223372036854775807.999999999");
+
+        assert_format(TimeStyle::DecimalHours, time(0, 0, 0, 0), "0");
+        assert_format(TimeStyle::DecimalHours, time(1, 30, 0, 0), "1.5");
+        assert_format(TimeStyle::DecimalHours, time(1, 2, 3, 0), "1.034166667");
+        assert_format(TimeStyle::DecimalHours, neg_time(0, 15, 0, 0), "-0.25");
+        assert_format(TimeStyle::DecimalHours, time(0, 0, 0, 1), "0");
+    }
+
+    #[test]
+    fn format_units() {
+        assert_format(TimeStyle::Units, time(0, 0, 0, 0), "0s");
+        assert_format(TimeStyle::Units, time(0, 0, 0, 500000000), "0.5s");
+        assert_format(TimeStyle::Units, time(1, 0, 0, 0), "1h");
+        assert_format(TimeStyle::Units, time(1, 2, 3, 0), "1h 2m 3s");
+        assert_format(TimeStyle::Units, time(1, 0, 3, 0), "1h 3s");
+        assert_format(TimeStyle::Units, time(100, 30, 0, 0), "100h 30m");
+        assert_format(TimeStyle::Units, neg_time(0, 2, 3, 250000000), "-2m 3.25s");
+    }
+
+    #[test]
+    fn format_iso8601() {
+        assert_format(TimeStyle::Iso8601, time(0, 0, 0, 0), "PT0S");
+        assert_format(TimeStyle::Iso8601, time(1, 2, 3, 500000000), "PT1H2M3.5S");
+        assert_format(TimeStyle::Iso8601, neg_time(1, 0, 0, 0), "-PT1H");
+    }
+
+    #[test]
+    fn format_fraction_digits() {
+        assert_format_digits(TimeStyle::Default, 3, time(0, 0, 3, 0), "3.000s");
+        assert_format_digits(TimeStyle::Default, 3, time(0, 0, 3, 123456789), "3.123s");
+        assert_format_digits(TimeStyle::Default, 3, time(0, 0, 3, 999500000), "4.000s");
+        assert_format_digits(TimeStyle::Default, 0, time(0, 0, 3, 500000000), "4s");
+        assert_format_digits(TimeStyle::Default, 0, neg_time(0, 0, 3, 500000000), "-4s");
+        assert_format_digits(TimeStyle::Default, 9, time(0, 0, 3, 5), "3.000000005s");
+        assert_format_digits(TimeStyle::Default, 20, time(0, 0, 3, 5), "3.000000005s");
+        assert_format_digits(TimeStyle::Default, 2, time(0, 59, 59, 995000000), "1:00:00.00");
+        assert_format_digits(TimeStyle::FixedClock, 2, time(1, 2, 3, 0), "01:02:03.00");
+        assert_format_digits(TimeStyle::Clock, 1, neg_time(0, 0, 0, 40000000), "0:00:00.0");
+        assert_format_digits(TimeStyle::Units, 1, time(1, 0, 0, 0), "1h 0.0s");
+        assert_format_digits(TimeStyle::Iso8601, 0, time(0, 1, 0, 400000000), "PT1M");
+        assert_format_digits(TimeStyle::DecimalSeconds, 2, time(0, 0, 3, 0), "3.00");
+        assert_format_digits(TimeStyle::DecimalSeconds, 0, time(0, 0, 3, 500000000), "4");
+        assert_format_digits(TimeStyle::DecimalHours, 2, time(1, 2, 3, 0), "1.03");
+        assert_format_digits(TimeStyle::DecimalHours, 2, neg_time(0, 0, 1, 0), "0.00");
+
+        // Rounding which would overflow is truncated instead.
+        assert_format_digits(TimeStyle::Default, 0, Time::MAX, "2562047788015215:30:07");
+        assert_format_digits(TimeStyle::Default, 0, Time::MIN, "-2562047788015215:30:07");
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!("default".parse(), Result::Ok(TimeFormat::new(TimeStyle::Default)));
+        assert_eq!("h:mm:ss".parse(), Result::Ok(TimeFormat::new(TimeStyle::Clock)));
+        assert_eq!("hh:mm:ss".parse(), Result::Ok(TimeFormat::new(TimeStyle::FixedClock)));
+        assert_eq!("days".parse(), Result::Ok(TimeFormat::new(TimeStyle::Days)));
+        assert_eq!("seconds".parse(), Result::Ok(TimeFormat::new(TimeStyle::DecimalSeconds)));
+        assert_eq!("hours".parse(), Result::Ok(TimeFormat::new(TimeStyle::DecimalHours)));
+        assert_eq!("units".parse(), Result::Ok(TimeFormat::new(TimeStyle::Units)));
+        assert_eq!("iso8601".parse(), Result::Ok(TimeFormat::new(TimeStyle::Iso8601)));
+        assert_eq!("hh:mm:ss.3".parse(), Result::Ok(*TimeFormat::new(TimeStyle::FixedClock).fraction_digits(3)));
+        assert_eq!("hours.0".parse(), Result::Ok(*TimeFormat::new(TimeStyle::DecimalHours).fraction_digits(0)));
+
+        assert_eq!("".parse::<TimeFormat>(), Result::Err(TimeFormatError::UnknownStyle("".to_string())));
+        assert_eq!("minutes".parse::<TimeFormat>(), Result::Err(TimeFormatError::UnknownStyle("minutes".to_string())));
+        assert_eq!("Default".parse::<TimeFormat>(), Result::Err(TimeFormatError::UnknownStyle("Default".to_string())));
+        assert_eq!("hours.".parse::<TimeFormat>(), Result::Err(TimeFormatError::InvalidFractionDigits("".to_string())));
+        assert_eq!("hours.10".parse::<TimeFormat>(), Result::Err(TimeFormatError::InvalidFractionDigits("10".to_string())));
+        assert_eq!("hours.-1".parse::<TimeFormat>(), Result::Err(TimeFormatError::InvalidFractionDigits("-1".to_string())));
+    }
+
+    #[test]
+    fn format_round_trip() {
+        let styles = [TimeStyle::Default, TimeStyle::Clock, TimeStyle::FixedClock, TimeStyle::Days,
+            TimeStyle::Units, TimeStyle::Iso8601];
+        let times = [time(0, 0, 0, 0), time(0, 0, 3, 5), time(0, 2, 3, 0), time(1, 2, 3, 400000000),
+            time(123, 45, 0, 0), neg_time(0, 0, 0, 1), neg_time(50, 0, 3, 0), Time::MAX, Time::MIN];
+        for style in styles.iter() {
+            for t in times.iter() {
+                let formatted = TimeFormat::new(*style).format(*t);
+                assert_eq!(formatted.parse::<Time>().unwrap(), *t, "{}", formatted);
+            }
+        }
+    }
+
+    fn time(hours: u64, minutes: u8, seconds: u8, nanoseconds: u32) -> Time {
+        Time::builder().hours(hours).minutes(minutes).seconds(seconds).nanoseconds(nanoseconds).build()
+    }
+
+    fn neg_time(hours: u64, minutes: u8, seconds: u8, nanoseconds: u32) -> Time {
+        Time::builder().negative().hours(hours).minutes(minutes).seconds(seconds).nanoseconds(nanoseconds).build()
+    }
+
+    fn assert_format(style: TimeStyle, time: Time, expected: &str) {
+        assert_eq!(TimeFormat::new(style).format(time), expected);
+    }
+
+    fn assert_format_digits(style: TimeStyle, digits: u32, time: Time, expected: &str) {
+        assert_eq!(TimeFormat::new(style).fraction_digits(digits).format(time), expected);
+    }
+}
@@ -1,6 +1,10 @@
+pub mod format;
 mod parse;
 
+use std::cmp::Ordering;
 use std::convert::From;
+use std::convert::TryFrom;
+use std::error;
 use std::fmt;
 
 use rust_decimal::prelude::ToPrimitive;
@@ -9,8 +13,15 @@ use rust_decimal::RoundingStrategy;
 use rust_decimal_macros::dec;
 use std::str::FromStr;
 
+// The lexer and parser are internal. Only the types which appear in errors are public.
+pub use crate::time::parse::LexError;
+pub use crate::time::parse::ParseError;
+pub use crate::time::parse::Token;
+
+use crate::time::format::TimeFormat;
+use crate::time::format::TimeStyle;
+use crate::time::parse::parse_iso8601;
 use crate::time::parse::parse_time;
-use crate::time::parse::ParseError;
 
 /// An amount of elapsed time.
 ///
@@ -28,6 +39,10 @@ pub struct Time {
 }
 
 /// A builder for creating new times.
+///
+/// Components are not validated until the time is built. Use
+/// [`try_build`](struct.TimeBuilder.html#method.try_build) to handle invalid components without
+/// panicking.
 pub struct TimeBuilder {
     negative: bool,
     hours: u64,
@@ -36,6 +51,127 @@ pub struct TimeBuilder {
     nanoseconds: u32,
 }
 
+/// An error which occurred while creating a time.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimeError {
+    MinutesOutOfRange(u8),
+    SecondsOutOfRange(u8),
+    NanosecondsOutOfRange(u32),
+    Overflow,
+}
+
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeError::MinutesOutOfRange(m) => {
+                write!(f, "minutes must be less than 60, found {}", m)
+            }
+            TimeError::SecondsOutOfRange(s) => {
+                write!(f, "seconds must be less than 60, found {}", s)
+            }
+            TimeError::NanosecondsOutOfRange(ns) => write!(
+                f,
+                "nanoseconds must be less than 1,000,000,000, found {}",
+                ns
+            ),
+            TimeError::Overflow => write!(f, "time exceeds the maximum time"),
+        }
+    }
+}
+
+impl error::Error for TimeError {}
+
+/// A unit of time which can be used as a suffix in a time, such as the `h` in `1.5h`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TimeUnit {
+    Days,
+    Hours,
+    Minutes,
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl TimeUnit {
+    /// Returns the unit with the given suffix, or `None` if the suffix is not a known unit.
+    ///
+    /// Microseconds can be written as either `us` or `µs`.
+    pub fn from_suffix(suffix: &str) -> Option<TimeUnit> {
+        match suffix {
+            "d" => Option::Some(TimeUnit::Days),
+            "h" => Option::Some(TimeUnit::Hours),
+            "m" => Option::Some(TimeUnit::Minutes),
+            "s" => Option::Some(TimeUnit::Seconds),
+            "ms" => Option::Some(TimeUnit::Milliseconds),
+            "us" | "µs" => Option::Some(TimeUnit::Microseconds),
+            "ns" => Option::Some(TimeUnit::Nanoseconds),
+            _ => Option::None,
+        }
+    }
+
+    /// Returns the unit with the given name or suffix, such as `minutes`, `min` or `m`, or `None`
+    /// if the name is not a known unit.
+    pub fn from_name(name: &str) -> Option<TimeUnit> {
+        match name {
+            "days" | "day" => Option::Some(TimeUnit::Days),
+            "hours" | "hour" | "hr" | "hrs" => Option::Some(TimeUnit::Hours),
+            "minutes" | "minute" | "min" | "mins" => Option::Some(TimeUnit::Minutes),
+            "seconds" | "second" | "sec" | "secs" => Option::Some(TimeUnit::Seconds),
+            "milliseconds" | "millisecond" => Option::Some(TimeUnit::Milliseconds),
+            "microseconds" | "microsecond" => Option::Some(TimeUnit::Microseconds),
+            "nanoseconds" | "nanosecond" => Option::Some(TimeUnit::Nanoseconds),
+            _ => TimeUnit::from_suffix(name),
+        }
+    }
+
+    /// Returns the suffix used to write this unit.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TimeUnit::Days => "d",
+            TimeUnit::Hours => "h",
+            TimeUnit::Minutes => "m",
+            TimeUnit::Seconds => "s",
+            TimeUnit::Milliseconds => "ms",
+            TimeUnit::Microseconds => "us",
+            TimeUnit::Nanoseconds => "ns",
+        }
+    }
+
+    /// Returns the length of this unit in nanoseconds.
+    pub fn nanoseconds(self) -> u64 {
+        match self {
+            TimeUnit::Days => 86_400_000_000_000,
+            TimeUnit::Hours => 3_600_000_000_000,
+            TimeUnit::Minutes => 60_000_000_000,
+            TimeUnit::Seconds => 1_000_000_000,
+            TimeUnit::Milliseconds => 1_000_000,
+            TimeUnit::Microseconds => 1_000,
+            TimeUnit::Nanoseconds => 1,
+        }
+    }
+
+    /// Returns the length of this unit in seconds.
+    pub fn seconds(self) -> Decimal {
+        match self {
+            TimeUnit::Days => dec!(86400),
+            TimeUnit::Hours => dec!(3600),
+            TimeUnit::Minutes => dec!(60),
+            TimeUnit::Seconds => dec!(1),
+            TimeUnit::Milliseconds => dec!(0.001),
+            TimeUnit::Microseconds => dec!(0.000001),
+            TimeUnit::Nanoseconds => dec!(0.000000001),
+        }
+    }
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.suffix())
+    }
+}
+
 impl Time {
     const MAX_TIME_HOURS: u64 = 2_562_047_788_015_215;
     const MAX_TIME_MINUTES: u8 = 30;
@@ -44,8 +180,21 @@ impl Time {
     const NANOS_PER_SECOND: u32 = 1_000_000_000;
     const SECONDS_PER_MINUTE: u8 = 60;
     const MINUTES_PER_HOUR: u8 = 60;
+    const HOURS_PER_DAY: u64 = 24;
     const SECONDS_PER_HOUR: u16 = Time::MINUTES_PER_HOUR as u16 * Time::SECONDS_PER_MINUTE as u16;
 
+    /// The largest time that can be represented, 2,562,047,788,015,215:30:07.999999999.
+    pub const MAX: Time = Time {
+        seconds: i64::MAX,
+        nanoseconds: 999_999_999,
+    };
+
+    /// The smallest time that can be represented, -2,562,047,788,015,215:30:07.999999999.
+    pub const MIN: Time = Time {
+        seconds: i64::MIN,
+        nanoseconds: 1,
+    };
+
     /// Returns a new time builder.
     pub fn builder() -> TimeBuilder {
         TimeBuilder {
@@ -57,13 +206,16 @@ impl Time {
         }
     }
 
-    /// Returns the total seconds of the time.
-    fn total_seconds(self) -> i64 {
+    /// Returns the total seconds of the time, rounded toward negative infinity. For example, the
+    /// total seconds of -1.2 seconds is -2.
+    pub fn total_seconds(self) -> i64 {
         self.seconds
     }
 
-    /// Returns the nanoseconds offset of the time.
-    fn nanoseconds_offset(self) -> u32 {
+    /// Returns the nanoseconds offset of the time, which is always positive and is added to the
+    /// [`total_seconds`](#method.total_seconds). For example, the nanoseconds offset of -1.2
+    /// seconds is 800,000,000.
+    pub fn nanoseconds_offset(self) -> u32 {
         self.nanoseconds
     }
 
@@ -72,7 +224,7 @@ impl Time {
     /// * `0` if the time is zero.
     /// * `1` if the time is positive.
     /// * `-1` if the time is negative.
-    fn signum(self) -> i64 {
+    pub fn signum(self) -> i64 {
         if self.total_seconds() == 0 {
             (i64::from(self.nanoseconds_offset())).signum()
         } else {
@@ -87,7 +239,7 @@ impl Time {
             total_seconds += 1;
         }
 
-        (total_seconds / i64::from(Time::SECONDS_PER_HOUR)).abs() as u64
+        (total_seconds / i64::from(Time::SECONDS_PER_HOUR)).unsigned_abs()
     }
 
     /// Returns the minutes component of the time.
@@ -99,7 +251,7 @@ impl Time {
 
         // Number of seconds in mm:ss portion of the time.
         let seconds_in_minutes = total_seconds % i64::from(Time::SECONDS_PER_HOUR);
-        (seconds_in_minutes / i64::from(Time::MINUTES_PER_HOUR)).abs() as u8
+        (seconds_in_minutes / i64::from(Time::MINUTES_PER_HOUR)).unsigned_abs() as u8
     }
 
     /// Returns the seconds component of the time.
@@ -109,7 +261,7 @@ impl Time {
             total_seconds += 1;
         }
 
-        (total_seconds % i64::from(Time::SECONDS_PER_MINUTE)).abs() as u8
+        (total_seconds % i64::from(Time::SECONDS_PER_MINUTE)).unsigned_abs() as u8
     }
 
     /// Returns the nanoseconds component of the time.
@@ -135,42 +287,64 @@ impl TimeBuilder {
         self
     }
 
-    /// Sets the minutes component.
+    /// Sets the minutes component. Must be between 0 and 59.
     pub fn minutes(&mut self, minutes: u8) -> &mut TimeBuilder {
-        if minutes > 59 {
-            panic!("Time must have between 0 and 59 minutes.");
-        }
         self.minutes = minutes;
         self
     }
 
-    /// Sets the seconds component.
+    /// Sets the seconds component. Must be between 0 and 59.
     pub fn seconds(&mut self, seconds: u8) -> &mut TimeBuilder {
-        if seconds > 59 {
-            panic!("Time must have between 0 and 59 seconds.");
-        }
         self.seconds = seconds;
         self
     }
 
-    /// Sets the nanoseconds component.
+    /// Sets the nanoseconds component. Must be between 0 and 999,999,999.
     pub fn nanoseconds(&mut self, nanoseconds: u32) -> &mut TimeBuilder {
-        if nanoseconds > 999_999_999 {
-            panic!("Time must have between 0 and 999,999,999 nanoseconds.");
-        }
         self.nanoseconds = nanoseconds;
         self
     }
 
     /// Returns a new time based on the contents of the builder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any component is out of range or if the time exceeds the maximum.
     pub fn build(&self) -> Time {
+        match self.try_build() {
+            Result::Ok(time) => time,
+            Result::Err(TimeError::MinutesOutOfRange(_)) => {
+                panic!("Time must have between 0 and 59 minutes.")
+            }
+            Result::Err(TimeError::SecondsOutOfRange(_)) => {
+                panic!("Time must have between 0 and 59 seconds.")
+            }
+            Result::Err(TimeError::NanosecondsOutOfRange(_)) => {
+                panic!("Time must have between 0 and 999,999,999 nanoseconds.")
+            }
+            Result::Err(TimeError::Overflow) => panic!("Time exceeds maximum."),
+        }
+    }
+
+    /// Returns a new time based on the contents of the builder, or an error if any component is
+    /// out of range or if the time exceeds the maximum.
+    pub fn try_build(&self) -> Result<Time, TimeError> {
+        if self.minutes > 59 {
+            return Result::Err(TimeError::MinutesOutOfRange(self.minutes));
+        }
+        if self.seconds > 59 {
+            return Result::Err(TimeError::SecondsOutOfRange(self.seconds));
+        }
+        if self.nanoseconds > 999_999_999 {
+            return Result::Err(TimeError::NanosecondsOutOfRange(self.nanoseconds));
+        }
         if self.hours > Time::MAX_TIME_HOURS
             || (self.hours == Time::MAX_TIME_HOURS && self.minutes > Time::MAX_TIME_MINUTES)
             || (self.hours == Time::MAX_TIME_HOURS
                 && self.minutes == Time::MAX_TIME_MINUTES
                 && self.seconds > Time::MAX_TIME_SECONDS)
         {
-            panic!("Time exceeds maximum.");
+            return Result::Err(TimeError::Overflow);
         }
 
         let mut seconds = (self.hours * u64::from(Time::SECONDS_PER_HOUR)
@@ -186,99 +360,376 @@ impl TimeBuilder {
             nanoseconds = Time::NANOS_PER_SECOND - nanoseconds;
         }
 
-        Time {
+        Result::Ok(Time {
             seconds,
             nanoseconds,
-        }
+        })
     }
 }
 
-impl From<Decimal> for Time {
-    fn from(decimal: Decimal) -> Self {
+impl TryFrom<Decimal> for Time {
+    type Error = TimeError;
+
+    /// Converts a number of seconds to a time, rounding to the nearest nanosecond.
+    fn try_from(decimal: Decimal) -> Result<Self, Self::Error> {
         let seconds_per_hour = Decimal::new(i64::from(Time::SECONDS_PER_HOUR), 0);
         let seconds_per_minute = Decimal::new(i64::from(Time::SECONDS_PER_MINUTE), 0);
 
+        // Round to 9 decimal places before splitting into components so that rounding up to the
+        // next whole second is carried into the seconds.
+        let decimal = decimal.round_dp_with_strategy(9, RoundingStrategy::MidpointAwayFromZero);
+
         let mut time_builder = Time::builder();
         if decimal.is_sign_negative() {
             time_builder.negative();
         }
 
-        time_builder.hours((decimal / seconds_per_hour).abs().to_u64().unwrap());
+        let hours = (decimal / seconds_per_hour).abs().trunc().to_u64();
+        time_builder.hours(hours.ok_or(TimeError::Overflow)?);
         time_builder.minutes(
             (decimal % seconds_per_hour / seconds_per_minute)
                 .abs()
+                .trunc()
+                .to_u8()
+                .unwrap(),
+        );
+        time_builder.seconds(
+            (decimal % seconds_per_minute)
+                .abs()
+                .trunc()
                 .to_u8()
                 .unwrap(),
         );
-        time_builder.seconds((decimal % seconds_per_minute).abs().to_u8().unwrap());
-
-        // `self` may not have enough decimal places. Multiplying by 1.000000000 should ensure we
-        // have at least nanosecond precision.
-        let mut nanos = (decimal * dec!(1.000000000))
-            // Round to 9 decimal places.
-            .round_dp_with_strategy(9, RoundingStrategy::RoundHalfUp)
-            // Keep only fractional part.
-            .fract();
+
+        // `decimal` may not have enough decimal places. Multiplying by 1.000000000 should ensure
+        // we have at least nanosecond precision. Only the fractional part is multiplied, so this
+        // can not overflow.
+        let mut nanos = (decimal.fract() * dec!(1.000000000))
+            // Reduce to exactly 9 decimal places.
+            .round_dp(9);
         // Convert fractional part to number of nanoseconds.
         // Decimal::set_scale(0) should always succeed so ignore result.
         let _ = nanos.set_scale(0);
 
         time_builder.nanoseconds(nanos.abs().to_u32().unwrap());
-        time_builder.build()
+        time_builder.try_build()
     }
 }
 
 impl FromStr for Time {
     type Err = ParseError;
 
+    /// Parses a time such as `1:30:00` or `1h30m`, or an ISO 8601 duration such as `PT1H30M`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         parse_time(s)
     }
 }
 
+impl Time {
+    /// Parses an ISO 8601 duration, such as `PT1H30M` or `P2DT3H`.
+    ///
+    /// Years and months are rejected, since they do not have a fixed length. Weeks are treated as 7
+    /// days and days as 24 hours. A leading `-` negates the duration.
+    pub fn parse_iso8601(s: &str) -> Result<Time, ParseError> {
+        parse_iso8601(s)
+    }
+
+    /// Returns this time as an ISO 8601 duration, such as `PT1H30M`.
+    ///
+    /// The largest component is always hours, so that the result does not depend on the length of
+    /// a day. A negative time is written with a leading `-`.
+    pub fn to_iso8601(&self) -> String {
+        self.format(&TimeFormat::new(TimeStyle::Iso8601))
+    }
+
+    /// Formats this time according to `format`.
+    pub fn format(&self, format: &TimeFormat) -> String {
+        format.format(*self)
+    }
+}
+
+/// Formats a time as `h:mm:ss`, `mm:ss` or `ss` seconds, such as `1:02:03`, `02:03` or `3s`.
+///
+/// The alternate flag (`{:#}`) formats times of at least one day with a days component, such as
+/// `5d 03:45:00`. Other styles are available with [`Time::format`](struct.Time.html#method.format).
 impl fmt::Display for Time {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let hours = self.hours();
-        let minutes = self.minutes();
-        let seconds = self.seconds();
-        let nanoseconds = self.nanoseconds();
+        let style = if f.alternate() {
+            TimeStyle::Days
+        } else {
+            TimeStyle::Default
+        };
+        write!(f, "{}", TimeFormat::new(style).format(*self))
+    }
+}
+
+impl From<Time> for Decimal {
+    fn from(time: Time) -> Self {
+        Decimal::new(time.total_seconds(), 0)
+            + Decimal::new(i64::from(time.nanoseconds_offset()), 9)
+    }
+}
+
+impl Time {
+    /// Checked time addition. Returns `None` if the result would overflow.
+    pub fn checked_add(self, other: Time) -> Option<Time> {
+        Decimal::from(self)
+            .checked_add(Decimal::from(other))
+            .and_then(|d| Time::try_from(d).ok())
+    }
+
+    /// Checked time subtraction. Returns `None` if the result would overflow.
+    pub fn checked_sub(self, other: Time) -> Option<Time> {
+        Decimal::from(self)
+            .checked_sub(Decimal::from(other))
+            .and_then(|d| Time::try_from(d).ok())
+    }
+
+    /// Checked multiplication by a number. Returns `None` if the result would overflow.
+    pub fn checked_mul(self, other: Decimal) -> Option<Time> {
+        Decimal::from(self)
+            .checked_mul(other)
+            .and_then(|d| Time::try_from(d).ok())
+    }
 
-        if self.signum() == -1 {
-            write!(f, "-")?;
+    /// Checked division by a number. Returns `None` if `other` is zero or if the result would
+    /// overflow.
+    pub fn checked_div(self, other: Decimal) -> Option<Time> {
+        Decimal::from(self)
+            .checked_div(other)
+            .and_then(|d| Time::try_from(d).ok())
+    }
+
+    /// Checked floored division by a time, rounding the quotient toward negative infinity. Returns
+    /// `None` if `other` is zero.
+    pub fn checked_div_floor(self, other: Time) -> Option<Decimal> {
+        let (quotient, _) = self.div_rem_floor(other)?;
+        Option::Some(Decimal::from_i128_with_scale(quotient, 0))
+    }
+
+    /// Checked remainder of floored division by a time. The remainder has the same sign as
+    /// `other`. Returns `None` if `other` is zero.
+    pub fn checked_rem(self, other: Time) -> Option<Time> {
+        let (_, remainder) = self.div_rem_floor(other)?;
+        Time::from_nanoseconds(remainder)
+    }
+
+    /// Returns the absolute value of the time. This can not overflow, since `Time::MIN` is
+    /// `-Time::MAX`.
+    pub fn abs(self) -> Time {
+        Time::from_nanoseconds(self.total_nanoseconds().abs()).unwrap()
+    }
+
+    /// Returns the quotient and remainder, in nanoseconds, of floored division by a time, or
+    /// `None` if `other` is zero.
+    fn div_rem_floor(self, other: Time) -> Option<(i128, i128)> {
+        let dividend = self.total_nanoseconds();
+        let divisor = other.total_nanoseconds();
+        let mut quotient = dividend.checked_div(divisor)?;
+        let mut remainder = dividend % divisor;
+        if remainder != 0 && (remainder < 0) != (divisor < 0) {
+            quotient -= 1;
+            remainder += divisor;
         }
-        if hours > 0 {
-            write!(f, "{}:", hours)?;
+        Option::Some((quotient, remainder))
+    }
+
+    /// Rounds to a multiple of `unit` using the specified rounding strategy. Returns `None` if the
+    /// rounded time would overflow.
+    pub fn round_to(self, unit: TimeUnit, strategy: RoundingStrategy) -> Option<Time> {
+        self.round_to_nanoseconds(i128::from(unit.nanoseconds()), strategy)
+    }
+
+    /// Rounds to a multiple of `increment` using the specified rounding strategy. The sign of
+    /// `increment` is ignored. Returns `None` if `increment` is zero or if the rounded time would
+    /// overflow.
+    pub fn round_to_multiple(self, increment: Time, strategy: RoundingStrategy) -> Option<Time> {
+        match increment.total_nanoseconds().abs() {
+            0 => Option::None,
+            increment_nanoseconds => self.round_to_nanoseconds(increment_nanoseconds, strategy),
+        }
+    }
+
+    fn round_to_nanoseconds(
+        self,
+        unit_nanoseconds: i128,
+        strategy: RoundingStrategy,
+    ) -> Option<Time> {
+        let nanoseconds = self.total_nanoseconds();
+        let quotient = nanoseconds.div_euclid(unit_nanoseconds);
+        let remainder = nanoseconds.rem_euclid(unit_nanoseconds);
+
+        // Rounding only depends on whether the remainder is zero, or less than, equal to or greater
+        // than half of the unit. A quarter, a half or three quarters is used in place of the exact
+        // fraction so that it can always be represented by a decimal.
+        let fraction = if remainder == 0 {
+            dec!(0)
+        } else {
+            match (2 * remainder).cmp(&unit_nanoseconds) {
+                Ordering::Less => dec!(0.25),
+                Ordering::Equal => dec!(0.5),
+                Ordering::Greater => dec!(0.75),
+            }
+        };
+        let rounded = (Decimal::from_i128_with_scale(quotient, 0) + fraction)
+            .round_dp_with_strategy(0, strategy)
+            .to_i128()?;
+        Time::from_nanoseconds(rounded.checked_mul(unit_nanoseconds)?)
+    }
+
+    /// Saturating time addition. Returns `Time::MAX` or `Time::MIN` instead of overflowing.
+    pub fn saturating_add(self, other: Time) -> Time {
+        self.checked_add(other).unwrap_or(if other.signum() < 0 {
+            Time::MIN
+        } else {
+            Time::MAX
+        })
+    }
+
+    /// Saturating time subtraction. Returns `Time::MAX` or `Time::MIN` instead of overflowing.
+    pub fn saturating_sub(self, other: Time) -> Time {
+        self.checked_sub(other).unwrap_or(if other.signum() > 0 {
+            Time::MIN
+        } else {
+            Time::MAX
+        })
+    }
+
+    /// Saturating multiplication by a number. Returns `Time::MAX` or `Time::MIN` instead of
+    /// overflowing.
+    pub fn saturating_mul(self, other: Decimal) -> Time {
+        self.checked_mul(other)
+            .unwrap_or_else(|| Time::saturated(self, other))
+    }
+
+    /// Saturating division by a number. Returns `Time::MAX` or `Time::MIN` instead of
+    /// overflowing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero.
+    pub fn saturating_div(self, other: Decimal) -> Time {
+        if other.is_zero() {
+            panic!("attempt to divide time by zero");
         }
-        if hours > 0 || minutes > 0 {
-            write!(f, "{:02}:{:02}", minutes, seconds)?;
+        self.checked_div(other)
+            .unwrap_or_else(|| Time::saturated(self, other))
+    }
+
+    /// Returns the bound with the same sign as the product of `time` and `number`.
+    fn saturated(time: Time, number: Decimal) -> Time {
+        if (time.signum() < 0) == number.is_sign_negative() {
+            Time::MAX
         } else {
-            write!(f, "{}", seconds)?;
+            Time::MIN
         }
+    }
 
-        if nanoseconds > 0 {
-            let mut nanos = format!("{:09}", nanoseconds);
-            // Remove trailing zeros.
-            while let Some(c) = nanos.pop() {
-                // Found non-'0'. Put it back and break from loop.
-                if c != '0' {
-                    nanos.push(c);
-                    break;
-                }
-            }
+    /// Wrapping time addition. Wraps around at the boundary of the time range.
+    pub fn wrapping_add(self, other: Time) -> Time {
+        Time::from_wrapped_nanoseconds(self.total_nanoseconds() + other.total_nanoseconds())
+    }
 
-            write!(f, ".{}", nanos)?;
+    /// Wrapping time subtraction. Wraps around at the boundary of the time range.
+    pub fn wrapping_sub(self, other: Time) -> Time {
+        Time::from_wrapped_nanoseconds(self.total_nanoseconds() - other.total_nanoseconds())
+    }
+
+    /// Wrapping multiplication by a number. Wraps around at the boundary of the time range.
+    pub fn wrapping_mul(self, other: Decimal) -> Time {
+        if let Option::Some(time) = self.checked_mul(other) {
+            return time;
         }
-        if hours == 0 && minutes == 0 {
-            write!(f, "s")?;
+
+        // The product of the time and the integer part of `other` may not fit in a `Decimal`, so
+        // it is computed modulo the size of the time range. The product of the time and the
+        // fractional part is always smaller than the time so it can be computed directly.
+        let range = Time::nanoseconds_range();
+        let time = self.total_nanoseconds().rem_euclid(range) as u128;
+        let integer = other.trunc().to_i128().unwrap().rem_euclid(range) as u128;
+        let integer_product = mul_mod(time, integer, range as u128) as i128;
+
+        let fraction_product = (Decimal::from(self) * other.fract())
+            .round_dp_with_strategy(9, RoundingStrategy::MidpointAwayFromZero);
+        let fraction_product = Decimal::from(Time::try_from(fraction_product).unwrap());
+        let fraction_product = (fraction_product * dec!(1_000_000_000)).to_i128().unwrap();
+
+        Time::from_wrapped_nanoseconds(integer_product + fraction_product)
+    }
+
+    /// Wrapping division by a number. Wraps around at the boundary of the time range.
+    ///
+    /// If the result wraps, it is calculated by multiplying by the reciprocal of `other`, so it
+    /// may differ from the exact result due to rounding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero.
+    pub fn wrapping_div(self, other: Decimal) -> Time {
+        if other.is_zero() {
+            panic!("attempt to divide time by zero");
+        }
+        match self.checked_div(other) {
+            Option::Some(time) => time,
+            // Overflow is only possible if `|other| < 1`, so the reciprocal always fits.
+            Option::None => self.wrapping_mul(Decimal::ONE / other),
+        }
+    }
+
+    /// Returns the total number of nanoseconds of the time.
+    fn total_nanoseconds(self) -> i128 {
+        i128::from(self.total_seconds()) * i128::from(Time::NANOS_PER_SECOND)
+            + i128::from(self.nanoseconds_offset())
+    }
+
+    /// Returns the time with the specified number of nanoseconds, or `None` if it can not be
+    /// represented.
+    fn from_nanoseconds(nanoseconds: i128) -> Option<Time> {
+        if nanoseconds < Time::MIN.total_nanoseconds()
+            || nanoseconds > Time::MAX.total_nanoseconds()
+        {
+            return Option::None;
         }
-        write!(f, "")
+        Time::try_from(Decimal::from_i128_with_scale(nanoseconds, 9)).ok()
+    }
+
+    /// Returns the number of distinct times that can be represented.
+    fn nanoseconds_range() -> i128 {
+        Time::MAX.total_nanoseconds() - Time::MIN.total_nanoseconds() + 1
+    }
+
+    /// Returns the time with the specified number of nanoseconds, wrapping around at the boundary
+    /// of the time range.
+    fn from_wrapped_nanoseconds(nanoseconds: i128) -> Time {
+        let min = Time::MIN.total_nanoseconds();
+        let nanoseconds = (nanoseconds - min).rem_euclid(Time::nanoseconds_range()) + min;
+        Time::try_from(Decimal::from_i128_with_scale(nanoseconds, 9)).unwrap()
     }
 }
 
-impl From<Time> for Decimal {
-    fn from(time: Time) -> Self {
-        Decimal::new(time.total_seconds(), 0)
-            + Decimal::new(i64::from(time.nanoseconds_offset()), 9)
+/// Returns `(a * b) % m` without overflowing. `a` and `b` must be less than `m`, and `m` must be
+/// less than `2^127`.
+fn mul_mod(mut a: u128, mut b: u128, m: u128) -> u128 {
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = (result + a) % m;
+        }
+        a = (a << 1) % m;
+        b >>= 1;
+    }
+    result
+}
+
+impl Ord for Time {
+    fn cmp(&self, other: &Time) -> Ordering {
+        self.total_nanoseconds().cmp(&other.total_nanoseconds())
+    }
+}
+
+impl PartialOrd for Time {
+    fn partial_cmp(&self, other: &Time) -> Option<Ordering> {
+        Option::Some(self.cmp(other))
     }
 }
 
@@ -286,7 +737,7 @@ impl From<Time> for Decimal {
 impl std::ops::Add for Time {
     type Output = Time;
     fn add(self, other: Time) -> Time {
-        (Decimal::from(self) + Decimal::from(other)).into()
+        self.checked_add(other).expect("overflow when adding times")
     }
 }
 
@@ -294,7 +745,16 @@ impl std::ops::Add for Time {
 impl std::ops::Sub for Time {
     type Output = Time;
     fn sub(self, other: Time) -> Self::Output {
-        (Decimal::from(self) - Decimal::from(other)).into()
+        self.checked_sub(other)
+            .expect("overflow when subtracting times")
+    }
+}
+
+// -time
+impl std::ops::Neg for Time {
+    type Output = Time;
+    fn neg(self) -> Time {
+        Time::from_nanoseconds(-self.total_nanoseconds()).unwrap()
     }
 }
 
@@ -306,11 +766,21 @@ impl std::ops::Div for Time {
     }
 }
 
+// time % time
+impl std::ops::Rem for Time {
+    type Output = Time;
+    fn rem(self, other: Time) -> Time {
+        self.checked_rem(other)
+            .expect("division by zero when taking remainder of times")
+    }
+}
+
 // time / decimal
 impl std::ops::Div<Decimal> for Time {
     type Output = Time;
     fn div(self, other: Decimal) -> Time {
-        (Decimal::from(self) / other).into()
+        self.checked_div(other)
+            .expect("overflow when dividing time")
     }
 }
 
@@ -318,7 +788,8 @@ impl std::ops::Div<Decimal> for Time {
 impl std::ops::Mul<Decimal> for Time {
     type Output = Time;
     fn mul(self, other: Decimal) -> Time {
-        (Decimal::from(self) * other).into()
+        self.checked_mul(other)
+            .expect("overflow when multiplying time")
     }
 }
 
@@ -334,7 +805,11 @@ impl std::ops::Mul<Time> for Decimal {
 #[rustfmt::skip]
 mod tests {
     use crate::time::Time;
+    use crate::time::TimeError;
+    use crate::time::TimeUnit;
     use rust_decimal::Decimal;
+    use std::cmp::Ordering;
+    use std::convert::TryFrom;
     use rust_decimal_macros::dec;
 
     #[test]
@@ -466,18 +941,18 @@ mod tests {
 
         // +/- 01:01:01.010101010
         let seconds = (60 * 60) + 60 + 1;
-        assert_time(Time::builder().hours(1).minutes(1).seconds(1).nanoseconds(010101010).build(),
-            seconds, 010101010, 1, 1, 1, 1, 010101010, "1:01:01.01010101");
-        assert_time(Time::builder().negative().hours(1).minutes(1).seconds(1).nanoseconds(010101010).build(),
-            -seconds - 1, 989898990, -1, 1, 1, 1, 010101010, "-1:01:01.01010101");
+        assert_time(Time::builder().hours(1).minutes(1).seconds(1).nanoseconds(10101010).build(),
+            seconds, 10101010, 1, 1, 1, 1, 10101010, "1:01:01.01010101");
+        assert_time(Time::builder().negative().hours(1).minutes(1).seconds(1).nanoseconds(10101010).build(),
+            -seconds - 1, 989898990, -1, 1, 1, 1, 10101010, "-1:01:01.01010101");
     }
 
     #[test]
     fn time_builder_min_max() {
         assert_time(Time::builder().hours(2562047788015215).minutes(30).seconds(7).nanoseconds(999999999).build(),
-            std::i64::MAX, 999999999, 1, 2562047788015215, 30, 7, 999999999, "2562047788015215:30:07.999999999");
+            i64::MAX, 999999999, 1, 2562047788015215, 30, 7, 999999999, "2562047788015215:30:07.999999999");
         assert_time(Time::builder().negative().hours(2562047788015215).minutes(30).seconds(7).nanoseconds(999999999).build(),
-            std::i64::MIN, 1, -1, 2562047788015215, 30, 7, 999999999, "-2562047788015215:30:07.999999999");
+            i64::MIN, 1, -1, 2562047788015215, 30, 7, 999999999, "-2562047788015215:30:07.999999999");
     }
 
     #[test]
@@ -792,6 +1267,198 @@ mod tests {
         assert_eq!(_1h_1m_1s_1ns / _1h, dec!(1.01694444444472222222222222222));
     }
 
+    #[test]
+    fn ord() {
+        let zero = time(0, 0, 0, 0);
+        assert_eq!(zero.cmp(&zero), Ordering::Equal);
+        assert!(time(0, 0, 0, 1) > zero);
+        assert!(neg_time(0, 0, 0, 1) < zero);
+        assert!(time(0, 0, 1, 0) > time(0, 0, 0, 999999999));
+        assert!(neg_time(0, 0, 1, 0) < neg_time(0, 0, 0, 999999999));
+        assert!(neg_time(0, 0, 0, 500000000) > neg_time(0, 0, 1, 0));
+        assert!(time(1, 0, 0, 0) > time(0, 59, 59, 999999999));
+        assert!(time(1, 2, 3, 4) <= time(1, 2, 3, 4));
+        assert!(time(1, 2, 3, 4) >= time(1, 2, 3, 4));
+        assert!(Time::MIN < neg_time(2562047788015215, 30, 7, 999999998));
+        assert!(Time::MAX > time(2562047788015215, 30, 7, 999999998));
+        assert!(Time::MIN < Time::MAX);
+
+        let mut times = vec![time(0, 1, 0, 0), neg_time(0, 0, 30, 0), zero, time(0, 0, 30, 0), neg_time(0, 1, 0, 0)];
+        times.sort();
+        assert_eq!(times, vec![neg_time(0, 1, 0, 0), neg_time(0, 0, 30, 0), zero, time(0, 0, 30, 0), time(0, 1, 0, 0)]);
+    }
+
+    #[test]
+    fn abs_signum_neg() {
+        let zero = time(0, 0, 0, 0);
+        assert_eq!(zero.abs(), zero);
+        assert_eq!(time(1, 2, 3, 4).abs(), time(1, 2, 3, 4));
+        assert_eq!(neg_time(1, 2, 3, 4).abs(), time(1, 2, 3, 4));
+        assert_eq!(neg_time(0, 0, 0, 1).abs(), time(0, 0, 0, 1));
+        assert_eq!(Time::MIN.abs(), Time::MAX);
+        assert_eq!(Time::MAX.abs(), Time::MAX);
+
+        assert_eq!(zero.signum(), 0);
+        assert_eq!(time(0, 0, 0, 1).signum(), 1);
+        assert_eq!(neg_time(0, 0, 0, 1).signum(), -1);
+        assert_eq!(Time::MAX.signum(), 1);
+        assert_eq!(Time::MIN.signum(), -1);
+
+        assert_eq!(-zero, zero);
+        assert_eq!(-time(1, 2, 3, 4), neg_time(1, 2, 3, 4));
+        assert_eq!(-neg_time(0, 0, 0, 500000000), time(0, 0, 0, 500000000));
+        assert_eq!(-Time::MAX, Time::MIN);
+        assert_eq!(-Time::MIN, Time::MAX);
+    }
+
+    #[test]
+    fn div_floor_time() {
+        let zero = time(0, 0, 0, 0);
+
+        // Nanoseconds.
+        let _1ns = time(0, 0, 0, 1);
+        let neg_1ns = neg_time(0, 0, 0, 1);
+
+        // Seconds.
+        let _1s = time(0, 0, 1, 0);
+        let neg_1s = neg_time(0, 0, 1, 0);
+
+        // Minutes.
+        let _1m = time(0, 1, 0, 0);
+        let neg_1m = neg_time(0, 1, 0, 0);
+
+        // Hours.
+        let _1h = time(1, 0, 0, 0);
+        let neg_1h = neg_time(1, 0, 0, 0);
+
+        // All.
+        let _1h_1m_1s_1ns = time(1, 1, 1, 1);
+        let neg_1h_1m_1s_1ns = neg_time(1, 1, 1, 1);
+
+        // 0 // x
+        assert_eq!(zero.checked_div_floor(_1ns), Option::Some(dec!(0)));
+        assert_eq!(zero.checked_div_floor(_1h_1m_1s_1ns), Option::Some(dec!(0)));
+        assert_eq!(zero.checked_div_floor(neg_1ns), Option::Some(dec!(0)));
+        assert_eq!(zero.checked_div_floor(neg_1h_1m_1s_1ns), Option::Some(dec!(0)));
+
+        // x // 0
+        assert_eq!(_1s.checked_div_floor(zero), Option::None);
+        assert_eq!(neg_1s.checked_div_floor(zero), Option::None);
+        assert_eq!(zero.checked_div_floor(zero), Option::None);
+
+        // Div nanoseconds.
+        assert_eq!(_1ns.checked_div_floor(_1ns), Option::Some(dec!(1)));
+        assert_eq!(_1ns.checked_div_floor(neg_1ns), Option::Some(dec!(-1)));
+        assert_eq!(neg_1ns.checked_div_floor(_1ns), Option::Some(dec!(-1)));
+        assert_eq!(neg_1ns.checked_div_floor(neg_1ns), Option::Some(dec!(1)));
+
+        // Div seconds.
+        assert_eq!(_1s.checked_div_floor(_1ns), Option::Some(dec!(1_000_000_000)));
+        assert_eq!(_1s.checked_div_floor(time(0, 0, 0, 300000000)), Option::Some(dec!(3)));
+        assert_eq!(_1s.checked_div_floor(neg_time(0, 0, 0, 300000000)), Option::Some(dec!(-4)));
+        assert_eq!(neg_1s.checked_div_floor(time(0, 0, 0, 300000000)), Option::Some(dec!(-4)));
+        assert_eq!(neg_1s.checked_div_floor(neg_time(0, 0, 0, 300000000)), Option::Some(dec!(3)));
+
+        // Div minutes.
+        assert_eq!(_1m.checked_div_floor(_1s), Option::Some(dec!(60)));
+        assert_eq!(_1m.checked_div_floor(time(0, 0, 16, 0)), Option::Some(dec!(3)));
+        assert_eq!(_1m.checked_div_floor(time(0, 4, 0, 0)), Option::Some(dec!(0)));
+        assert_eq!(neg_1m.checked_div_floor(time(0, 4, 0, 0)), Option::Some(dec!(-1)));
+
+        // Div hours.
+        assert_eq!(_1h.checked_div_floor(_1m), Option::Some(dec!(60)));
+        assert_eq!(_1h.checked_div_floor(time(0, 7, 30, 0)), Option::Some(dec!(8)));
+        assert_eq!(_1h.checked_div_floor(time(0, 25, 0, 0)), Option::Some(dec!(2)));
+        assert_eq!(time(3, 10, 0, 0).checked_div_floor(time(0, 25, 0, 0)), Option::Some(dec!(7)));
+        assert_eq!(_1h.checked_div_floor(neg_1h), Option::Some(dec!(-1)));
+
+        // Div all.
+        assert_eq!(_1h_1m_1s_1ns.checked_div_floor(_1ns), Option::Some(dec!(3661000000001)));
+        assert_eq!(_1h_1m_1s_1ns.checked_div_floor(_1s), Option::Some(dec!(3661)));
+        assert_eq!(_1h_1m_1s_1ns.checked_div_floor(_1m), Option::Some(dec!(61)));
+        assert_eq!(_1h_1m_1s_1ns.checked_div_floor(_1h), Option::Some(dec!(1)));
+        assert_eq!(neg_1h_1m_1s_1ns.checked_div_floor(_1h), Option::Some(dec!(-2)));
+        assert_eq!(neg_1h_1m_1s_1ns.checked_div_floor(neg_1h), Option::Some(dec!(1)));
+
+        // Limits.
+        assert_eq!(Time::MAX.checked_div_floor(_1ns), Option::Some(dec!(9223372036854775807999999999)));
+        assert_eq!(Time::MIN.checked_div_floor(_1ns), Option::Some(dec!(-9223372036854775807999999999)));
+        assert_eq!(Time::MIN.checked_div_floor(Time::MAX), Option::Some(dec!(-1)));
+    }
+
+    #[test]
+    fn rem_time() {
+        let zero = time(0, 0, 0, 0);
+
+        // Nanoseconds.
+        let _1ns = time(0, 0, 0, 1);
+        let neg_1ns = neg_time(0, 0, 0, 1);
+
+        // Seconds.
+        let _1s = time(0, 0, 1, 0);
+        let neg_1s = neg_time(0, 0, 1, 0);
+
+        // Minutes.
+        let _1m = time(0, 1, 0, 0);
+        let neg_1m = neg_time(0, 1, 0, 0);
+
+        // Hours.
+        let _1h = time(1, 0, 0, 0);
+
+        // All.
+        let _1h_1m_1s_1ns = time(1, 1, 1, 1);
+        let neg_1h_1m_1s_1ns = neg_time(1, 1, 1, 1);
+
+        // 0 % x
+        assert_eq!(zero % _1ns, zero);
+        assert_eq!(zero % _1h_1m_1s_1ns, zero);
+        assert_eq!(zero % neg_1ns, zero);
+        assert_eq!(zero % neg_1h_1m_1s_1ns, zero);
+
+        // x % 0
+        assert_eq!(_1s.checked_rem(zero), Option::None);
+        assert_eq!(neg_1s.checked_rem(zero), Option::None);
+        assert_eq!(zero.checked_rem(zero), Option::None);
+
+        // Rem nanoseconds.
+        assert_eq!(_1ns % _1ns, zero);
+        assert_eq!(_1ns % neg_1ns, zero);
+        assert_eq!(neg_1ns % _1ns, zero);
+        assert_eq!(neg_1ns % neg_1ns, zero);
+
+        // Rem seconds.
+        assert_eq!(_1s % _1ns, zero);
+        assert_eq!(_1s % time(0, 0, 0, 300000000), time(0, 0, 0, 100000000));
+        assert_eq!(_1s % neg_time(0, 0, 0, 300000000), neg_time(0, 0, 0, 200000000));
+        assert_eq!(neg_1s % time(0, 0, 0, 300000000), time(0, 0, 0, 200000000));
+        assert_eq!(neg_1s % neg_time(0, 0, 0, 300000000), neg_time(0, 0, 0, 100000000));
+
+        // Rem minutes.
+        assert_eq!(_1m % _1s, zero);
+        assert_eq!(_1m % time(0, 0, 16, 0), time(0, 0, 12, 0));
+        assert_eq!(_1m % time(0, 4, 0, 0), _1m);
+        assert_eq!(neg_1m % time(0, 4, 0, 0), time(0, 3, 0, 0));
+
+        // Rem hours.
+        assert_eq!(_1h % _1m, zero);
+        assert_eq!(_1h % time(0, 7, 30, 0), zero);
+        assert_eq!(_1h % time(0, 25, 0, 0), time(0, 10, 0, 0));
+        assert_eq!(time(3, 10, 0, 0) % time(0, 25, 0, 0), time(0, 15, 0, 0));
+
+        // Rem all.
+        assert_eq!(_1h_1m_1s_1ns % _1s, _1ns);
+        assert_eq!(_1h_1m_1s_1ns % _1m, time(0, 0, 1, 1));
+        assert_eq!(_1h_1m_1s_1ns % _1h, time(0, 1, 1, 1));
+        assert_eq!(neg_1h_1m_1s_1ns % _1h, time(0, 58, 58, 999999999));
+        assert_eq!(neg_1h_1m_1s_1ns % neg_time(1, 0, 0, 0), neg_time(0, 1, 1, 1));
+
+        // Limits.
+        assert_eq!(Time::MAX % _1s, time(0, 0, 0, 999999999));
+        assert_eq!(Time::MIN % _1s, time(0, 0, 0, 1));
+        assert_eq!(Time::MIN % Time::MAX, zero);
+        assert_eq!(Time::MAX % Time::MIN, zero);
+    }
+
     #[test]
     fn div_num() {
         let zero = time(0, 0, 0, 0);
@@ -983,19 +1650,257 @@ mod tests {
     #[test]
     #[should_panic]
     fn greater_than_max_nanoseconds() {
-        Time::builder().nanoseconds(1000000000);
+        Time::builder().nanoseconds(1000000000).build();
     }
 
     #[test]
     #[should_panic]
     fn greater_than_max_seconds() {
-        Time::builder().seconds(60);
+        Time::builder().seconds(60).build();
     }
 
     #[test]
     #[should_panic]
     fn greater_than_max_minutes() {
-        Time::builder().minutes(60);
+        Time::builder().minutes(60).build();
+    }
+
+    #[test]
+    fn try_build() {
+        assert_eq!(Time::builder().hours(1).minutes(2).seconds(3).nanoseconds(4).try_build(),
+            Result::Ok(time(1, 2, 3, 4)));
+        assert_eq!(Time::builder().hours(2562047788015215).minutes(30).seconds(7).nanoseconds(999999999).try_build(),
+            Result::Ok(time(2562047788015215, 30, 7, 999999999)));
+
+        assert_eq!(Time::builder().minutes(60).try_build(), Result::Err(TimeError::MinutesOutOfRange(60)));
+        assert_eq!(Time::builder().seconds(60).try_build(), Result::Err(TimeError::SecondsOutOfRange(60)));
+        assert_eq!(Time::builder().nanoseconds(1000000000).try_build(),
+            Result::Err(TimeError::NanosecondsOutOfRange(1000000000)));
+        assert_eq!(Time::builder().hours(2562047788015215).minutes(30).seconds(8).try_build(),
+            Result::Err(TimeError::Overflow));
+        assert_eq!(Time::builder().negative().hours(2562047788015216).try_build(),
+            Result::Err(TimeError::Overflow));
+    }
+
+    #[test]
+    fn try_from_decimal() {
+        assert_eq!(Time::try_from(dec!(0)), Result::Ok(time(0, 0, 0, 0)));
+        assert_eq!(Time::try_from(dec!(3723.000000004)), Result::Ok(time(1, 2, 3, 4)));
+        assert_eq!(Time::try_from(dec!(-3723.000000004)), Result::Ok(neg_time(1, 2, 3, 4)));
+        assert_eq!(Time::try_from(dec!(59.9999999995)), Result::Ok(time(0, 1, 0, 0)));
+        assert_eq!(Time::try_from(dec!(9223372036854775807.999999999)),
+            Result::Ok(time(2562047788015215, 30, 7, 999999999)));
+
+        assert_eq!(Time::try_from(dec!(9223372036854775808)), Result::Err(TimeError::Overflow));
+        assert_eq!(Time::try_from(dec!(-9223372036854775808)), Result::Err(TimeError::Overflow));
+        assert_eq!(Time::try_from(dec!(1e25)), Result::Err(TimeError::Overflow));
+    }
+
+    #[test]
+    fn time_unit() {
+        for unit in [TimeUnit::Days, TimeUnit::Hours, TimeUnit::Minutes, TimeUnit::Seconds,
+                TimeUnit::Milliseconds, TimeUnit::Microseconds, TimeUnit::Nanoseconds].iter() {
+            assert_eq!(TimeUnit::from_suffix(unit.suffix()), Option::Some(*unit));
+        }
+        assert_eq!(TimeUnit::from_suffix("µs"), Option::Some(TimeUnit::Microseconds));
+        assert_eq!(TimeUnit::from_suffix("min"), Option::None);
+        assert_eq!(TimeUnit::from_suffix("S"), Option::None);
+        assert_eq!(TimeUnit::from_suffix(""), Option::None);
+
+        assert_eq!(TimeUnit::from_name("days"), Option::Some(TimeUnit::Days));
+        assert_eq!(TimeUnit::from_name("hr"), Option::Some(TimeUnit::Hours));
+        assert_eq!(TimeUnit::from_name("minutes"), Option::Some(TimeUnit::Minutes));
+        assert_eq!(TimeUnit::from_name("min"), Option::Some(TimeUnit::Minutes));
+        assert_eq!(TimeUnit::from_name("m"), Option::Some(TimeUnit::Minutes));
+        assert_eq!(TimeUnit::from_name("second"), Option::Some(TimeUnit::Seconds));
+        assert_eq!(TimeUnit::from_name("milliseconds"), Option::Some(TimeUnit::Milliseconds));
+        assert_eq!(TimeUnit::from_name("µs"), Option::Some(TimeUnit::Microseconds));
+        assert_eq!(TimeUnit::from_name("nanosecond"), Option::Some(TimeUnit::Nanoseconds));
+        assert_eq!(TimeUnit::from_name("Minutes"), Option::None);
+        assert_eq!(TimeUnit::from_name("fortnights"), Option::None);
+
+        assert_eq!(TimeUnit::Days.seconds(), dec!(86400));
+        assert_eq!(TimeUnit::Nanoseconds.seconds(), dec!(0.000000001));
+    }
+
+    #[test]
+    fn display_days() {
+        assert_eq!(format!("{:#}", time(0, 0, 0, 0)), "0s");
+        assert_eq!(format!("{:#}", time(0, 1, 2, 0)), "01:02");
+        assert_eq!(format!("{:#}", time(23, 59, 59, 999999999)), "23:59:59.999999999");
+        assert_eq!(format!("{:#}", time(24, 0, 0, 0)), "1d 00:00:00");
+        assert_eq!(format!("{:#}", time(123, 45, 0, 0)), "5d 03:45:00");
+        assert_eq!(format!("{:#}", time(50, 0, 3, 40000000)), "2d 02:00:03.04");
+        assert_eq!(format!("{:#}", neg_time(123, 45, 0, 0)), "-5d 03:45:00");
+        assert_eq!(format!("{:#}", Time::MAX), "106751991167300d 15:30:07.999999999");
+        assert_eq!(format!("{:#}", Time::MIN), "-106751991167300d 15:30:07.999999999");
+
+        for t in [time(24, 0, 0, 0), time(123, 45, 6, 789), neg_time(48, 0, 0, 1), Time::MAX, Time::MIN].iter() {
+            assert_eq!(format!("{:#}", t).parse::<Time>().unwrap(), *t);
+            assert_eq!(t.to_string().parse::<Time>().unwrap(), *t);
+        }
+    }
+
+    #[test]
+    fn to_iso8601() {
+        assert_eq!(time(0, 0, 0, 0).to_iso8601(), "PT0S");
+        assert_eq!(time(0, 0, 1, 0).to_iso8601(), "PT1S");
+        assert_eq!(time(0, 0, 0, 500000000).to_iso8601(), "PT0.5S");
+        assert_eq!(time(0, 1, 0, 0).to_iso8601(), "PT1M");
+        assert_eq!(time(1, 0, 0, 0).to_iso8601(), "PT1H");
+        assert_eq!(time(1, 30, 0, 0).to_iso8601(), "PT1H30M");
+        assert_eq!(time(1, 0, 0, 1).to_iso8601(), "PT1H0.000000001S");
+        assert_eq!(time(51, 2, 3, 40000000).to_iso8601(), "PT51H2M3.04S");
+        assert_eq!(neg_time(0, 0, 1, 0).to_iso8601(), "-PT1S");
+        assert_eq!(neg_time(1, 30, 0, 0).to_iso8601(), "-PT1H30M");
+        assert_eq!(Time::MAX.to_iso8601(), "PT2562047788015215H30M7.999999999S");
+        assert_eq!(Time::MIN.to_iso8601(), "-PT2562047788015215H30M7.999999999S");
+
+        for t in [time(0, 0, 0, 0), time(12, 34, 56, 789), neg_time(0, 1, 0, 100), Time::MAX, Time::MIN].iter() {
+            assert_eq!(Time::parse_iso8601(&t.to_iso8601()).unwrap(), *t);
+        }
+    }
+
+    #[test]
+    fn round_to() {
+        use rust_decimal::RoundingStrategy::*;
+
+        let t = time(1, 2, 3, 500000000);
+        assert_eq!(t.round_to(TimeUnit::Seconds, MidpointAwayFromZero), Option::Some(time(1, 2, 4, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, MidpointTowardZero), Option::Some(time(1, 2, 3, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, MidpointNearestEven), Option::Some(time(1, 2, 4, 0)));
+        assert_eq!(time(1, 2, 4, 500000000).round_to(TimeUnit::Seconds, MidpointNearestEven), Option::Some(time(1, 2, 4, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, ToNegativeInfinity), Option::Some(time(1, 2, 3, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, ToPositiveInfinity), Option::Some(time(1, 2, 4, 0)));
+        assert_eq!(t.round_to(TimeUnit::Minutes, MidpointAwayFromZero), Option::Some(time(1, 2, 0, 0)));
+        assert_eq!(t.round_to(TimeUnit::Minutes, ToPositiveInfinity), Option::Some(time(1, 3, 0, 0)));
+        assert_eq!(t.round_to(TimeUnit::Hours, MidpointAwayFromZero), Option::Some(time(1, 0, 0, 0)));
+        assert_eq!(t.round_to(TimeUnit::Days, MidpointAwayFromZero), Option::Some(time(0, 0, 0, 0)));
+        assert_eq!(t.round_to(TimeUnit::Days, AwayFromZero), Option::Some(time(24, 0, 0, 0)));
+        assert_eq!(t.round_to(TimeUnit::Nanoseconds, ToZero), Option::Some(t));
+        assert_eq!(time(0, 0, 0, 1499999).round_to(TimeUnit::Milliseconds, MidpointAwayFromZero), Option::Some(time(0, 0, 0, 1000000)));
+        assert_eq!(time(0, 0, 0, 1500).round_to(TimeUnit::Microseconds, MidpointTowardZero), Option::Some(time(0, 0, 0, 1000)));
+
+        // Negative.
+        let t = neg_time(1, 2, 3, 500000000);
+        assert_eq!(t.round_to(TimeUnit::Seconds, MidpointAwayFromZero), Option::Some(neg_time(1, 2, 4, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, MidpointTowardZero), Option::Some(neg_time(1, 2, 3, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, MidpointNearestEven), Option::Some(neg_time(1, 2, 4, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, ToNegativeInfinity), Option::Some(neg_time(1, 2, 4, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, ToPositiveInfinity), Option::Some(neg_time(1, 2, 3, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, ToZero), Option::Some(neg_time(1, 2, 3, 0)));
+        assert_eq!(t.round_to(TimeUnit::Seconds, AwayFromZero), Option::Some(neg_time(1, 2, 4, 0)));
+        assert_eq!(neg_time(0, 0, 0, 1).round_to(TimeUnit::Seconds, MidpointAwayFromZero), Option::Some(time(0, 0, 0, 0)));
+        assert_eq!(neg_time(0, 0, 0, 1).round_to(TimeUnit::Seconds, ToNegativeInfinity), Option::Some(neg_time(0, 0, 1, 0)));
+
+        // Overflow.
+        assert_eq!(Time::MAX.round_to(TimeUnit::Seconds, MidpointAwayFromZero), Option::None);
+        assert_eq!(Time::MAX.round_to(TimeUnit::Seconds, ToZero), Option::Some(time(2562047788015215, 30, 7, 0)));
+        assert_eq!(Time::MIN.round_to(TimeUnit::Days, AwayFromZero), Option::None);
+        assert_eq!(Time::MIN.round_to(TimeUnit::Days, ToZero), Option::Some(neg_time(2562047788015200, 0, 0, 0)));
+        assert_eq!(Time::MAX.round_to(TimeUnit::Nanoseconds, AwayFromZero), Option::Some(Time::MAX));
+    }
+
+    #[test]
+    fn round_to_multiple() {
+        use rust_decimal::RoundingStrategy::*;
+
+        let quarter_hour = time(0, 15, 0, 0);
+        assert_eq!(time(1, 7, 29, 0).round_to_multiple(quarter_hour, MidpointAwayFromZero), Option::Some(time(1, 0, 0, 0)));
+        assert_eq!(time(1, 7, 30, 0).round_to_multiple(quarter_hour, MidpointAwayFromZero), Option::Some(time(1, 15, 0, 0)));
+        assert_eq!(time(1, 14, 59, 0).round_to_multiple(quarter_hour, ToNegativeInfinity), Option::Some(time(1, 0, 0, 0)));
+        assert_eq!(time(1, 0, 0, 1).round_to_multiple(quarter_hour, ToPositiveInfinity), Option::Some(time(1, 15, 0, 0)));
+        assert_eq!(time(1, 0, 0, 0).round_to_multiple(quarter_hour, ToPositiveInfinity), Option::Some(time(1, 0, 0, 0)));
+        assert_eq!(neg_time(1, 7, 30, 0).round_to_multiple(quarter_hour, MidpointAwayFromZero), Option::Some(neg_time(1, 15, 0, 0)));
+        assert_eq!(neg_time(1, 14, 59, 0).round_to_multiple(quarter_hour, ToZero), Option::Some(neg_time(1, 0, 0, 0)));
+        assert_eq!(time(0, 0, 1, 0).round_to_multiple(time(0, 0, 0, 300000000), ToNegativeInfinity), Option::Some(time(0, 0, 0, 900000000)));
+
+        // The sign of the increment is ignored.
+        assert_eq!(time(1, 7, 30, 0).round_to_multiple(neg_time(0, 15, 0, 0), MidpointAwayFromZero), Option::Some(time(1, 15, 0, 0)));
+
+        // Zero increment.
+        assert_eq!(time(1, 7, 30, 0).round_to_multiple(time(0, 0, 0, 0), MidpointAwayFromZero), Option::None);
+
+        // Overflow.
+        assert_eq!(Time::MAX.round_to_multiple(quarter_hour, ToPositiveInfinity), Option::None);
+    }
+
+    #[test]
+    fn min_max() {
+        assert_eq!(Time::MAX, time(2562047788015215, 30, 7, 999999999));
+        assert_eq!(Time::MIN, neg_time(2562047788015215, 30, 7, 999999999));
+    }
+
+    #[test]
+    fn saturating() {
+        let _1ns = time(0, 0, 0, 1);
+        let _1h = time(1, 0, 0, 0);
+
+        assert_eq!(_1h.saturating_add(_1h), time(2, 0, 0, 0));
+        assert_eq!(_1h.saturating_sub(_1h), time(0, 0, 0, 0));
+        assert_eq!(_1h.saturating_mul(dec!(2.5)), time(2, 30, 0, 0));
+        assert_eq!(_1h.saturating_div(dec!(4)), time(0, 15, 0, 0));
+
+        assert_eq!(Time::MAX.saturating_add(_1ns), Time::MAX);
+        assert_eq!(Time::MAX.saturating_sub(neg_time(0, 0, 0, 1)), Time::MAX);
+        assert_eq!(Time::MIN.saturating_sub(_1ns), Time::MIN);
+        assert_eq!(Time::MIN.saturating_add(neg_time(0, 0, 0, 1)), Time::MIN);
+
+        assert_eq!(Time::MAX.saturating_mul(dec!(2)), Time::MAX);
+        assert_eq!(Time::MAX.saturating_mul(dec!(-2)), Time::MIN);
+        assert_eq!(Time::MIN.saturating_mul(dec!(2)), Time::MIN);
+        assert_eq!(Time::MIN.saturating_mul(dec!(-2)), Time::MAX);
+        assert_eq!(_1h.saturating_mul(dec!(1e25)), Time::MAX);
+        assert_eq!(_1h.saturating_mul(dec!(-1e25)), Time::MIN);
+
+        assert_eq!(Time::MAX.saturating_div(dec!(0.5)), Time::MAX);
+        assert_eq!(Time::MAX.saturating_div(dec!(-0.5)), Time::MIN);
+        assert_eq!(Time::MIN.saturating_div(dec!(0.5)), Time::MIN);
+        assert_eq!(Time::MIN.saturating_div(dec!(-0.5)), Time::MAX);
+    }
+
+    #[test]
+    fn wrapping() {
+        let zero = time(0, 0, 0, 0);
+        let _1ns = time(0, 0, 0, 1);
+        let _1h = time(1, 0, 0, 0);
+
+        assert_eq!(_1h.wrapping_add(_1h), time(2, 0, 0, 0));
+        assert_eq!(_1h.wrapping_sub(_1h), zero);
+        assert_eq!(_1h.wrapping_mul(dec!(2.5)), time(2, 30, 0, 0));
+        assert_eq!(_1h.wrapping_div(dec!(4)), time(0, 15, 0, 0));
+
+        assert_eq!(Time::MAX.wrapping_add(_1ns), Time::MIN);
+        assert_eq!(Time::MAX.wrapping_add(time(0, 0, 1, 0)), neg_time(2562047788015215, 30, 7, 0));
+        assert_eq!(Time::MIN.wrapping_sub(_1ns), Time::MAX);
+        assert_eq!(Time::MAX.wrapping_add(Time::MAX), neg_time(0, 0, 0, 1));
+
+        // MAX * 2 == MAX + MAX
+        assert_eq!(Time::MAX.wrapping_mul(dec!(2)), neg_time(0, 0, 0, 1));
+        assert_eq!(Time::MAX.wrapping_mul(dec!(2.5)), Time::MAX.wrapping_add(Time::MAX).wrapping_add(Time::MAX / dec!(2)));
+        assert_eq!(Time::MAX.wrapping_mul(dec!(-2)), _1ns);
+        assert_eq!(Time::MAX.wrapping_div(dec!(0.5)), neg_time(0, 0, 0, 1));
+        // The time range is 18,446,744,073,709,551,615.999999999 seconds.
+        assert_eq!(_1ns.wrapping_mul(dec!(18446744073709551615999999999)), zero);
+        assert_eq!(_1ns.wrapping_mul(dec!(18446744073709551616000000000)), _1ns);
+    }
+
+    #[test]
+    fn checked_overflow() {
+        let max = time(2562047788015215, 30, 7, 999999999);
+        let min = neg_time(2562047788015215, 30, 7, 999999999);
+        let _1ns = time(0, 0, 0, 1);
+
+        assert_eq!(max.checked_add(time(0, 0, 0, 0)), Option::Some(max));
+        assert_eq!(max.checked_add(_1ns), Option::None);
+        assert_eq!(min.checked_sub(_1ns), Option::None);
+        assert_eq!(min.checked_add(_1ns), Option::Some(neg_time(2562047788015215, 30, 7, 999999998)));
+        assert_eq!(max.checked_mul(dec!(2)), Option::None);
+        assert_eq!(max.checked_mul(dec!(-1)), Option::Some(min));
+        assert_eq!(max.checked_mul(dec!(1e20)), Option::None);
+        assert_eq!(max.checked_div(dec!(0.5)), Option::None);
+        assert_eq!(max.checked_div(dec!(0)), Option::None);
+        assert_eq!(max.checked_div(dec!(-1)), Option::Some(min));
     }
 
     #[test]
@@ -1049,6 +1954,7 @@ mod tests {
             .build()
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn assert_time(
         time: Time, total_seconds: i64, nanoseconds_offset: u32,
         signum: i64, hours: u64, minutes: u8, seconds: u8, nanoseconds: u32,
@@ -1,17 +1,26 @@
+use std::convert::TryFrom;
+use std::error;
 use std::fmt;
 use std::iter::Peekable;
 use std::result::Result;
-use std::str::Chars;
+use std::str::CharIndices;
+use std::str::FromStr;
 
+use rust_decimal::Decimal;
+
+use crate::span::Span;
 use crate::time::Time;
+use crate::time::TimeUnit;
 
+/// A token in a time.
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
 pub enum Token {
     Hyphen,
     Number(String),
     Colon,
     FullStop,
-    S,
+    Unit(TimeUnit),
 }
 
 impl fmt::Display for Token {
@@ -21,7 +30,7 @@ impl fmt::Display for Token {
             Token::Number(n) => n.clone(),
             Token::Colon => String::from(":"),
             Token::FullStop => String::from("."),
-            Token::S => String::from("s"),
+            Token::Unit(unit) => unit.to_string(),
         };
 
         write!(f, "{}", t)
@@ -29,35 +38,69 @@ impl fmt::Display for Token {
 }
 
 struct Lexer<'a> {
-    chars: Peekable<Chars<'a>>,
-    tokens: Vec<Token>,
+    chars: Peekable<CharIndices<'a>>,
+    input_length: usize,
+    tokens: Vec<(Token, Span)>,
     scan_complete: bool,
 }
 
+/// An error which occurred while scanning a time.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum LexError {
-    UnexpectedCharacter(char),
-    EndOfInput,
+    UnexpectedCharacter(char, Span),
+    UnknownUnit(String, Span),
+    EndOfInput(Span),
+}
+
+impl LexError {
+    /// Returns the location of the error in the input.
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedCharacter(_, span)
+            | LexError::UnknownUnit(_, span)
+            | LexError::EndOfInput(span) => *span,
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::UnexpectedCharacter(c, _) => write!(f, "unexpected character '{}'", c),
+            LexError::UnknownUnit(unit, _) => write!(f, "unknown unit '{}'", unit),
+            LexError::EndOfInput(_) => write!(f, "unexpected end of input"),
+        }
+    }
 }
 
+impl error::Error for LexError {}
+
 impl<'a> Lexer<'a> {
-    fn new(input: &str) -> Lexer {
+    fn new(input: &str) -> Lexer<'_> {
         Lexer {
-            chars: input.chars().peekable(),
+            chars: input.char_indices().peekable(),
+            input_length: input.len(),
             tokens: Vec::new(),
             scan_complete: false,
         }
     }
 
-    fn scan(&mut self) -> Result<Vec<Token>, Vec<LexError>> {
+    fn scan(&mut self) -> Result<Vec<(Token, Span)>, Vec<LexError>> {
         let mut errors = Vec::new();
 
         if !self.scan_complete {
             while let Some(ch) = self.peek() {
-                if ch.is_numeric() {
+                if ch.is_ascii_digit() {
                     if let Result::Err(err) = self.scan_number() {
                         errors.push(err);
                     }
+                } else if ch.is_alphabetic() {
+                    if let Result::Err(err) = self.scan_unit() {
+                        errors.push(err);
+                    }
+                } else if ch.is_whitespace() && self.is_unit_separator() {
+                    self.next();
                 } else if let Result::Err(err) = self.scan_character() {
                     errors.push(err);
                 }
@@ -73,10 +116,11 @@ impl<'a> Lexer<'a> {
     }
 
     fn scan_number(&mut self) -> Result<(), LexError> {
+        let start = self.position();
         let mut num = String::new();
         loop {
             match self.peek() {
-                Option::Some(c) if c.is_digit(10) => {
+                Option::Some(c) if c.is_ascii_digit() => {
                     num.push(*c);
                     self.next();
                 }
@@ -84,54 +128,255 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        self.tokens.push(Token::Number(num));
+        let span = Span::new(start, self.position());
+        self.tokens.push((Token::Number(num), span));
         Ok(())
     }
 
+    fn scan_unit(&mut self) -> Result<(), LexError> {
+        let start = self.position();
+        let mut suffix = String::new();
+        while let Option::Some(c) = self.peek() {
+            if !c.is_alphabetic() {
+                break;
+            }
+            suffix.push(*c);
+            self.next();
+        }
+
+        let span = Span::new(start, self.position());
+        match TimeUnit::from_suffix(&suffix) {
+            Option::Some(unit) => {
+                self.tokens.push((Token::Unit(unit), span));
+                Result::Ok(())
+            }
+            Option::None => Result::Err(LexError::UnknownUnit(suffix, span)),
+        }
+    }
+
+    /// Returns `true` if the whitespace at the current position separates two unit-suffixed
+    /// components, such as the space in `1h 30m`.
+    fn is_unit_separator(&self) -> bool {
+        if let Option::Some((Token::Unit(_), _)) = self.tokens.last() {
+            let mut chars = self.chars.clone().map(|(_, c)| c);
+            chars
+                .find(|c| !c.is_whitespace())
+                .is_some_and(|c| c.is_ascii_digit())
+        } else {
+            false
+        }
+    }
+
     fn scan_character(&mut self) -> Result<(), LexError> {
+        let start = self.position();
         let token = match self.next() {
             Option::Some('-') => Token::Hyphen,
             Option::Some(':') => Token::Colon,
             Option::Some('.') => Token::FullStop,
-            Option::Some('s') => Token::S,
-            Option::Some(c) => return Result::Err(LexError::UnexpectedCharacter(c)),
-            Option::None => return Result::Err(LexError::EndOfInput),
+            Option::Some(c) => {
+                return Result::Err(LexError::UnexpectedCharacter(
+                    c,
+                    Span::new(start, self.position()),
+                ))
+            }
+            Option::None => return Result::Err(LexError::EndOfInput(Span::new(start, start))),
         };
 
-        self.tokens.push(token);
+        let span = Span::new(start, self.position());
+        self.tokens.push((token, span));
         Ok(())
     }
 
     fn peek(&mut self) -> Option<&char> {
-        self.chars.peek()
+        self.chars.peek().map(|(_, c)| c)
     }
 
     fn next(&mut self) -> Option<char> {
-        self.chars.next()
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    /// Returns the byte offset of the next character, or the length of the input if there are no
+    /// more characters.
+    fn position(&mut self) -> usize {
+        match self.chars.peek() {
+            Option::Some((i, _)) => *i,
+            Option::None => self.input_length,
+        }
     }
 }
 
 struct Parser {
-    tokens: Vec<Token>,
+    tokens: Vec<(Token, Span)>,
+    input_length: usize,
 }
 
+/// An error which occurred while parsing a time.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ParseError {
     LexError(Vec<LexError>),
-    ExpectedNumber(Option<Token>),
-    ExceededMaxComponents,
-    ExpectedNumberAfterDecimal(Option<Token>),
-    ExpectedSecondsIdentifier,
-    UnexpectedSecondsIdentifier,
-    ExpectedTwoDigitMinutes(String),
-    ExpectedTwoDigitSeconds(String),
-    SecondsOutOfRange(u8),
-    MinutesOutOfRange(u8),
-    FractionalSecondsTooLarge(String),
-    ExpectedEndOfInputOrFraction(Option<Token>),
-    ExpectedEndOfInput(Token),
+    ExpectedNumber(Option<Token>, Span),
+    ExceededMaxComponents(Span),
+    ExpectedNumberAfterDecimal(Option<Token>, Span),
+    ExpectedUnit(Option<Token>, Span),
+    UnexpectedUnit(TimeUnit, Span),
+    UnitOutOfOrder(TimeUnit, Span),
+    ExpectedHoursAfterDays(Span),
+    ExpectedTwoDigitHours(String, Span),
+    ExpectedTwoDigitMinutes(String, Span),
+    ExpectedTwoDigitSeconds(String, Span),
+    SecondsOutOfRange(u64, Span),
+    MinutesOutOfRange(u8, Span),
+    HoursOutOfRange(u8, Span),
+    ExceededMaxTime(Span),
+    FractionalSecondsTooLarge(String, Span),
+    ExpectedEndOfInput(Token, Span),
+    ExpectedIsoDurationDesignator(Span),
+    ExpectedIsoNumber(Option<char>, Span),
+    ExpectedIsoDesignator(Span),
+    UnexpectedIsoDesignator(char, Span),
+    IsoCalendarComponent(char, Span),
+    EmptyIsoDuration(Span),
+}
+
+impl ParseError {
+    /// Returns the location of the error in the input.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::LexError(errors) => errors
+                .iter()
+                .map(LexError::span)
+                .fold(errors[0].span(), Span::to),
+            ParseError::ExpectedNumber(_, span)
+            | ParseError::ExceededMaxComponents(span)
+            | ParseError::ExpectedNumberAfterDecimal(_, span)
+            | ParseError::ExpectedUnit(_, span)
+            | ParseError::UnexpectedUnit(_, span)
+            | ParseError::UnitOutOfOrder(_, span)
+            | ParseError::ExpectedHoursAfterDays(span)
+            | ParseError::ExpectedTwoDigitHours(_, span)
+            | ParseError::ExpectedTwoDigitMinutes(_, span)
+            | ParseError::ExpectedTwoDigitSeconds(_, span)
+            | ParseError::SecondsOutOfRange(_, span)
+            | ParseError::MinutesOutOfRange(_, span)
+            | ParseError::HoursOutOfRange(_, span)
+            | ParseError::ExceededMaxTime(span)
+            | ParseError::FractionalSecondsTooLarge(_, span)
+            | ParseError::ExpectedEndOfInput(_, span)
+            | ParseError::ExpectedIsoDurationDesignator(span)
+            | ParseError::ExpectedIsoNumber(_, span)
+            | ParseError::ExpectedIsoDesignator(span)
+            | ParseError::UnexpectedIsoDesignator(_, span)
+            | ParseError::IsoCalendarComponent(_, span)
+            | ParseError::EmptyIsoDuration(span) => *span,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::LexError(errors) => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Result::Ok(())
+            }
+            ParseError::ExpectedNumber(Option::Some(t), _) => {
+                write!(f, "expected a number, found '{}'", t)
+            }
+            ParseError::ExpectedNumber(Option::None, _) => {
+                write!(f, "expected a number, found end of input")
+            }
+            ParseError::ExceededMaxComponents(_) => {
+                write!(
+                    f,
+                    "too many components, expected at most days, hours, minutes and seconds"
+                )
+            }
+            ParseError::ExpectedNumberAfterDecimal(Option::Some(t), _) => {
+                write!(f, "expected a number after '.', found '{}'", t)
+            }
+            ParseError::ExpectedNumberAfterDecimal(Option::None, _) => {
+                write!(f, "expected a number after '.', found end of input")
+            }
+            ParseError::ExpectedUnit(Option::Some(t), _) => {
+                write!(f, "expected a unit such as 's', found '{}'", t)
+            }
+            ParseError::ExpectedUnit(Option::None, _) => {
+                write!(f, "expected a unit such as 's', found end of input")
+            }
+            ParseError::UnexpectedUnit(unit, _) => {
+                write!(f, "unexpected '{}' after a time with minutes", unit)
+            }
+            ParseError::UnitOutOfOrder(unit, _) => write!(
+                f,
+                "units must be written from largest to smallest, found '{}'",
+                unit
+            ),
+            ParseError::ExpectedHoursAfterDays(_) => {
+                write!(f, "expected hours, minutes and seconds after days")
+            }
+            ParseError::ExpectedTwoDigitHours(h, _) => {
+                write!(f, "expected two digit hours after days, found '{}'", h)
+            }
+            ParseError::ExpectedTwoDigitMinutes(m, _) => {
+                write!(f, "expected two digit minutes, found '{}'", m)
+            }
+            ParseError::ExpectedTwoDigitSeconds(s, _) => {
+                write!(f, "expected two digit seconds, found '{}'", s)
+            }
+            ParseError::SecondsOutOfRange(s, _) => {
+                write!(f, "seconds must be less than 60, found {}", s)
+            }
+            ParseError::MinutesOutOfRange(m, _) => {
+                write!(f, "minutes must be less than 60, found {}", m)
+            }
+            ParseError::HoursOutOfRange(h, _) => {
+                write!(f, "hours must be less than 24 after days, found {}", h)
+            }
+            ParseError::ExceededMaxTime(_) => write!(f, "time exceeds the maximum time"),
+            ParseError::FractionalSecondsTooLarge(fraction, _) => write!(
+                f,
+                "fractional seconds must have at most 9 digits, found '{}'",
+                fraction
+            ),
+            ParseError::ExpectedEndOfInput(t, _) => {
+                write!(f, "expected end of time, found '{}'", t)
+            }
+            ParseError::ExpectedIsoDurationDesignator(_) => {
+                write!(f, "expected an ISO 8601 duration starting with 'P'")
+            }
+            ParseError::ExpectedIsoNumber(Option::Some(c), _) => {
+                write!(f, "expected a number, found '{}'", c)
+            }
+            ParseError::ExpectedIsoNumber(Option::None, _) => {
+                write!(f, "expected a number, found end of input")
+            }
+            ParseError::ExpectedIsoDesignator(_) => write!(
+                f,
+                "expected a designator such as 'H', 'M' or 'S' after a number"
+            ),
+            ParseError::UnexpectedIsoDesignator(c, _) => {
+                write!(f, "unexpected designator '{}'", c)
+            }
+            ParseError::IsoCalendarComponent(c, _) => write!(
+                f,
+                "unsupported component '{}', durations with years or months do not have a fixed length",
+                c
+            ),
+            ParseError::EmptyIsoDuration(_) => {
+                write!(f, "expected at least one component in ISO 8601 duration")
+            }
+        }
+    }
 }
 
+impl error::Error for ParseError {}
+
 impl std::convert::From<Vec<LexError>> for ParseError {
     fn from(lex_error: Vec<LexError>) -> Self {
         ParseError::LexError(lex_error)
@@ -139,30 +384,69 @@ impl std::convert::From<Vec<LexError>> for ParseError {
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Parser {
-        Parser { tokens }
+    /// Returns `true` if the tokens start with days written with a unit, followed by hours,
+    /// minutes and seconds, such as `2d 03:04:05`.
+    fn has_days_prefix(tokens: &[(Token, Span)]) -> bool {
+        let tokens = match tokens.first() {
+            Option::Some((Token::Hyphen, _)) => &tokens[1..],
+            _ => tokens,
+        };
+        matches!(
+            tokens,
+            [
+                (Token::Number(_), _),
+                (Token::Unit(TimeUnit::Days), _),
+                (Token::Number(_), _),
+                (Token::Colon, _),
+                ..
+            ]
+        )
+    }
+
+    fn new(tokens: Vec<(Token, Span)>, input_length: usize) -> Parser {
+        Parser {
+            tokens,
+            input_length,
+        }
     }
 
     fn parse(self) -> Result<Time, ParseError> {
+        let input = Span::new(0, self.input_length);
+        let end_of_input = Span::new(self.input_length, self.input_length);
+        let has_days_prefix = Parser::has_days_prefix(&self.tokens);
         let mut token_iter = self.tokens.into_iter().peekable();
 
         // Only consume first token if it is a hyphen, indicating a negative time.
-        let is_negative = if token_iter.peek() == Option::Some(&Token::Hyphen) {
+        let is_negative = if let Option::Some((Token::Hyphen, _)) = token_iter.peek() {
             token_iter.next();
             true
         } else {
             false
         };
 
+        // Consume days written with a unit, such as the `2d` in `2d 03:04:05`.
+        let mut days = if has_days_prefix {
+            let days = token_iter.next().map(|(t, span)| (t.to_string(), span));
+            token_iter.next();
+            days
+        } else {
+            Option::None
+        };
+
         // Read numbers, separated by colons.
         let mut components = Vec::new();
         loop {
             match token_iter.next() {
-                Option::Some(Token::Number(n)) => components.push(n),
-                t => return Result::Err(ParseError::ExpectedNumber(t)),
+                Option::Some((Token::Number(n), span)) => components.push((n, span)),
+                Option::Some((t, span)) => {
+                    return Result::Err(ParseError::ExpectedNumber(Option::Some(t), span))
+                }
+                Option::None => {
+                    return Result::Err(ParseError::ExpectedNumber(Option::None, end_of_input))
+                }
             }
             match token_iter.peek() {
-                Option::Some(Token::Colon) => {
+                Option::Some((Token::Colon, _)) => {
                     token_iter.next();
                 }
                 _ => break,
@@ -170,42 +454,84 @@ impl Parser {
         }
         // If we reach this point, we must have consumed at least one number from tokens so we don't
         // need to check for `components.len() == 0`.
-        if components.len() > 3 {
-            return Result::Err(ParseError::ExceededMaxComponents);
+        let max_components = if days.is_some() { 3 } else { 4 };
+        if components.len() > max_components {
+            let extra = components[max_components]
+                .1
+                .to(components[components.len() - 1].1);
+            return Result::Err(ParseError::ExceededMaxComponents(extra));
         }
 
         // Consume fractional seconds.
-        let fraction = if let Option::Some(&Token::FullStop) = token_iter.peek() {
-            // Consume full stop.
-            token_iter.next();
+        let fraction = parse_fraction(&mut token_iter, end_of_input)?;
 
-            // Consume number.
-            match token_iter.next() {
-                Option::Some(Token::Number(n)) => Option::Some(n.to_string()),
-                t => return Result::Err(ParseError::ExpectedNumberAfterDecimal(t)),
+        // A number followed by a unit is the first component of a unit-suffixed time, such as
+        // `1h30m`.
+        if let Option::Some((Token::Unit(unit), span)) = token_iter.peek() {
+            if components.len() != 1 {
+                return Result::Err(ParseError::UnexpectedUnit(*unit, *span));
             }
-        } else {
-            Option::None
-        };
 
-        // Consume 's'.
-        let is_seconds = if token_iter.peek() == Option::Some(&Token::S) {
-            token_iter.next();
-            true
-        } else {
-            false
-        };
+            let mut total = Decimal::ZERO;
+            let mut previous_unit = Option::None;
+            let mut value = (components.remove(0), fraction);
+            loop {
+                let (unit, span) = match token_iter.next() {
+                    Option::Some((Token::Unit(unit), span)) => (unit, span),
+                    Option::Some((t, span)) => {
+                        return Result::Err(ParseError::ExpectedUnit(Option::Some(t), span))
+                    }
+                    Option::None => {
+                        return Result::Err(ParseError::ExpectedUnit(Option::None, end_of_input))
+                    }
+                };
+                if previous_unit.is_some_and(|previous| unit <= previous) {
+                    return Result::Err(ParseError::UnitOutOfOrder(unit, span));
+                }
+                previous_unit = Option::Some(unit);
+
+                let component = unit_component(&value.0, &value.1, unit)?;
+                total = total
+                    .checked_add(component)
+                    .ok_or(ParseError::ExceededMaxTime(input))?;
+
+                // Continue while there are more components.
+                match token_iter.next() {
+                    Option::Some((Token::Number(n), span)) => {
+                        value = ((n, span), parse_fraction(&mut token_iter, end_of_input)?)
+                    }
+                    Option::Some((t, span)) => {
+                        return Result::Err(ParseError::ExpectedEndOfInput(t, span))
+                    }
+                    Option::None => break,
+                }
+            }
+
+            if is_negative {
+                total = -total;
+            }
+            return Time::try_from(total).map_err(|_| ParseError::ExceededMaxTime(input));
+        }
+
+        // A single number must have a unit.
+        if components.len() == 1 {
+            return Result::Err(match token_iter.next() {
+                Option::Some((t, span)) => ParseError::ExpectedUnit(Option::Some(t), span),
+                Option::None => ParseError::ExpectedUnit(Option::None, end_of_input),
+            });
+        }
 
         // We've consumed everything we understand.
-        if let Option::Some(t) = token_iter.peek() {
-            return Result::Err(ParseError::ExpectedEndOfInput(t.clone()));
+        if let Option::Some((t, span)) = token_iter.next() {
+            return Result::Err(ParseError::ExpectedEndOfInput(t, span));
         }
 
-        if is_seconds && components.len() != 1 {
-            return Result::Err(ParseError::UnexpectedSecondsIdentifier);
+        if components.len() == 4 {
+            days = Option::Some(components.remove(0));
         }
-        if !is_seconds && components.len() == 1 {
-            return Result::Err(ParseError::ExpectedSecondsIdentifier);
+        if days.is_some() && components.len() != 3 {
+            let span = components[0].1.to(components[components.len() - 1].1);
+            return Result::Err(ParseError::ExpectedHoursAfterDays(span));
         }
 
         // Construct time.
@@ -215,48 +541,65 @@ impl Parser {
             time_builder.negative();
         }
 
-        // Hours.
+        // Days and hours.
         if components.len() >= 3 {
-            let h = components[0].to_string();
-            time_builder.hours(h.parse().unwrap());
+            let (h, span) = &components[0];
+            let hours = match &days {
+                Option::Some((d, days_span)) => {
+                    if h.len() != 2 {
+                        return Result::Err(ParseError::ExpectedTwoDigitHours(
+                            h.to_string(),
+                            *span,
+                        ));
+                    }
+                    let hours = h.parse().unwrap();
+                    if hours >= 24 {
+                        return Result::Err(ParseError::HoursOutOfRange(hours, *span));
+                    }
+                    d.parse::<u64>()
+                        .ok()
+                        .and_then(|d| d.checked_mul(24))
+                        .and_then(|d| d.checked_add(u64::from(hours)))
+                        .ok_or(ParseError::ExceededMaxTime(*days_span))?
+                }
+                Option::None => match h.parse() {
+                    Result::Ok(hours) => hours,
+                    Result::Err(_) => return Result::Err(ParseError::ExceededMaxTime(*span)),
+                },
+            };
+            time_builder.hours(hours);
         }
         // Minutes
         if components.len() >= 2 {
-            let m = components[if components.len() == 2 { 0 } else { 1 }].to_string();
+            let (m, span) = &components[if components.len() == 2 { 0 } else { 1 }];
 
             if m.len() != 2 {
-                return Result::Err(ParseError::ExpectedTwoDigitMinutes(m));
+                return Result::Err(ParseError::ExpectedTwoDigitMinutes(m.to_string(), *span));
             }
             let minutes = m.parse().unwrap();
             if minutes >= 60 {
-                return Result::Err(ParseError::MinutesOutOfRange(minutes));
+                return Result::Err(ParseError::MinutesOutOfRange(minutes, *span));
             }
             time_builder.minutes(minutes);
         }
         // Seconds
-        if !components.is_empty() {
-            let s = components[if components.len() == 1 {
-                0
-            } else if components.len() == 2 {
-                1
-            } else {
-                2
-            }]
-            .to_string();
+        let (s, span) = &components[components.len() - 1];
 
-            if s.len() != 2 && !is_seconds {
-                return Result::Err(ParseError::ExpectedTwoDigitSeconds(s));
-            }
-            let seconds = s.parse().unwrap();
-            if seconds >= 60 {
-                return Result::Err(ParseError::SecondsOutOfRange(seconds));
-            }
-            time_builder.seconds(seconds);
+        if s.len() != 2 {
+            return Result::Err(ParseError::ExpectedTwoDigitSeconds(s.to_string(), *span));
         }
+        let seconds = match s.parse() {
+            Result::Ok(seconds) => seconds,
+            Result::Err(_) => return Result::Err(ParseError::ExceededMaxTime(*span)),
+        };
+        if seconds >= 60 {
+            return Result::Err(ParseError::SecondsOutOfRange(seconds, *span));
+        }
+        time_builder.seconds(seconds as u8);
         // Nanoseconds
-        if let Option::Some(mut ns) = fraction {
+        if let Option::Some((mut ns, span)) = fraction {
             if ns.len() > 9 {
-                return Result::Err(ParseError::FractionalSecondsTooLarge(ns.to_string()));
+                return Result::Err(ParseError::FractionalSecondsTooLarge(ns, span));
             }
             while ns.len() < 9 {
                 ns.push('0');
@@ -264,12 +607,249 @@ impl Parser {
             time_builder.nanoseconds(ns.parse().unwrap());
         }
 
-        Result::Ok(time_builder.build())
+        time_builder
+            .try_build()
+            .map_err(|_| ParseError::ExceededMaxTime(input))
+    }
+}
+
+/// Consumes a full stop followed by a number, if the next token is a full stop.
+fn parse_fraction<I>(
+    tokens: &mut Peekable<I>,
+    end_of_input: Span,
+) -> Result<Option<(String, Span)>, ParseError>
+where
+    I: Iterator<Item = (Token, Span)>,
+{
+    if let Option::Some((Token::FullStop, _)) = tokens.peek() {
+        // Consume full stop.
+        tokens.next();
+
+        // Consume number.
+        match tokens.next() {
+            Option::Some((Token::Number(n), span)) => Result::Ok(Option::Some((n, span))),
+            Option::Some((t, span)) => Result::Err(ParseError::ExpectedNumberAfterDecimal(
+                Option::Some(t),
+                span,
+            )),
+            Option::None => Result::Err(ParseError::ExpectedNumberAfterDecimal(
+                Option::None,
+                end_of_input,
+            )),
+        }
+    } else {
+        Result::Ok(Option::None)
+    }
+}
+
+/// Returns the number of seconds in a unit-suffixed component, such as `2.5h`.
+fn unit_component(
+    number: &(String, Span),
+    fraction: &Option<(String, Span)>,
+    unit: TimeUnit,
+) -> Result<Decimal, ParseError> {
+    let (n, mut span) = number.clone();
+    let mut value = n;
+    if let Option::Some((f, fraction_span)) = fraction {
+        if unit == TimeUnit::Seconds && f.len() > 9 {
+            return Result::Err(ParseError::FractionalSecondsTooLarge(
+                f.clone(),
+                *fraction_span,
+            ));
+        }
+        value = format!("{}.{}", value, f);
+        span = span.to(*fraction_span);
+    }
+
+    seconds_in(&value, unit.seconds(), span)
+}
+
+/// Returns the number of seconds in `value` units of `unit_seconds` seconds each.
+fn seconds_in(value: &str, unit_seconds: Decimal, span: Span) -> Result<Decimal, ParseError> {
+    Decimal::from_str(value)
+        .ok()
+        .and_then(|value| value.checked_mul(unit_seconds))
+        .ok_or(ParseError::ExceededMaxTime(span))
+}
+
+/// A parser for ISO 8601 durations, such as `PT1H30M` or `P2DT3H`.
+///
+/// Only the components with a fixed length are supported: weeks, days, hours, minutes and seconds.
+/// A duration may be negated with a leading `-`.
+struct Iso8601Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    input_length: usize,
+}
+
+impl<'a> Iso8601Parser<'a> {
+    const SECONDS_PER_WEEK: u32 = 604_800;
+
+    fn new(input: &str) -> Iso8601Parser<'_> {
+        Iso8601Parser {
+            chars: input.char_indices().peekable(),
+            input_length: input.len(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Time, ParseError> {
+        let input = Span::new(0, self.input_length);
+
+        let is_negative = if let Option::Some('-') = self.peek() {
+            self.next();
+            true
+        } else {
+            false
+        };
+
+        let start = self.position();
+        if self.next() != Option::Some('P') {
+            return Result::Err(ParseError::ExpectedIsoDurationDesignator(Span::new(
+                start,
+                self.position(),
+            )));
+        }
+
+        let mut total = Decimal::ZERO;
+        let mut is_time = false;
+        let mut previous_rank = Option::None;
+        let mut is_empty = true;
+        loop {
+            let start = self.position();
+            match self.peek() {
+                Option::None => break,
+                Option::Some('T') => {
+                    self.next();
+                    if is_time {
+                        return Result::Err(ParseError::UnexpectedIsoDesignator(
+                            'T',
+                            Span::new(start, self.position()),
+                        ));
+                    }
+                    is_time = true;
+                    // 'T' must be followed by at least one time component.
+                    if self.peek().is_none() {
+                        let end_of_input = Span::new(self.input_length, self.input_length);
+                        return Result::Err(ParseError::ExpectedIsoNumber(
+                            Option::None,
+                            end_of_input,
+                        ));
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+
+            let (value, fraction_digits, value_span) = self.scan_number()?;
+
+            let start = self.position();
+            let designator = self.next();
+            let span = Span::new(start, self.position());
+            let (rank, unit_seconds) = match (designator, is_time) {
+                (Option::Some(c @ 'Y'), false) | (Option::Some(c @ 'M'), false) => {
+                    return Result::Err(ParseError::IsoCalendarComponent(c, span))
+                }
+                (Option::Some('W'), false) => (0, Decimal::from(Iso8601Parser::SECONDS_PER_WEEK)),
+                (Option::Some('D'), false) => (1, TimeUnit::Days.seconds()),
+                (Option::Some('H'), true) => (2, TimeUnit::Hours.seconds()),
+                (Option::Some('M'), true) => (3, TimeUnit::Minutes.seconds()),
+                (Option::Some('S'), true) => (4, TimeUnit::Seconds.seconds()),
+                (Option::Some(c), _) => {
+                    return Result::Err(ParseError::UnexpectedIsoDesignator(c, span))
+                }
+                (Option::None, _) => return Result::Err(ParseError::ExpectedIsoDesignator(span)),
+            };
+            // Components must be in order, and each component may only appear once.
+            if previous_rank.is_some_and(|previous| rank <= previous) {
+                return Result::Err(ParseError::UnexpectedIsoDesignator(
+                    designator.unwrap(),
+                    span,
+                ));
+            }
+            previous_rank = Option::Some(rank);
+
+            if rank == 4 && fraction_digits > 9 {
+                let fraction = value.split('.').nth(1).unwrap_or_default().to_string();
+                return Result::Err(ParseError::FractionalSecondsTooLarge(fraction, value_span));
+            }
+            total = total
+                .checked_add(seconds_in(&value, unit_seconds, value_span)?)
+                .ok_or(ParseError::ExceededMaxTime(input))?;
+            is_empty = false;
+        }
+
+        if is_empty {
+            return Result::Err(ParseError::EmptyIsoDuration(input));
+        }
+
+        if is_negative {
+            total = -total;
+        }
+        Time::try_from(total).map_err(|_| ParseError::ExceededMaxTime(input))
+    }
+
+    /// Scans a number with an optional fraction, which may be separated by either `.` or `,`.
+    /// Returns the number with a `.` separator, the number of fractional digits and its location.
+    fn scan_number(&mut self) -> Result<(String, usize, Span), ParseError> {
+        let start = self.position();
+        let mut value = String::new();
+        let mut fraction_digits = Option::None;
+        while let Option::Some(c) = self.peek() {
+            match c {
+                '0'..='9' => {
+                    value.push(c);
+                    fraction_digits = fraction_digits.map(|digits| digits + 1);
+                }
+                '.' | ',' if fraction_digits.is_none() && !value.is_empty() => {
+                    value.push('.');
+                    fraction_digits = Option::Some(0);
+                }
+                _ => break,
+            }
+            self.next();
+        }
+
+        // Either there was no number, or there was no number after the separator.
+        if value.is_empty() || fraction_digits == Option::Some(0) {
+            let position = self.position();
+            let found = self.peek();
+            let span = Span::new(position, position + found.map_or(0, char::len_utf8));
+            return Result::Err(ParseError::ExpectedIsoNumber(found, span));
+        }
+
+        let span = Span::new(start, self.position());
+        Result::Ok((value, fraction_digits.unwrap_or(0), span))
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    fn position(&mut self) -> usize {
+        match self.chars.peek() {
+            Option::Some((i, _)) => *i,
+            Option::None => self.input_length,
+        }
     }
 }
 
 pub(crate) fn parse_time(time: &str) -> Result<Time, ParseError> {
-    Parser::new(Lexer::new(time).scan()?).parse()
+    if is_iso8601(time) {
+        return parse_iso8601(time);
+    }
+    Parser::new(Lexer::new(time).scan()?, time.len()).parse()
+}
+
+pub(crate) fn parse_iso8601(time: &str) -> Result<Time, ParseError> {
+    Iso8601Parser::new(time).parse()
+}
+
+/// Returns `true` if `time` looks like an ISO 8601 duration rather than a time such as `1:30:00`.
+pub(crate) fn is_iso8601(time: &str) -> bool {
+    time.strip_prefix('-').unwrap_or(time).starts_with('P')
 }
 
 #[cfg(test)]
@@ -279,7 +859,9 @@ mod tests {
     use super::Lexer;
     use super::Token;
     use super::Token::*;
+    use crate::span::Span;
     use crate::time::Time;
+    use crate::time::TimeUnit;
 
     #[test]
     fn scan_single_token() {
@@ -301,6 +883,14 @@ mod tests {
         assert_scan_tokens("13579", vec![Number(13579.to_string())]);
         assert_scan_tokens("24680", vec![Number(24680.to_string())]);
         assert_scan_tokens("123456789", vec![Number(123456789.to_string())]);
+        assert_scan_tokens("d", vec![Unit(TimeUnit::Days)]);
+        assert_scan_tokens("h", vec![Unit(TimeUnit::Hours)]);
+        assert_scan_tokens("m", vec![Unit(TimeUnit::Minutes)]);
+        assert_scan_tokens("s", vec![Unit(TimeUnit::Seconds)]);
+        assert_scan_tokens("ms", vec![Unit(TimeUnit::Milliseconds)]);
+        assert_scan_tokens("us", vec![Unit(TimeUnit::Microseconds)]);
+        assert_scan_tokens("µs", vec![Unit(TimeUnit::Microseconds)]);
+        assert_scan_tokens("ns", vec![Unit(TimeUnit::Nanoseconds)]);
     }
 
     #[test]
@@ -399,6 +989,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn scan_units() {
+        assert_scan_tokens(
+            "1h30m",
+            vec![
+                Number(1.to_string()), Unit(TimeUnit::Hours),
+                Number(30.to_string()), Unit(TimeUnit::Minutes),
+            ],
+        );
+        assert_scan_tokens(
+            "2d 3.5s  40ms",
+            vec![
+                Number(2.to_string()), Unit(TimeUnit::Days),
+                Number(3.to_string()), FullStop, Number(5.to_string()), Unit(TimeUnit::Seconds),
+                Number(40.to_string()), Unit(TimeUnit::Milliseconds),
+            ],
+        );
+        assert!(Lexer::new("1h ").scan().is_err());
+        assert!(Lexer::new("1h -").scan().is_err());
+        assert!(Lexer::new("1 h").scan().is_err());
+        assert!(Lexer::new("1min").scan().is_err());
+        assert!(Lexer::new("1H").scan().is_err());
+    }
+
     #[test]
     fn scan_unknown_tokens() {
         assert!(Lexer::new(" ").scan().is_err());
@@ -415,7 +1029,7 @@ mod tests {
         assert!(Lexer::new("j.").scan().is_err());
         assert!(Lexer::new("1-2-k").scan().is_err());
         assert!(Lexer::new("l-3-4").scan().is_err());
-        assert!(Lexer::new("5:6:m").scan().is_err());
+        assert!(Lexer::new("5:6:q").scan().is_err());
         assert!(Lexer::new("n:7:8").scan().is_err());
         assert!(Lexer::new("9.0.o").scan().is_err());
         assert!(Lexer::new("p.1.2").scan().is_err());
@@ -485,6 +1099,18 @@ mod tests {
         assert_parse_time("59:59", Time::builder().minutes(59).seconds(59).build());
         assert_parse_time("12:34.56789", Time::builder().minutes(12).seconds(34).nanoseconds(567890000).build());
 
+        // Days.
+        assert_parse_time("0:00:00:00", Time::builder().build());
+        assert_parse_time("1:00:00:00", Time::builder().hours(24).build());
+        assert_parse_time("2:03:04:05", Time::builder().hours(51).minutes(4).seconds(5).build());
+        assert_parse_time("2:23:59:59.5", Time::builder().hours(71).minutes(59).seconds(59).nanoseconds(500000000).build());
+        assert_parse_time("2d 03:04:05", Time::builder().hours(51).minutes(4).seconds(5).build());
+        assert_parse_time("2d03:04:05", Time::builder().hours(51).minutes(4).seconds(5).build());
+        assert_parse_time("5d  03:45:00.25", Time::builder().hours(123).minutes(45).nanoseconds(250000000).build());
+        assert_parse_time("-5d 03:45:00", Time::builder().negative().hours(123).minutes(45).build());
+        assert_parse_time("-2:03:04:05", Time::builder().negative().hours(51).minutes(4).seconds(5).build());
+        assert_parse_time("106751991167300d 15:30:07.999999999", Time::MAX);
+
         // Negative.
         assert_parse_time("-0.000000001s", Time::builder().negative().nanoseconds(1).build());
         assert_parse_time("-0.999999999s", Time::builder().negative().nanoseconds(999999999).build());
@@ -499,6 +1125,47 @@ mod tests {
         assert_parse_time("-11:22:33.456789", Time::builder().negative().hours(11).minutes(22).seconds(33).nanoseconds(456789000).build());
     }
 
+    #[test]
+    fn parse_time_units() {
+        assert_parse_time("0d", Time::builder().build());
+        assert_parse_time("1d", Time::builder().hours(24).build());
+        assert_parse_time("1h", Time::builder().hours(1).build());
+        assert_parse_time("1m", Time::builder().minutes(1).build());
+        assert_parse_time("1ms", Time::builder().nanoseconds(1000000).build());
+        assert_parse_time("1us", Time::builder().nanoseconds(1000).build());
+        assert_parse_time("1µs", Time::builder().nanoseconds(1000).build());
+        assert_parse_time("1ns", Time::builder().nanoseconds(1).build());
+
+        // Components are not limited to the size of the next largest unit.
+        assert_parse_time("90m", Time::builder().hours(1).minutes(30).build());
+        assert_parse_time("300s", Time::builder().minutes(5).build());
+        assert_parse_time("1500ms", Time::builder().seconds(1).nanoseconds(500000000).build());
+        assert_parse_time("1h90m", Time::builder().hours(2).minutes(30).build());
+
+        // Fractional components.
+        assert_parse_time("2.5h", Time::builder().hours(2).minutes(30).build());
+        assert_parse_time("1.5d", Time::builder().hours(36).build());
+        assert_parse_time("0.25m", Time::builder().seconds(15).build());
+        assert_parse_time("1.5ms", Time::builder().nanoseconds(1500000).build());
+        assert_parse_time("1.5ns", Time::builder().nanoseconds(2).build());
+        assert_parse_time("0.0000000000001h", Time::builder().build());
+
+        // Compound.
+        assert_parse_time("1h30m", Time::builder().hours(1).minutes(30).build());
+        assert_parse_time("1h2m3.5s", Time::builder().hours(1).minutes(2).seconds(3).nanoseconds(500000000).build());
+        assert_parse_time("1h 30m", Time::builder().hours(1).minutes(30).build());
+        assert_parse_time("2d 3h 4m 5s 6ms 7us 8ns", Time::builder().hours(51).minutes(4).seconds(5).nanoseconds(6007008).build());
+        assert_parse_time("1d1ns", Time::builder().hours(24).nanoseconds(1).build());
+
+        // Negative.
+        assert_parse_time("-1h30m", Time::builder().negative().hours(1).minutes(30).build());
+        assert_parse_time("-250ms", Time::builder().negative().nanoseconds(250000000).build());
+
+        // Limits.
+        assert_parse_time("2562047788015215h 30m 7.999999999s", Time::MAX);
+        assert_parse_time("-2562047788015215h 30m 7.999999999s", Time::MIN);
+    }
+
     #[test]
     fn parse_invalid_time() {
         // Missing components.
@@ -513,11 +1180,29 @@ mod tests {
         assert!(parse_time("-12::56").is_err());
 
         // Too many components.
-        assert!(parse_time("10:20:30:40").is_err());
+        assert!(parse_time("10:20:30:40:50").is_err());
         assert!(parse_time("10:20:30:40:50.60").is_err());
+        assert!(parse_time("1d 10:20:30:40").is_err());
+
+        // Invalid days.
+        assert!(parse_time("1:2:30:40").is_err());
+        assert!(parse_time("1:24:00:00").is_err());
+        assert!(parse_time("1d 24:00:00").is_err());
+        assert!(parse_time("1d 2:00:00").is_err());
+        assert!(parse_time("1d 20:00").is_err());
+        assert!(parse_time("1.5d 20:00:00").is_err());
+        assert!(parse_time("1d 20:00:00s").is_err());
+        assert!(parse_time("1h 20:00:00").is_err());
+        assert!(parse_time("106751991167301d 00:00:00").is_err());
+        assert!(parse_time("1000000000000000000000d 00:00:00").is_err());
 
         // Trailing decimal.
         assert!(parse_time("12:34:56.").is_err());
+        // Exceeds maximum.
+        assert!(parse_time("2562047788015215:30:08").is_err());
+        assert!(parse_time("-2562047788015216:00:00").is_err());
+        assert!(parse_time("123456789012345678901234567890:00:00").is_err());
+
         // Too many fractional second digits.
         assert!(parse_time("12:34:56.0123456789").is_err());
 
@@ -526,17 +1211,29 @@ mod tests {
         assert!(parse_time("00:00:4").is_err());
         assert!(parse_time("00:00:60").is_err());
         assert!(parse_time("00:00:99").is_err());
+        assert!(parse_time("123456789012345678901234567890s").is_err());
 
         // Invalid minutes.
         assert!(parse_time("00:8:00").is_err());
         assert!(parse_time("00:60:00").is_err());
         assert!(parse_time("00:99:00").is_err());
 
-        // Invalid seconds identifier.
+        // Invalid units.
         assert!(parse_time("1.2s4").is_err());
         assert!(parse_time("24:35s").is_err());
         assert!(parse_time("54:32.4s").is_err());
         assert!(parse_time("s4").is_err());
+        assert!(parse_time("1x").is_err());
+        assert!(parse_time("1hm").is_err());
+        assert!(parse_time("1h 2").is_err());
+        assert!(parse_time("1h ").is_err());
+        assert!(parse_time("1 h").is_err());
+        assert!(parse_time("1h1h").is_err());
+        assert!(parse_time("1s1m").is_err());
+        assert!(parse_time("1h-30m").is_err());
+        assert!(parse_time("1h:30m").is_err());
+        assert!(parse_time("106751991167301d").is_err());
+        assert!(parse_time("-2562047788015216h").is_err());
 
         // Invalid tokens.
         assert!(parse_time("00.00:00").is_err());
@@ -549,8 +1246,106 @@ mod tests {
         assert!(parse_time("--00:00:00").is_err());
     }
 
+    #[test]
+    fn parse_error_span() {
+        assert_error("1:2x:03", "unknown unit 'x'", 3, 4);
+        assert_error("1:2x:y3", "unknown unit 'x', unknown unit 'y'", 3, 6);
+        assert_error("1:!", "unexpected character '!'", 2, 3);
+        assert_error("1:٣", "unexpected character '٣'", 2, 4);
+        assert_error("", "expected a number, found end of input", 0, 0);
+        assert_error("12:", "expected a number, found end of input", 3, 3);
+        assert_error("12::56", "expected a number, found ':'", 3, 4);
+        assert_error("1:22:33:44:55:66", "too many components, expected at most days, hours, minutes and seconds", 11, 16);
+        assert_error("1d 22:33:44:55", "too many components, expected at most days, hours, minutes and seconds", 12, 14);
+        assert_error("1d 22:33", "expected hours, minutes and seconds after days", 3, 8);
+        assert_error("1:2:33:44", "expected two digit hours after days, found '2'", 2, 3);
+        assert_error("1d 24:33:44", "hours must be less than 24 after days, found 24", 3, 5);
+        assert_error("106751991167301d 00:00:00", "time exceeds the maximum time", 0, 25);
+        assert_error("1000000000000000000000d 00:00:00", "time exceeds the maximum time", 0, 22);
+        assert_error("12:34.", "expected a number after '.', found end of input", 6, 6);
+        assert_error("12", "expected a unit such as 's', found end of input", 2, 2);
+        assert_error("12.5-", "expected a unit such as 's', found '-'", 4, 5);
+        assert_error("1h 2", "expected a unit such as 's', found end of input", 4, 4);
+        assert_error("24:35s", "unexpected 's' after a time with minutes", 5, 6);
+        assert_error("1h30m2h", "units must be written from largest to smallest, found 'h'", 6, 7);
+        assert_error("1:2:03", "expected two digit minutes, found '2'", 2, 3);
+        assert_error("1:02:3", "expected two digit seconds, found '3'", 5, 6);
+        assert_error("-1:60:00", "minutes must be less than 60, found 60", 3, 5);
+        assert_error("00:75", "seconds must be less than 60, found 75", 3, 5);
+        assert_error("1d 2562047788015215h", "time exceeds the maximum time", 0, 20);
+        assert_error("2562047788015215:30:08", "time exceeds the maximum time", 0, 22);
+        assert_error("1.0123456789s", "fractional seconds must have at most 9 digits, found '0123456789'", 2, 12);
+        assert_error("1:00:00-", "expected end of time, found '-'", 7, 8);
+    }
+
+    #[test]
+    fn parse_iso8601_valid() {
+        assert_parse_iso8601("PT0S", Time::builder().build());
+        assert_parse_iso8601("P0D", Time::builder().build());
+        assert_parse_iso8601("PT1H", Time::builder().hours(1).build());
+        assert_parse_iso8601("PT1M", Time::builder().minutes(1).build());
+        assert_parse_iso8601("PT1S", Time::builder().seconds(1).build());
+        assert_parse_iso8601("P1D", Time::builder().hours(24).build());
+        assert_parse_iso8601("P1W", Time::builder().hours(168).build());
+        assert_parse_iso8601("PT1H30M", Time::builder().hours(1).minutes(30).build());
+        assert_parse_iso8601("P2DT3H", Time::builder().hours(51).build());
+        assert_parse_iso8601("P1DT2H3M4.5S", Time::builder().hours(26).minutes(3).seconds(4).nanoseconds(500000000).build());
+        assert_parse_iso8601("PT90M", Time::builder().hours(1).minutes(30).build());
+        assert_parse_iso8601("PT1.5H", Time::builder().hours(1).minutes(30).build());
+        assert_parse_iso8601("PT1,5S", Time::builder().seconds(1).nanoseconds(500000000).build());
+        assert_parse_iso8601("PT0.000000001S", Time::builder().nanoseconds(1).build());
+        assert_parse_iso8601("-PT1H", Time::builder().negative().hours(1).build());
+        assert_parse_iso8601("-P1DT0.5S", Time::builder().negative().hours(24).nanoseconds(500000000).build());
+        assert_parse_iso8601("PT2562047788015215H30M7.999999999S", Time::MAX);
+
+        // Times are parsed as ISO 8601 if they start with 'P'.
+        assert_parse_time("PT1H30M", Time::builder().hours(1).minutes(30).build());
+        assert_parse_time("-PT1S", Time::builder().negative().seconds(1).build());
+    }
+
+    #[test]
+    fn parse_iso8601_invalid() {
+        assert_iso8601_error("", "expected an ISO 8601 duration starting with 'P'", 0, 0);
+        assert_iso8601_error("1:00:00", "expected an ISO 8601 duration starting with 'P'", 0, 1);
+        assert_iso8601_error("pt1h", "expected an ISO 8601 duration starting with 'P'", 0, 1);
+        assert_iso8601_error("P", "expected at least one component in ISO 8601 duration", 0, 1);
+        assert_iso8601_error("PT", "expected a number, found end of input", 2, 2);
+        assert_iso8601_error("P1DT", "expected a number, found end of input", 4, 4);
+        assert_iso8601_error("PTH", "expected a number, found 'H'", 2, 3);
+        assert_iso8601_error("PT1.S", "expected a number, found 'S'", 4, 5);
+        assert_iso8601_error("PT1", "expected a designator such as 'H', 'M' or 'S' after a number", 3, 3);
+        assert_iso8601_error("P1Y", "unsupported component 'Y', durations with years or months do not have a fixed length", 2, 3);
+        assert_iso8601_error("P1M", "unsupported component 'M', durations with years or months do not have a fixed length", 2, 3);
+        assert_iso8601_error("P1Y2M3DT4H", "unsupported component 'Y', durations with years or months do not have a fixed length", 2, 3);
+        assert_iso8601_error("P1H", "unexpected designator 'H'", 2, 3);
+        assert_iso8601_error("PT1D", "unexpected designator 'D'", 3, 4);
+        assert_iso8601_error("PT1M1H", "unexpected designator 'H'", 5, 6);
+        assert_iso8601_error("PT1S1S", "unexpected designator 'S'", 5, 6);
+        assert_iso8601_error("PT1HT1M", "unexpected designator 'T'", 4, 5);
+        assert_iso8601_error("PT1X", "unexpected designator 'X'", 3, 4);
+        assert_iso8601_error("PT1.0123456789S", "fractional seconds must have at most 9 digits, found '0123456789'", 2, 14);
+        assert_iso8601_error("PT2562047788015216H", "time exceeds the maximum time", 0, 19);
+    }
+
+    fn assert_iso8601_error(input: &str, message: &str, start: usize, end: usize) {
+        let error = Time::parse_iso8601(input).unwrap_err();
+        assert_eq!(error.to_string(), message);
+        assert_eq!(error.span(), Span::new(start, end));
+    }
+
+    fn assert_parse_iso8601(time_str: &str, time: Time) {
+        assert_eq!(Time::parse_iso8601(time_str).unwrap(), time);
+    }
+
+    fn assert_error(input: &str, message: &str, start: usize, end: usize) {
+        let error = parse_time(input).unwrap_err();
+        assert_eq!(error.to_string(), message);
+        assert_eq!(error.span(), Span::new(start, end));
+    }
+
     fn assert_scan_tokens(input: &str, tokens: Vec<Token>) {
-        assert_eq!(Lexer::new(input).scan().unwrap(), tokens);
+        let scanned: Vec<Token> = Lexer::new(input).scan().unwrap().into_iter().map(|(t, _)| t).collect();
+        assert_eq!(scanned, tokens);
     }
 
     fn assert_parse_time(time_str: &str, time: Time) {